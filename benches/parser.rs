@@ -0,0 +1,87 @@
+//! Compares the incremental parser with the previous line-based parser.
+//!
+//! Run with `cargo bench --bench parser`.
+use std::{collections::HashMap, hint::black_box, io::{BufRead, BufReader}, time::Instant};
+
+use webserver::{parser::{Parser, Status}, ServerConfig};
+
+const ITERATIONS: u32 = 100_000;
+
+const REQUEST: &[u8] = b"POST /api/v1/items?page=2&limit=50 HTTP/1.1\r\n\
+Host: example.com\r\n\
+User-Agent: Mozilla/5.0 (X11; Linux x86_64; rv:120.0) Gecko/20100101 Firefox/120.0\r\n\
+Accept: text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8\r\n\
+Accept-Language: en-US,en;q=0.5\r\n\
+Accept-Encoding: gzip, deflate, br\r\n\
+Content-Type: application/json\r\n\
+Content-Length: 2\r\n\
+Cookie: session=0123456789abcdef0123456789abcdef; theme=dark\r\n\
+Connection: keep-alive\r\n\
+\r\n\
+{}";
+
+// the line-based parser this crate used before the incremental one:
+// one owned string per line, split and lowercased into a map
+fn legacy_parse(buf: &[u8]) -> (Vec<String>, HashMap<String, Vec<String>>) {
+  let mut reader = BufReader::new(buf);
+  let mut line = Vec::new();
+  let mut location = Vec::new();
+  let mut headers: HashMap<String, Vec<String>> = HashMap::new();
+
+  loop {
+    let res = reader.read_until(0xA, &mut line).unwrap();
+    if res == 0 || line == b"\r\n" {
+      break
+    }
+
+    let str = String::from_utf8(line.clone()).unwrap();
+    if location.is_empty() {
+      location = str.split(' ').map(|s| s.to_string()).collect();
+    } else if let Some((key, value)) = str.split_once(':') {
+      headers.entry(key.to_lowercase()).or_default().push(value.trim().to_lowercase());
+    }
+    line.clear();
+  }
+
+  (location, headers)
+}
+
+fn incremental_parse(buf: &[u8], chunk: usize) -> usize {
+  let mut parser = Parser::request(&ServerConfig::default());
+  let mut end = 0;
+  loop {
+    end = (end + chunk).min(buf.len());
+    if let Status::Complete(length) = parser.parse(&buf[..end]).unwrap() {
+      return length + parser.headers().len()
+    }
+  }
+}
+
+fn bench(name: &str, f: impl Fn()) {
+  for _ in 0..ITERATIONS / 10 {
+    f();
+  }
+
+  let start = Instant::now();
+  for _ in 0..ITERATIONS {
+    f();
+  }
+  let elapsed = start.elapsed();
+
+  println!("{name:<32} {:>10.1} ns/iter", elapsed.as_nanos() as f64 / ITERATIONS as f64);
+}
+
+fn main() {
+  bench("legacy line-based", || {
+    black_box(legacy_parse(black_box(REQUEST)));
+  });
+  bench("incremental, whole buffer", || {
+    black_box(incremental_parse(black_box(REQUEST), REQUEST.len()));
+  });
+  bench("incremental, 64 byte reads", || {
+    black_box(incremental_parse(black_box(REQUEST), 64));
+  });
+  bench("incremental, 1 byte reads", || {
+    black_box(incremental_parse(black_box(REQUEST), 1));
+  });
+}
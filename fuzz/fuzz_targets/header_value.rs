@@ -0,0 +1,10 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use webserver::fuzzing::{multi_values_field, single_value_field, value};
+
+fuzz_target!(|data: &str| {
+  let _ = value(data);
+  let _ = multi_values_field(data);
+  let _ = single_value_field(data);
+});
@@ -0,0 +1,18 @@
+#![no_main]
+
+use std::io::{Cursor, Read};
+
+use libfuzzer_sys::fuzz_target;
+use webserver::{fuzzing::parse_stream, ServerConfig};
+
+fuzz_target!(|data: &[u8]| {
+  for lenient_parsing in [false, true] {
+    let config = ServerConfig { lenient_parsing, ..Default::default() };
+    if let Ok(mut req) = parse_stream(Cursor::new(data.to_vec()), &config) {
+      // drives the body framing (content-length or chunked) as well
+      if let Some(body) = req.body().as_mut() {
+        let _ = body.read_to_end(&mut Vec::new());
+      }
+    }
+  }
+});
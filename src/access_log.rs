@@ -0,0 +1,287 @@
+//! Access logs, one line per response, in the Common Log Format, the
+//! Combined Log Format or as JSON lines.
+//!
+//! The middleware is registered first, so that its after hook runs last
+//! and logs the response as sent, error responses included. Requests that
+//! could not be parsed reach no handler and are not logged.
+//!
+//! ```no_run
+//! use webserver::{App, access_log::{AccessLog, Format}};
+//!
+//! let mut app = App::new();
+//! app.all("*", AccessLog::file("access.log").unwrap().with_format(Format::Combined).middleware());
+//! app.listen("127.0.0.1:8080").unwrap();
+//! ```
+//!
+//! Once the file has been rotated, [`reopen`] makes the log write to a new
+//! one, e.g. from a `SIGHUP` handler installed with the `signal-hook` crate.
+use std::{cell::RefCell, fmt::Write as _, fs::{File, OpenOptions}, io::{self, Write}, net::SocketAddr, path::{Path, PathBuf}, sync::atomic::{AtomicUsize, Ordering}, time::{Duration, Instant}};
+
+use crate::{header::typed::HttpDate, protocol, request::Request, request_id::RequestId, response::Response, router::Context, Handle, HookType, Return};
+
+#[cfg(test)]
+mod access_log_test;
+
+// bumped to make the logs writing to files reopen them
+static GENERATION: AtomicUsize = AtomicUsize::new(0);
+
+/// Makes every log written to a file reopen it before its next line,
+/// once the file has been moved away by a log rotation.
+pub fn reopen() {
+  GENERATION.fetch_add(1, Ordering::SeqCst);
+}
+
+/// The format of the log lines.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+  /// `127.0.0.1 - - [10/Oct/2000:13:55:36 +0000] "GET /index.html HTTP/1.1" 200 2326`
+  Common,
+  /// The Common Log Format followed by the quoted `Referer` and `User-Agent`.
+  Combined,
+  /// A JSON object per line, with the latency in milliseconds and
+  /// the [`RequestId`], if any.
+  Json
+}
+
+/// What is logged about a response, kept in the request extensions
+/// from the before hook to the after one.
+#[derive(Debug, Clone)]
+struct Entry {
+  remote_addr: Option<SocketAddr>,
+  time: HttpDate,
+  started: Instant,
+  method: &'static str,
+  target: String,
+  user_agent: Option<String>,
+  referer: Option<String>,
+  request_id: Option<String>,
+  status: u16,
+  size: u64,
+  latency: Duration
+}
+
+impl Entry {
+  /// Starts timing the answer to `req`.
+  fn new(req: &Request) -> Entry {
+    let location = req.location();
+    Entry {
+      remote_addr: req.remote_addr(),
+      time: HttpDate::now(),
+      started: Instant::now(),
+      method: location.0.as_str(),
+      target: location.1.clone(),
+      user_agent: req.headers().get("user-agent").cloned(),
+      referer: req.headers().get("referer").cloned(),
+      request_id: None,
+      status: 0,
+      size: 0,
+      latency: Duration::ZERO
+    }
+  }
+
+  /// Completes the entry with the response sent to `req`.
+  fn finish(&mut self, req: &Request, res: &Response) {
+    self.request_id = req.extensions().get::<RequestId>().map(|id| id.to_string());
+    self.status = res.status.to_u16();
+    self.size = size(res);
+    self.latency = self.started.elapsed();
+  }
+}
+
+// the size of the body, unknown for chunked bodies
+fn size(res: &Response) -> u64 {
+  match res.body() {
+    Some(body) => body.len() as u64,
+    None => res.headers
+      .get("Content-Length")
+      .and_then(|length| length.parse().ok())
+      .unwrap_or(0)
+  }
+}
+
+enum Sink {
+  Writer(Box<dyn Write>),
+  File {
+    path: PathBuf,
+    file: File,
+    generation: usize
+  }
+}
+
+/// The access log middleware, see the [module documentation](self).
+///
+/// Failing to write a line does not fail the response, the line is lost.
+pub struct AccessLog {
+  sink: Sink,
+  format: Format
+}
+
+fn open(path: &Path) -> io::Result<File> {
+  OpenOptions::new().create(true).append(true).open(path)
+}
+
+// quotes a field value the way Apache does, escaping what
+// could be mistaken for the end of the field or a new line
+fn quoted(value: Option<&str>) -> String {
+  let Some(value) = value else {
+    return "\"-\"".to_string()
+  };
+
+  let mut quoted = String::with_capacity(value.len() + 2);
+  quoted.push('"');
+  for c in value.chars() {
+    match c {
+      '"' | '\\' => {
+        quoted.push('\\');
+        quoted.push(c);
+      },
+      c if c.is_ascii_control() => {
+        let _ = write!(quoted, "\\x{:02x}", c as u32);
+      },
+      c => quoted.push(c)
+    }
+  }
+  quoted.push('"');
+  quoted
+}
+
+fn json_string(value: Option<&str>) -> String {
+  let Some(value) = value else {
+    return "null".to_string()
+  };
+
+  let mut json = String::with_capacity(value.len() + 2);
+  json.push('"');
+  for c in value.chars() {
+    match c {
+      '"' => json.push_str("\\\""),
+      '\\' => json.push_str("\\\\"),
+      '\n' => json.push_str("\\n"),
+      '\r' => json.push_str("\\r"),
+      '\t' => json.push_str("\\t"),
+      c if (c as u32) < 0x20 || c == '\u{7f}' => {
+        let _ = write!(json, "\\u{:04x}", c as u32);
+      },
+      c => json.push(c)
+    }
+  }
+  json.push('"');
+  json
+}
+
+impl AccessLog {
+  /// Logs to `writer` in the Common Log Format.
+  pub fn new(writer: impl Write + 'static) -> AccessLog {
+    AccessLog {
+      sink: Sink::Writer(Box::new(writer)),
+      format: Format::Common
+    }
+  }
+
+  /// Appends to the file at `path`, created if it does not exist.
+  /// It is reopened after [`reopen`] is called.
+  pub fn file(path: impl Into<PathBuf>) -> io::Result<AccessLog> {
+    let path = path.into();
+    let file = open(&path)?;
+    Ok(AccessLog {
+      sink: Sink::File { path, file, generation: GENERATION.load(Ordering::SeqCst) },
+      format: Format::Common
+    })
+  }
+
+  pub fn with_format(mut self, format: Format) -> AccessLog {
+    self.format = format;
+    self
+  }
+
+  pub fn middleware(self) -> Handle {
+    let log = RefCell::new(self);
+    Handle::middleware(move |ctx: &mut Context| {
+      match ctx.hook() {
+        HookType::Before => {
+          let entry = Entry::new(ctx.req);
+          ctx.req.extensions_mut().insert(entry);
+        },
+        _ => {
+          // nothing is written when no handler sent a response
+          if let Some(mut entry) = ctx.req.extensions_mut().remove::<Entry>().filter(|_| ctx.res.is_sent()) {
+            entry.finish(ctx.req, ctx.res);
+            log.borrow_mut().log(&entry);
+          }
+        }
+      }
+      Ok(Return::Next)
+    })
+  }
+
+  fn log(&mut self, entry: &Entry) {
+    let line = self.line(entry);
+    let _ = self.write(line.as_bytes());
+  }
+
+  fn write(&mut self, line: &[u8]) -> io::Result<()> {
+    match &mut self.sink {
+      Sink::Writer(writer) => {
+        writer.write_all(line)?;
+        writer.flush()
+      },
+      Sink::File { path, file, generation } => {
+        // the previous file is kept if the new one cannot be opened
+        let current = GENERATION.load(Ordering::SeqCst);
+        if *generation != current {
+          *file = open(path)?;
+          *generation = current;
+        }
+        file.write_all(line)
+      }
+    }
+  }
+
+  fn line(&self, entry: &Entry) -> String {
+    let host = entry.remote_addr.map(|addr| addr.ip().to_string());
+    let request = format!("{} {} {}", entry.method, entry.target, protocol::HTTP_PROTOCOL);
+
+    match self.format {
+      Format::Common | Format::Combined => {
+        let size = match entry.size {
+          0 => "-".to_string(),
+          size => size.to_string()
+        };
+        let mut line = format!(
+          "{} - - [{}] {} {} {size}",
+          host.as_deref().unwrap_or("-"),
+          entry.time.to_common_log(),
+          quoted(Some(&request)),
+          entry.status
+        );
+
+        if self.format == Format::Combined {
+          let _ = write!(line, " {} {}", quoted(entry.referer.as_deref()), quoted(entry.user_agent.as_deref()));
+        }
+        line.push('\n');
+        line
+      },
+      Format::Json => {
+        let (path, query) = match entry.target.split_once('?') {
+          Some((path, query)) => (path, Some(query)),
+          None => (entry.target.as_str(), None)
+        };
+
+        format!(
+          "{{\"time\":\"{}\",\"remote_addr\":{},\"method\":{},\"path\":{},\"query\":{},\"status\":{},\"size\":{},\"latency_ms\":{:.3},\"referer\":{},\"user_agent\":{},\"request_id\":{}}}\n",
+          entry.time.to_rfc3339(),
+          json_string(host.as_deref()),
+          json_string(Some(entry.method)),
+          json_string(Some(path)),
+          json_string(query),
+          entry.status,
+          entry.size,
+          entry.latency.as_secs_f64() * 1000.0,
+          json_string(entry.referer.as_deref()),
+          json_string(entry.user_agent.as_deref()),
+          json_string(entry.request_id.as_deref())
+        )
+      }
+    }
+  }
+}
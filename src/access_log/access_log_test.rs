@@ -0,0 +1,151 @@
+use std::{cell::RefCell, fs, io::Cursor, rc::Rc};
+
+use crate::{protocol::StatusCode, request_id::RequestIds, App, Handle, random};
+use super::*;
+
+// a sink the test can read back
+#[derive(Clone, Default)]
+struct Shared(Rc<RefCell<Vec<u8>>>);
+
+impl Write for Shared {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    self.0.borrow_mut().write(buf)
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    Ok(())
+  }
+}
+
+impl Shared {
+  fn lines(&self) -> Vec<String> {
+    String::from_utf8(self.0.borrow().clone()).unwrap().lines().map(String::from).collect()
+  }
+}
+
+fn app(log: AccessLog) -> App {
+  let mut app = App::new();
+  app.all("*", log.middleware());
+  app.get("/hello", Handle::main(|ctx| ctx.res.send_body(b"hello".to_vec())));
+  app.get("/fail", Handle::main(|_| Err("failed".into())));
+  app.get("/empty", Handle::main(|ctx| ctx.res.status(StatusCode::NoContent).send_headers()));
+  app
+}
+
+fn send(app: &App, raw: &str) {
+  let remote_addr = "192.0.2.1:4711".parse().ok();
+  app.respond_from(remote_addr, Cursor::new(raw.as_bytes().to_vec()), io::sink());
+}
+
+// the line without the date, which changes
+fn undated(line: &str) -> String {
+  let start = line.find('[').unwrap();
+  let end = line.find(']').unwrap();
+  format!("{}[]{}", &line[..start], &line[end + 1..])
+}
+
+mod format_test {
+  use super::*;
+
+  #[test]
+  fn common_test() {
+    let sink = Shared::default();
+    let app = app(AccessLog::new(sink.clone()));
+    send(&app, "GET /hello?name=a HTTP/1.1\r\nHost: a\r\nUser-Agent: curl\r\n\r\n");
+    send(&app, "GET /empty HTTP/1.1\r\nHost: a\r\n\r\n");
+
+    let lines = sink.lines();
+    assert_eq!(undated(&lines[0]), "192.0.2.1 - - [] \"GET /hello?name=a HTTP/1.1\" 200 5");
+    assert_eq!(undated(&lines[1]), "192.0.2.1 - - [] \"GET /empty HTTP/1.1\" 204 -");
+
+    let date = &lines[0][lines[0].find('[').unwrap() + 1..lines[0].find(']').unwrap()];
+    assert_eq!(date.len(), "10/Oct/2000:13:55:36 +0000".len());
+    assert!(date.ends_with(" +0000"));
+  }
+
+  #[test]
+  fn combined_test() {
+    let sink = Shared::default();
+    let app = app(AccessLog::new(sink.clone()).with_format(Format::Combined));
+    send(&app, "GET /hello HTTP/1.1\r\nHost: a\r\nReferer: https://example.com/\r\nUser-Agent: Mozilla \"5.0\"\r\n\r\n");
+    send(&app, "GET /hello HTTP/1.1\r\nHost: a\r\n\r\n");
+
+    let lines = sink.lines();
+    assert_eq!(
+      undated(&lines[0]),
+      "192.0.2.1 - - [] \"GET /hello HTTP/1.1\" 200 5 \"https://example.com/\" \"Mozilla \\\"5.0\\\"\""
+    );
+    assert_eq!(undated(&lines[1]), "192.0.2.1 - - [] \"GET /hello HTTP/1.1\" 200 5 \"-\" \"-\"");
+  }
+
+  #[test]
+  fn json_test() {
+    let sink = Shared::default();
+    let app = app(AccessLog::new(sink.clone()).with_format(Format::Json));
+    send(&app, "GET /hello?q=1 HTTP/1.1\r\nHost: a\r\nUser-Agent: curl\\8\r\n\r\n");
+
+    let line = &sink.lines()[0];
+    assert!(line.starts_with("{\"time\":\""));
+    let time = &line[9..29];
+    assert!(time.ends_with('Z') && time.as_bytes()[10] == b'T', "{time}");
+    assert!(line.contains(
+      "\"remote_addr\":\"192.0.2.1\",\"method\":\"GET\",\"path\":\"/hello\",\"query\":\"q=1\",\"status\":200,\"size\":5,\"latency_ms\":"
+    ));
+    assert!(line.ends_with(",\"referer\":null,\"user_agent\":\"curl\\\\8\",\"request_id\":null}"));
+  }
+
+  #[test]
+  fn escape_test() {
+    assert_eq!(quoted(Some("a\"b\\c\u{1b}")), "\"a\\\"b\\\\c\\x1b\"");
+    assert_eq!(quoted(None), "\"-\"");
+    assert_eq!(json_string(Some("a\"\n\u{1}é")), "\"a\\\"\\n\\u0001é\"");
+  }
+}
+
+mod exchange_test {
+  use super::*;
+
+  #[test]
+  fn errors_test() {
+    let sink = Shared::default();
+    let app = app(AccessLog::new(sink.clone()));
+    send(&app, "GET /fail HTTP/1.1\r\nHost: a\r\n\r\n");
+    send(&app, "BREW /pot HTTP/1.1\r\nHost: a\r\n\r\n");
+
+    // the request that could not be parsed reached no handler
+    let lines = sink.lines();
+    assert_eq!(lines.len(), 1);
+    assert_eq!(undated(&lines[0]), "192.0.2.1 - - [] \"GET /fail HTTP/1.1\" 500 6");
+  }
+
+  #[test]
+  fn request_id_test() {
+    let sink = Shared::default();
+    let mut app = app(AccessLog::new(sink.clone()).with_format(Format::Json));
+    app.all("*", RequestIds::new().middleware());
+    send(&app, "GET /fail HTTP/1.1\r\nHost: a\r\nX-Request-Id: abc\r\n\r\n");
+
+    assert!(sink.lines()[0].ends_with(",\"request_id\":\"abc\"}"));
+  }
+
+  #[test]
+  fn reopen_test() {
+    let dir = std::env::temp_dir().join(format!("webserver-log-{}", random::hex(8)));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("access.log");
+    let app = app(AccessLog::file(&path).unwrap());
+
+    send(&app, "GET /hello HTTP/1.1\r\nHost: a\r\n\r\n");
+    fs::rename(&path, dir.join("access.log.1")).unwrap();
+    // still written to the rotated file until reopened
+    send(&app, "GET /hello HTTP/1.1\r\nHost: a\r\n\r\n");
+    reopen();
+    send(&app, "GET /empty HTTP/1.1\r\nHost: a\r\n\r\n");
+
+    assert_eq!(fs::read_to_string(dir.join("access.log.1")).unwrap().lines().count(), 2);
+    let reopened = fs::read_to_string(&path).unwrap();
+    assert_eq!(reopened.lines().count(), 1);
+    assert!(reopened.contains("\"GET /empty HTTP/1.1\" 204"));
+    fs::remove_dir_all(dir).unwrap();
+  }
+}
@@ -1,9 +1,31 @@
-use std::{error::Error, net::TcpListener};
+use std::{error::Error, io::{BufWriter, Read, Write}, net::{SocketAddr, TcpListener}, time::Instant};
+#[cfg(feature = "secure-cookies")]
+use std::rc::Rc;
 
-use crate::{protocol::StatusCode, request::Method, router::{HandleType, Handler, HookType, Router}, stream, Handle};
+use tracing::{debug, error, field, info, info_span};
 
+use crate::{config::ServerConfig, error::HttpError, header::{self, Headers}, metrics::{Counted, Metrics}, protocol::{InvalidReasonPhrase, InvalidStatusCode, StatusCode}, random, request::{Method, Request}, request_id::{self, RequestId}, response::Response, router::{HandleType, Handler, HookType, Router}, stream::{self, error::Error as ParseError}, Handle};
+
+#[cfg(feature = "secure-cookies")]
+use crate::cookie::{Jar, Key};
+
+#[cfg(test)]
+pub(crate) mod app_test;
+
+/// An application, the routes and handlers a server answers with.
+///
+/// Events are emitted through the `tracing` facade, each exchange in a
+/// `request` span with a random `id`, and the `request_id` assigned by
+/// [`RequestIds`](crate::request_id::RequestIds) if any. Request handling is logged at the
+/// `DEBUG` level, under the `webserver::app` target, and routing and
+/// parsing diagnostics at the `TRACE` level, under `webserver::router`
+/// and `webserver::stream`, so that a subscriber can enable them at runtime.
 pub struct App {
-  router: Router
+  router: Router,
+  config: ServerConfig,
+  metrics: Option<Metrics>,
+  #[cfg(feature = "secure-cookies")]
+  jar: Option<Rc<Jar>>
 }
 
 macro_rules! unwrap_or_continue {
@@ -17,13 +39,45 @@ macro_rules! unwrap_or_continue {
     };
 }
 
+impl Default for App {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
 impl App {
   pub fn new() -> App {
+    App::with_config(ServerConfig::default())
+  }
+
+  pub fn with_config(config: ServerConfig) -> App {
     App{
-      router: Router::new()
+      router: Router::new(),
+      config,
+      metrics: None,
+      #[cfg(feature = "secure-cookies")]
+      jar: None
     }
   }
 
+  pub fn config(&self) -> &ServerConfig {
+    &self.config
+  }
+
+  /// Sets the key signed and private cookies are protected with.
+  #[cfg(feature = "secure-cookies")]
+  pub fn cookie_key(&mut self, key: Key) -> &Self {
+    self.jar = Some(Rc::new(Jar::new(key)));
+    self
+  }
+
+  /// Records the [`Metrics`] of every exchange and exposes them on `path`.
+  pub fn metrics(&mut self, path: &str, metrics: Metrics) -> &Self {
+    self.get(path, metrics.handle());
+    self.metrics = Some(metrics);
+    self
+  }
+
   pub fn all(&mut self, path: &str, handle: Handle) -> &Self {
     self.register_handle(Method::All, path, handle)
   }
@@ -36,6 +90,10 @@ impl App {
     self.register_handle(Method::Post, path, handle)
   }
 
+  pub fn options(&mut self, path: &str, handle: Handle) -> &Self {
+    self.register_handle(Method::Options, path, handle)
+  }
+
   fn register_handle(&mut self, method: Method, path: &str, handle: Handle) -> &Self {
     let (hook_type, function) = Router::handler(handle);
     let mut handler = Handler{
@@ -70,39 +128,160 @@ impl App {
     self
   }
 
+  // shares the app state handlers reach through the request and response
+  #[allow(unused_variables)]
+  fn prepare(&self, req: &mut Request, res: &mut Response) {
+    #[cfg(feature = "secure-cookies")]
+    {
+      req.set_jar(self.jar.clone());
+      res.set_jar(self.jar.clone());
+    }
+  }
+
+  // answers with the status of the error, or 500 if it has none
+  fn fail(req: &Request, res: &mut Response, err: Box<dyn Error>) {
+    // the fields that failed validation would fail again
+    if err.is::<header::Error>() {
+      res.headers = Headers::new();
+    }
+
+    let request_id = req.extensions().get::<RequestId>();
+    if let Some(id) = request_id {
+      res.headers.set(request_id::HEADER, id.to_string());
+    }
+
+    let status = err
+      .downcast_ref::<HttpError>()
+      .map(|err| err.status().clone())
+      .unwrap_or(StatusCode::InternalServerError);
+
+    match status.is_server_error() {
+      true => error!(status = status.to_u16(), error = %err, "Request failed"),
+      false => debug!(status = status.to_u16(), error = %err, "Request failed")
+    }
+
+    // so that the client can report the error with its ID
+    let mut body = err.to_string();
+    if let Some(id) = request_id {
+      body.push_str(&format!("\nRequest ID: {id}"));
+    }
+
+    let _ = res
+      .content_type("text/plain")
+      .status(status)
+      .send_body(body.into());
+  }
+
+  #[cfg(test)]
+  pub(crate) fn respond<R: Read + 'static>(&self, reader: R, writer: impl Write) {
+    self.respond_from(None, reader, writer)
+  }
+
+  // answers a single request read from `reader`
+  pub(crate) fn respond_from<R: Read + 'static>(&self, remote_addr: Option<SocketAddr>, reader: R, writer: impl Write) {
+    // every event of the exchange is recorded within this span
+    let span = info_span!("request", id = %random::hex(8), request_id = field::Empty, remote_addr = field::Empty);
+    if let Some(remote_addr) = remote_addr {
+      span.record("remote_addr", field::display(remote_addr));
+    }
+    let _span = span.enter();
+
+    // each connection carries a single exchange
+    let (reader, received) = Counted::new(reader);
+    let (writer, sent) = Counted::new(writer);
+    if let Some(metrics) = &self.metrics {
+      metrics.connection_opened();
+    }
+    self.exchange(remote_addr, reader, writer);
+    if let Some(metrics) = &self.metrics {
+      metrics.connection_closed(received.get(), sent.get());
+    }
+  }
+
+  fn exchange<R: Read + 'static>(&self, remote_addr: Option<SocketAddr>, reader: R, writer: impl Write) {
+    let started = Instant::now();
+    let mut writer = BufWriter::new(writer);
+    let mut req = match stream::parse_stream(reader, &self.config) {
+      Ok(req) => req,
+      Err(err) => {
+        debug!(error = %err, "Invalid request");
+        if let Some(metrics) = &self.metrics {
+          // `Other` for invalid UTF-8 in the head or a failed read
+          metrics.parse_failed(err.downcast_ref::<ParseError>().map_or("Other", |err| err.name()));
+        }
+        let status = err
+          .downcast_ref::<ParseError>()
+          .map(|err| err.status())
+          .unwrap_or(StatusCode::BadRequest);
+
+        let mut res = Response::new();
+        let _ = res
+          .content_type("text/plain")
+          .status(status)
+          .send_body(err.to_string().into());
+        let _ = res.write_to(&mut writer);
+        return
+      }
+    };
+    req.set_remote_addr(remote_addr);
+    let method = req.location().0.as_str();
+    debug!(method, target = req.location().1, "Request received");
+    if let Some(metrics) = &self.metrics {
+      metrics.request_started();
+    }
+
+    let mut res = Response::new();
+    self.prepare(&mut req, &mut res);
+
+    let route = self.router.dispatch(&mut req, &mut res, App::fail);
+
+    if !res.is_sent() {
+      debug!("No handler sent a response");
+      if let Some(metrics) = &self.metrics {
+        metrics.request_finished(method, route.as_deref().unwrap_or_default(), None, started.elapsed());
+      }
+      return
+    }
+
+    let size = match res.write_to(&mut writer) {
+      Ok(size) => size,
+      // nothing is written when the status line or the fields fail validation
+      Err(err) if err.is::<header::Error>() || err.is::<InvalidStatusCode>() || err.is::<InvalidReasonPhrase>() => {
+        App::fail(&req, &mut res, err);
+        res.write_to(&mut writer).unwrap_or(0)
+      },
+      Err(err) => {
+        debug!(error = %err, "Response could not be written");
+        0
+      }
+    };
+    debug!(status = res.status.to_u16(), size, "Response sent");
+    if let Some(metrics) = &self.metrics {
+      metrics.request_finished(method, route.as_deref().unwrap_or_default(), Some(res.status.to_u16()), started.elapsed());
+    }
+  }
+
   pub fn listen(&self, address: &str) -> Result<(), Box<dyn Error>> {
     let listener = TcpListener::bind(address)?;
+    info!(address, "Listening");
 
     for incoming in listener.incoming() {
       let stream = match incoming {
         Ok(stream) => stream,
-        Err(_) => {
+        Err(err) => {
+          debug!(error = %err, "Connection failed");
           continue;
         }
       };
       let req_stream = unwrap_or_continue!(stream.try_clone());
       let res_stream = unwrap_or_continue!(stream.try_clone());
 
-      let req = match stream::parse_stream(req_stream) {
-        Ok(req) => req,
-        Err(err) => {
-          println!("Error parsing stream: {err:?}");
-          continue;
-        }
-      };
-
-      let (mut res, error) = self.router.dispatch(req, res_stream);
-      if let Some(err) = error {
-        let _ = res
-          .content_type("text/plain")
-          .status(StatusCode::InternalServerError)
-          .send_body(err.to_string().into());
-      }
+      self.respond_from(stream.peer_addr().ok(), req_stream, res_stream);
 
       let _ = stream.shutdown(std::net::Shutdown::Both);
       continue
     }
 
-    return Ok(())
+    Ok(())
   }
-}
\ No newline at end of file
+}
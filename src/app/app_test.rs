@@ -0,0 +1,281 @@
+use std::io::Cursor;
+
+use crate::{error::HttpError, protocol::StatusCode, Context, Handle, HookType, Return};
+use super::*;
+
+// the helpers below are shared with the tests of the middlewares
+
+/// The response of `app` to the `raw` request, as sent by `remote_addr`.
+pub(crate) fn send_from(app: &App, remote_addr: Option<SocketAddr>, raw: &str) -> String {
+  let mut output = Vec::new();
+  app.respond_from(remote_addr, Cursor::new(raw.as_bytes().to_vec()), &mut output);
+  String::from_utf8(output).unwrap()
+}
+
+pub(crate) fn send(app: &App, raw: &str) -> String {
+  send_from(app, None, raw)
+}
+
+/// `headers` are the field lines, each ending with CRLF.
+pub(crate) fn request(app: &App, method: &str, path: &str, headers: &str) -> String {
+  send(app, &format!("{method} {path} HTTP/1.1\r\nHost: localhost\r\n{headers}\r\n"))
+}
+
+pub(crate) fn get(app: &App, path: &str, headers: &str) -> String {
+  request(app, "GET", path, headers)
+}
+
+/// The value of the first field named `name` in the head of `response`.
+pub(crate) fn header<'a>(response: &'a str, name: &str) -> Option<&'a str> {
+  let head = response.split("\r\n\r\n").next().unwrap();
+  head
+    .lines()
+    .filter_map(|line| line.split_once(": "))
+    .find(|(key, _)| key.eq_ignore_ascii_case(name))
+    .map(|(_, value)| value)
+}
+
+pub(crate) fn body(response: &str) -> &str {
+  response.split_once("\r\n\r\n").unwrap().1
+}
+
+#[test]
+fn response_test() {
+  let mut app = App::new();
+  app.get("/", Handle::main(|ctx| {
+    ctx.res.content_type("text/plain").send_body(b"hello".to_vec())
+  }));
+
+  let response = get(&app, "/", "");
+  assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+  assert!(response.contains("\r\nContent-Length: 5\r\n"));
+  assert!(response.contains("\r\nContent-Type: text/plain\r\n"));
+  assert!(response.ends_with("\r\n\r\nhello"));
+}
+
+#[test]
+fn query_string_test() {
+  let mut app = App::new();
+  app.get("/search", Handle::main(|ctx| {
+    let q = ctx.req.query().get("q").unwrap_or_default().to_string();
+    ctx.res.send_body(q.into_bytes())
+  }));
+
+  assert!(get(&app, "/search?q=a+b", "").ends_with("\r\n\r\na b"));
+}
+
+#[test]
+fn stream_test() {
+  let mut app = App::new();
+  app.get("/sized", Handle::main(|ctx| ctx.res.send_stream(Cursor::new(b"hello world".to_vec()), Some(5))));
+  app.get("/chunked", Handle::main(|ctx| ctx.res.send_stream(Cursor::new(b"hello".to_vec()), None)));
+
+  let response = get(&app, "/sized", "");
+  assert!(response.contains("\r\nContent-Length: 5\r\n"));
+  assert!(response.ends_with("\r\n\r\nhello"));
+
+  let response = get(&app, "/chunked", "");
+  assert!(response.contains("\r\nTransfer-Encoding: chunked\r\n"));
+  assert!(!response.contains("Content-Length"));
+  assert!(response.ends_with("\r\n\r\n5\r\nhello\r\n0\r\n\r\n"));
+}
+
+#[test]
+fn bodiless_test() {
+  let mut app = App::new();
+  app.get("/registered", Handle::main(|ctx| ctx.res.status(StatusCode::NoContent).send_headers()));
+  app.get("/other", Handle::main(|ctx| ctx.res.status(StatusCode::Other(304, String::new())).send_headers()));
+
+  for path in ["/registered", "/other"] {
+    let response = get(&app, path, "");
+    assert!(!response.contains("Content-Length"), "for {path}");
+  }
+}
+
+#[test]
+fn parse_error_test() {
+  let app = App::new();
+  let response = send(&app, "GET / HTTP/1.1\r\n\r\n");
+  assert!(response.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+}
+
+#[test]
+fn handler_error_test() {
+  let mut app = App::new();
+  app.get("/missing", Handle::main(|_| Err(Box::new(HttpError::new(StatusCode::NotFound, "No such page")))));
+  app.get("/broken", Handle::main(|_| Err("broken".into())));
+
+  let response = get(&app, "/missing", "");
+  assert!(response.starts_with("HTTP/1.1 404 Not Found\r\n"));
+  assert!(response.ends_with("No such page"));
+
+  let response = get(&app, "/broken", "");
+  assert!(response.starts_with("HTTP/1.1 500 Internal Server Error\r\n"));
+}
+
+#[test]
+fn after_hook_modifies_response_test() {
+  let mut app = App::new();
+  app.get("/", Handle::main(|ctx| ctx.res.send_body(b"body".to_vec())));
+  app.get("/", Handle::after(|ctx| {
+    ctx.res.headers.set("X-After", "1".to_string());
+    Ok(Return::Next)
+  }));
+
+  assert!(get(&app, "/", "").contains("\r\nX-After: 1\r\n"));
+}
+
+#[test]
+fn middleware_test() {
+  let mut app = App::new();
+  app.all("*", Handle::middleware(|ctx| {
+    match ctx.hook() {
+      HookType::Before => { ctx.req.extensions_mut().insert(7u32); },
+      _ => {
+        let value = ctx.req.extensions().get::<u32>().copied().unwrap_or_default();
+        ctx.res.headers.set("X-Value", value.to_string());
+      }
+    }
+    Ok(Return::Next)
+  }));
+  app.get("/", Handle::main(|ctx| ctx.res.send_headers()));
+
+  let response = get(&app, "/", "");
+  assert!(response.contains("\r\nX-Value: 7\r\n"));
+  assert!(response.contains("\r\nContent-Length: 0\r\n"));
+}
+
+#[test]
+fn after_hook_on_error_test() {
+  let mut app = App::new();
+  app.all("*", Handle::after(|ctx| {
+    ctx.res.headers.set("X-After", ctx.res.status.to_string());
+    Ok(Return::Next)
+  }));
+  app.get("/missing", Handle::main(|_| Err(Box::new(HttpError::new(StatusCode::NotFound, "No such page")))));
+  app.get("/hook", Handle::before(|_| Err("broken".into())));
+  app.get("/hook", Handle::main(|ctx| ctx.res.send_body(b"unreachable".to_vec())));
+
+  let response = get(&app, "/missing", "");
+  assert!(response.starts_with("HTTP/1.1 404 Not Found\r\n"));
+  assert!(response.contains("\r\nX-After: 404\r\n"));
+
+  let response = get(&app, "/hook", "");
+  assert!(response.starts_with("HTTP/1.1 500 Internal Server Error\r\n"));
+  assert!(response.contains("\r\nX-After: 500\r\n"));
+}
+
+#[test]
+fn middleware_order_test() {
+  // appends the name of the hook to `X-Order`
+  fn record(name: &'static str) -> impl Fn(&mut Context) -> Result<Return, Box<dyn Error>> {
+    move |ctx| {
+      let order = ctx.res.headers.get("X-Order").cloned().unwrap_or_default();
+      let hook = match ctx.hook() {
+        HookType::Before => "before",
+        _ => "after"
+      };
+      ctx.res.headers.set("X-Order", format!("{order}{name}-{hook},"));
+      Ok(Return::Next)
+    }
+  }
+
+  let mut app = App::new();
+  app.all("*", Handle::middleware(record("outer")));
+  app.get("/", Handle::middleware(record("inner")));
+  app.get("/", Handle::after(record("hook")));
+  app.get("/", Handle::main(|ctx| ctx.res.send_headers()));
+
+  let response = get(&app, "/", "");
+  assert!(response.contains("\r\nX-Order: outer-before,inner-before,hook-after,inner-after,outer-after,\r\n"), "{response}");
+}
+
+#[test]
+fn header_injection_test() {
+  let mut app = App::new();
+  app.get("/", Handle::main(|ctx| {
+    ctx.res.headers.set("Location", "/\r\nSet-Cookie: a=b".to_string());
+    ctx.res.send_headers()
+  }));
+
+  let response = get(&app, "/", "");
+  assert!(response.starts_with("HTTP/1.1 500 Internal Server Error\r\n"));
+  assert!(!response.contains("Set-Cookie"));
+}
+
+#[test]
+fn status_line_injection_test() {
+  let mut app = App::new();
+  app.get("/reason", Handle::main(|ctx| {
+    ctx.res.status(StatusCode::Other(200, "OK\r\nSet-Cookie: a=b".to_string())).send_headers()
+  }));
+  app.get("/code", Handle::main(|ctx| ctx.res.status(StatusCode::Other(700, String::new())).send_headers()));
+
+  let response = get(&app, "/reason", "");
+  assert!(response.starts_with("HTTP/1.1 500 Internal Server Error\r\n"));
+  assert_eq!(header(&response, "Set-Cookie"), None);
+
+  let response = get(&app, "/code", "");
+  assert!(response.starts_with("HTTP/1.1 500 Internal Server Error\r\n"));
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn json_test() {
+  let mut app = App::new();
+  app.post("/", Handle::main(|ctx| {
+    let values: Vec<u32> = ctx.req.json()?;
+    ctx.res.json(&values.iter().sum::<u32>())
+  }));
+
+  let response = send(&app, "POST / HTTP/1.1\r\nHost: a\r\nContent-Type: application/json\r\nContent-Length: 7\r\n\r\n[1,2,3]");
+  assert!(response.contains("\r\nContent-Type: application/json\r\n"));
+  assert!(response.ends_with("\r\n\r\n6"));
+
+  // errors get the status of the failure
+  let response = send(&app, "POST / HTTP/1.1\r\nHost: a\r\nContent-Type: text/plain\r\nContent-Length: 7\r\n\r\n[1,2,3]");
+  assert!(response.starts_with("HTTP/1.1 415 Unsupported Media Type\r\n"));
+}
+
+#[test]
+fn logging_test() {
+  use std::sync::{Arc, Mutex};
+
+  #[derive(Clone, Default)]
+  struct Shared(Arc<Mutex<Vec<u8>>>);
+
+  impl std::io::Write for Shared {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+      self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+      Ok(())
+    }
+  }
+
+  let mut app = App::new();
+  app.get("/a/:id", Handle::main(|ctx| ctx.res.send_body(b"a".to_vec())));
+  app.get("/fail", Handle::main(|_| Err("broken".into())));
+
+  let output = Shared::default();
+  let writer = output.clone();
+  let subscriber = tracing_subscriber::fmt()
+    .with_max_level(tracing::Level::TRACE)
+    .with_writer(move || writer.clone())
+    .finish();
+  tracing::subscriber::with_default(subscriber, || {
+    get(&app, "/a/1", "");
+    get(&app, "/fail", "");
+    send(&app, "BREW / HTTP/1.1\r\n\r\n");
+  });
+
+  let output = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+  let lines: Vec<&str> = output.lines().collect();
+  assert!(lines.iter().all(|line| line.contains(" request{id=")), "{output}");
+  assert!(lines.iter().any(|line| line.contains("DEBUG") && line.contains("webserver::app: Request received method=\"GET\" target=\"/a/1\"")));
+  assert!(lines.iter().any(|line| line.contains("TRACE") && line.contains("webserver::router::tree: Parameterized fragment matches")));
+  assert!(lines.iter().any(|line| line.contains("DEBUG") && line.contains("Response sent status=200 size=1")));
+  assert!(lines.iter().any(|line| line.contains("ERROR") && line.contains("Request failed status=500 error=broken")));
+  assert!(lines.iter().any(|line| line.contains("Invalid request error=Invalid request method: BREW")));
+}
@@ -0,0 +1,51 @@
+//! Base64 with the standard alphabet and padding, see RFC 4648, section 4.
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+pub fn encode(input: &[u8]) -> String {
+  let mut output = String::with_capacity(input.len().div_ceil(3) * 4);
+  for chunk in input.chunks(3) {
+    let bytes = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+    let group = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+
+    for i in 0..4 {
+      if i <= chunk.len() {
+        output.push(ALPHABET[(group >> (18 - 6 * i) & 0x3f) as usize] as char);
+      } else {
+        output.push('=');
+      }
+    }
+  }
+  output
+}
+
+fn decode_byte(byte: u8) -> Option<u32> {
+  ALPHABET.iter().position(|b| *b == byte).map(|p| p as u32)
+}
+
+pub fn decode(input: &str) -> Option<Vec<u8>> {
+  let input = input.as_bytes();
+  if input.len() % 4 != 0 {
+    return None
+  }
+
+  let mut output = Vec::with_capacity(input.len() / 4 * 3);
+  let groups = input.len() / 4;
+  for (index, chunk) in input.chunks(4).enumerate() {
+    // padding is only allowed at the very end
+    let padding = chunk.iter().rev().take_while(|b| **b == b'=').count();
+    if padding > 2 || (padding > 0 && index + 1 != groups) {
+      return None
+    }
+
+    let mut group = 0;
+    for byte in &chunk[..4 - padding] {
+      group = group << 6 | decode_byte(*byte)?;
+    }
+    group <<= 6 * padding;
+
+    let bytes = group.to_be_bytes();
+    output.extend_from_slice(&bytes[1..4 - padding]);
+  }
+  Some(output)
+}
@@ -0,0 +1,219 @@
+//! Response compression with the `gzip`, `deflate` and `br` content
+//! codings, negotiated with `Accept-Encoding`, and request decompression
+//! on the routes that accept encoded bodies.
+//!
+//! ```no_run
+//! use webserver::{App, compression::{Compression, Decompression}};
+//!
+//! let mut app = App::new();
+//! app.all("*", Compression::new().middleware());
+//! app.post("/upload", Decompression::new().middleware());
+//! app.listen("127.0.0.1:8080").unwrap();
+//! ```
+use std::{error::Error, io::{Read, Write}};
+
+use brotli::{CompressorReader, CompressorWriter};
+use flate2::{read::{GzEncoder, ZlibEncoder}, write, Compression as Level};
+
+use crate::{header::typed::{ContentType, ETag, Vary}, negotiation, response::{Body, Response}, router::Context, Handle, Return};
+
+mod decompression;
+pub use decompression::Decompression;
+
+#[cfg(test)]
+mod compression_test;
+
+const BROTLI_BUFFER_SIZE: usize = 4096;
+// favours speed, as responses are compressed on the fly
+const BROTLI_QUALITY: u32 = 5;
+const BROTLI_WINDOW: u32 = 22;
+
+/// A content coding, see RFC 9110, section 8.4.1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Encoding {
+  Brotli,
+  Gzip,
+  /// The zlib format, as `deflate` is defined by RFC 9110.
+  Deflate
+}
+
+impl Encoding {
+  /// The name of the coding in `Accept-Encoding` and `Content-Encoding`.
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      Encoding::Brotli => "br",
+      Encoding::Gzip => "gzip",
+      Encoding::Deflate => "deflate"
+    }
+  }
+
+  /// Compresses `bytes` in memory.
+  pub fn encode(&self, bytes: &[u8]) -> Vec<u8> {
+    // writing to a vector cannot fail
+    match self {
+      Encoding::Brotli => {
+        let mut encoder = CompressorWriter::new(Vec::new(), BROTLI_BUFFER_SIZE, BROTLI_QUALITY, BROTLI_WINDOW);
+        encoder.write_all(bytes).unwrap();
+        encoder.into_inner()
+      },
+      Encoding::Gzip => {
+        let mut encoder = write::GzEncoder::new(Vec::new(), Level::default());
+        encoder.write_all(bytes).unwrap();
+        encoder.finish().unwrap()
+      },
+      Encoding::Deflate => {
+        let mut encoder = write::ZlibEncoder::new(Vec::new(), Level::default());
+        encoder.write_all(bytes).unwrap();
+        encoder.finish().unwrap()
+      }
+    }
+  }
+
+  /// Compresses `reader` as it is read.
+  pub fn encode_reader(&self, reader: impl Read + 'static) -> Box<dyn Read> {
+    match self {
+      Encoding::Brotli => Box::new(CompressorReader::new(reader, BROTLI_BUFFER_SIZE, BROTLI_QUALITY, BROTLI_WINDOW)),
+      Encoding::Gzip => Box::new(GzEncoder::new(reader, Level::default())),
+      Encoding::Deflate => Box::new(ZlibEncoder::new(reader, Level::default()))
+    }
+  }
+}
+
+/// Whether a media type is already compressed, so that compressing it
+/// again would only cost time.
+pub fn is_compressed(media_type: &str) -> bool {
+  let media_type = media_type.to_ascii_lowercase();
+  let (main_type, subtype) = media_type.split_once('/').unwrap_or((&media_type, ""));
+  match main_type {
+    "image" => subtype != "svg+xml" && subtype != "bmp" && subtype != "x-icon" && subtype != "vnd.microsoft.icon",
+    "audio" | "video" => true,
+    "font" => subtype == "woff" || subtype == "woff2",
+    "application" => matches!(
+      subtype,
+      "zip" | "gzip" | "x-gzip" | "x-bzip2" | "x-xz" | "zstd" | "x-7z-compressed" | "x-rar-compressed" | "pdf" | "wasm"
+    ),
+    _ => false
+  }
+}
+
+/// The compression middleware, see the [module documentation](self).
+///
+/// Bodies under 1 KiB, responses without a `Content-Type` and content
+/// types that are already compressed (see [`is_compressed`]) are sent
+/// as is. Streamed bodies are compressed as they are written, and then
+/// sent chunked.
+pub struct Compression {
+  encodings: Vec<Encoding>,
+  min_size: u64
+}
+
+impl Default for Compression {
+  fn default() -> Self {
+    Compression::new()
+  }
+}
+
+impl Compression {
+  pub fn new() -> Compression {
+    Compression {
+      encodings: vec![Encoding::Brotli, Encoding::Gzip, Encoding::Deflate],
+      min_size: 1024
+    }
+  }
+
+  /// The encodings offered, by order of preference when the client
+  /// prefers none of them.
+  pub fn with_encodings(mut self, encodings: Vec<Encoding>) -> Compression {
+    self.encodings = encodings;
+    self
+  }
+
+  /// The size under which bodies are not worth compressing.
+  pub fn with_min_size(mut self, size: u64) -> Compression {
+    self.min_size = size;
+    self
+  }
+
+  pub fn middleware(self) -> Handle {
+    Handle::after(move |ctx: &mut Context| {
+      self.compress(ctx)?;
+      Ok(Return::Next)
+    })
+  }
+
+  fn compress(&self, ctx: &mut Context) -> Result<(), Box<dyn Error>> {
+    let res = &mut *ctx.res;
+    if !self.is_compressible(res) {
+      return Ok(())
+    }
+
+    let length = match res.body() {
+      Some(body) => Some(body.len() as u64),
+      None => res.headers.content_length().map(|length| length as u64)
+    };
+    if length.is_some_and(|length| length < self.min_size) {
+      return Ok(())
+    }
+
+    // the response now depends on the request encodings
+    let mut vary = res.headers.typed::<Vary>().unwrap_or(Vary::Fields(Vec::new()));
+    vary.insert("Accept-Encoding");
+    res.headers.set_typed(&vary);
+
+    let Some(encoding) = self.negotiate(ctx) else {
+      return Ok(())
+    };
+
+    let res = &mut *ctx.res;
+    match res.take_body() {
+      Some(Body::Bytes(bytes)) => {
+        let encoded = encoding.encode(&bytes);
+        if encoded.len() >= bytes.len() {
+          res.send_body(bytes)?;
+          return Ok(())
+        }
+        res.send_body(encoded)?;
+      },
+      Some(Body::Stream { reader, length }) => {
+        let reader: Box<dyn Read> = match length {
+          Some(length) => Box::new(reader.take(length)),
+          None => reader
+        };
+        res.send_stream(encoding.encode_reader(reader), None)?;
+      },
+      None => return Ok(())
+    }
+
+    res.headers.set("Content-Encoding", encoding.as_str().to_string());
+    // the encoded representation is not byte for byte the same
+    if let Some(etag) = res.headers.typed::<ETag>().filter(|etag| !etag.is_weak()) {
+      res.headers.set_typed(&ETag::weak(etag.tag())?);
+    }
+    Ok(())
+  }
+
+  fn is_compressible(&self, res: &Response) -> bool {
+    // a partial response is a range of the unencoded representation
+    let code = res.status.to_u16();
+    let has_body = res.is_sent() && code >= 200 && !matches!(code, 204 | 206 | 304);
+
+    has_body
+      && !res.headers.contains("Content-Encoding")
+      && res.headers
+        .typed::<ContentType>()
+        .is_some_and(|content_type| !is_compressed(content_type.media_type()))
+  }
+
+  fn negotiate(&self, ctx: &Context) -> Option<Encoding> {
+    // without `Accept-Encoding`, any coding is acceptable but few
+    // clients that do not send it support them
+    if !ctx.req.headers().contains("accept-encoding") {
+      return None
+    }
+
+    let mut available: Vec<&str> = self.encodings.iter().map(Encoding::as_str).collect();
+    available.push("identity");
+    let chosen = negotiation::encoding(ctx.req.headers(), &available).ok()?;
+    self.encodings.iter().find(|encoding| encoding.as_str() == chosen).copied()
+  }
+}
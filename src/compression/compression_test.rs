@@ -0,0 +1,235 @@
+use std::io::Cursor;
+
+use flate2::read::{GzDecoder, ZlibDecoder};
+
+use crate::App;
+use super::*;
+
+fn text() -> String {
+  "All work and no play makes Jack a dull boy. ".repeat(100)
+}
+
+fn app(compression: Compression) -> App {
+  let mut app = App::new();
+  app.all("*", compression.middleware());
+  app.get("/text", Handle::main(|ctx| ctx.res.content_type("text/plain").send_body(text().into_bytes())));
+  app.get("/small", Handle::main(|ctx| ctx.res.content_type("text/plain").send_body(b"tiny".to_vec())));
+  app.get("/image", Handle::main(|ctx| ctx.res.content_type("image/png").send_body(text().into_bytes())));
+  app.get("/tagged", Handle::main(|ctx| {
+    ctx.res.headers.set("ETag", "\"v1\"".to_string());
+    ctx.res.headers.set("Vary", "Cookie".to_string());
+    ctx.res.content_type("text/plain").send_body(text().into_bytes())
+  }));
+  app.get("/stream", Handle::main(|ctx| {
+    let body = text().into_bytes();
+    let length = body.len() as u64;
+    ctx.res.content_type("text/plain").send_stream(Cursor::new(body), Some(length))
+  }));
+  app
+}
+
+// the head, as text, and the raw body
+fn get(app: &App, path: &str, accept_encoding: Option<&str>) -> (String, Vec<u8>) {
+  let accept_encoding = accept_encoding.map_or(String::new(), |a| format!("Accept-Encoding: {a}\r\n"));
+  let raw = format!("GET {path} HTTP/1.1\r\nHost: localhost\r\n{accept_encoding}\r\n");
+  let mut output = Vec::new();
+  app.respond(Cursor::new(raw.into_bytes()), &mut output);
+
+  let end = output.windows(4).position(|w| w == b"\r\n\r\n").unwrap();
+  let head = String::from_utf8(output[..end].to_vec()).unwrap();
+  (head, output[end + 4..].to_vec())
+}
+
+fn header<'a>(head: &'a str, name: &str) -> Option<&'a str> {
+  head
+    .lines()
+    .filter_map(|line| line.split_once(": "))
+    .find(|(key, _)| key.eq_ignore_ascii_case(name))
+    .map(|(_, value)| value)
+}
+
+fn decode(encoding: &str, body: &[u8]) -> String {
+  let mut decoded = String::new();
+  match encoding {
+    "br" => brotli::Decompressor::new(body, 4096).read_to_string(&mut decoded),
+    "gzip" => GzDecoder::new(body).read_to_string(&mut decoded),
+    "deflate" => ZlibDecoder::new(body).read_to_string(&mut decoded),
+    _ => panic!("Unexpected encoding {encoding}")
+  }.unwrap();
+  decoded
+}
+
+fn dechunk(mut body: &[u8]) -> Vec<u8> {
+  let mut decoded = Vec::new();
+  loop {
+    let end = body.windows(2).position(|w| w == b"\r\n").unwrap();
+    let size = usize::from_str_radix(std::str::from_utf8(&body[..end]).unwrap(), 16).unwrap();
+    if size == 0 {
+      return decoded
+    }
+    decoded.extend_from_slice(&body[end + 2..end + 2 + size]);
+    body = &body[end + 2 + size + 2..];
+  }
+}
+
+mod negotiation_test {
+  use super::*;
+
+  #[test]
+  fn encodings_test() {
+    let app = app(Compression::new());
+    for (accept_encoding, expected) in [("gzip", "gzip"), ("deflate", "deflate"), ("br", "br"), ("gzip, br", "br"), ("br;q=0.5, gzip", "gzip"), ("*", "br")] {
+      let (head, body) = get(&app, "/text", Some(accept_encoding));
+      assert_eq!(header(&head, "Content-Encoding"), Some(expected), "for {accept_encoding}");
+      assert_eq!(header(&head, "Content-Length"), Some(body.len().to_string().as_str()));
+      assert_eq!(header(&head, "Vary"), Some("Accept-Encoding"));
+      assert!(body.len() < text().len());
+      assert_eq!(decode(expected, &body), text());
+    }
+  }
+
+  #[test]
+  fn preference_test() {
+    let app = app(Compression::new().with_encodings(vec![Encoding::Gzip, Encoding::Brotli]));
+    let (head, _) = get(&app, "/text", Some("br, gzip"));
+    assert_eq!(header(&head, "Content-Encoding"), Some("gzip"));
+  }
+
+  #[test]
+  fn identity_test() {
+    let app = app(Compression::new());
+    for accept_encoding in [None, Some("identity"), Some("gzip;q=0, br;q=0"), Some("compress")] {
+      let (head, body) = get(&app, "/text", accept_encoding);
+      assert_eq!(header(&head, "Content-Encoding"), None);
+      assert_eq!(body, text().into_bytes());
+    }
+  }
+}
+
+mod skip_test {
+  use super::*;
+
+  #[test]
+  fn small_body_test() {
+    let (head, body) = get(&app(Compression::new()), "/small", Some("gzip"));
+    assert_eq!(header(&head, "Content-Encoding"), None);
+    assert_eq!(header(&head, "Vary"), None);
+    assert_eq!(body, b"tiny");
+
+    let (head, _) = get(&app(Compression::new().with_min_size(0)), "/small", Some("gzip"));
+    assert_eq!(header(&head, "Vary"), Some("Accept-Encoding"));
+  }
+
+  #[test]
+  fn compressed_type_test() {
+    let (head, body) = get(&app(Compression::new()), "/image", Some("gzip"));
+    assert_eq!(header(&head, "Content-Encoding"), None);
+    assert_eq!(body, text().into_bytes());
+
+    assert!(is_compressed("image/png"));
+    assert!(is_compressed("Application/GZIP"));
+    assert!(is_compressed("font/woff2"));
+    assert!(!is_compressed("image/svg+xml"));
+    assert!(!is_compressed("text/html"));
+    assert!(!is_compressed("application/json"));
+  }
+}
+
+mod headers_test {
+  use super::*;
+
+  #[test]
+  fn vary_and_etag_test() {
+    let (head, body) = get(&app(Compression::new()), "/tagged", Some("gzip"));
+    assert_eq!(header(&head, "Vary"), Some("Cookie, Accept-Encoding"));
+    assert_eq!(header(&head, "ETag"), Some("W/\"v1\""));
+    assert_eq!(decode("gzip", &body), text());
+  }
+
+  #[test]
+  fn stream_test() {
+    let (head, body) = get(&app(Compression::new()), "/stream", Some("gzip"));
+    assert_eq!(header(&head, "Content-Encoding"), Some("gzip"));
+    assert_eq!(header(&head, "Content-Length"), None);
+    assert_eq!(header(&head, "Transfer-Encoding"), Some("chunked"));
+    assert_eq!(decode("gzip", &dechunk(&body)), text());
+  }
+}
+
+mod decompression_test {
+  use super::*;
+
+  fn upload_app(decompression: Decompression) -> App {
+    let mut app = App::new();
+    app.post("/upload", decompression.middleware());
+    app.post("/upload", Handle::main(|ctx| {
+      let body = ctx.req.read_body()?;
+      let encoding = ctx.req.headers().get("content-encoding").cloned().unwrap_or_default();
+      ctx.res.send_body(format!("{encoding}{}", String::from_utf8(body)?).into_bytes())
+    }));
+    app
+  }
+
+  fn post(app: &App, content_encoding: &str, body: &[u8]) -> (String, Vec<u8>) {
+    let head = format!(
+      "POST /upload HTTP/1.1\r\nHost: localhost\r\nContent-Encoding: {content_encoding}\r\nContent-Length: {}\r\n\r\n",
+      body.len()
+    );
+    let mut raw = head.into_bytes();
+    raw.extend_from_slice(body);
+
+    let mut output = Vec::new();
+    app.respond(Cursor::new(raw), &mut output);
+    let end = output.windows(4).position(|w| w == b"\r\n\r\n").unwrap();
+    let head = String::from_utf8(output[..end].to_vec()).unwrap();
+    (head, output[end + 4..].to_vec())
+  }
+
+  #[test]
+  fn encodings_test() {
+    let app = upload_app(Decompression::new());
+    for encoding in [Encoding::Brotli, Encoding::Gzip, Encoding::Deflate] {
+      let (head, body) = post(&app, encoding.as_str(), &encoding.encode(text().as_bytes()));
+      assert!(head.starts_with("HTTP/1.1 200 OK\r\n"), "for {}", encoding.as_str());
+      assert_eq!(body, text().into_bytes());
+    }
+
+    let (_, body) = post(&app, "X-GZIP", &Encoding::Gzip.encode(b"aliased"));
+    assert_eq!(body, b"aliased");
+    let (_, body) = post(&app, "identity", b"plain");
+    assert_eq!(body, b"plain");
+  }
+
+  #[test]
+  fn multiple_encodings_test() {
+    let encoded = Encoding::Gzip.encode(&Encoding::Deflate.encode(b"twice"));
+    let (_, body) = post(&upload_app(Decompression::new()), "deflate, gzip", &encoded);
+    assert_eq!(body, b"twice");
+  }
+
+  #[test]
+  fn unsupported_encoding_test() {
+    let (head, _) = post(&upload_app(Decompression::new()), "gzip, compress", b"data");
+    assert!(head.starts_with("HTTP/1.1 415 Unsupported Media Type\r\n"));
+    assert_eq!(header(&head, "Accept-Encoding"), Some("br, gzip, deflate"));
+  }
+
+  #[test]
+  fn max_size_test() {
+    // a few bytes that expand to a megabyte
+    let bomb = Encoding::Gzip.encode(&vec![0; 1 << 20]);
+    assert!(bomb.len() < 4096);
+
+    let (head, _) = post(&upload_app(Decompression::new().with_max_size(1024)), "gzip", &bomb);
+    assert!(head.starts_with("HTTP/1.1 413 Content Too Large\r\n"));
+
+    let (head, _) = post(&upload_app(Decompression::new().with_max_size(1024)), "gzip", &Encoding::Gzip.encode(b"small"));
+    assert!(head.starts_with("HTTP/1.1 200 OK\r\n"));
+  }
+
+  #[test]
+  fn invalid_body_test() {
+    let (head, _) = post(&upload_app(Decompression::new()), "gzip", b"not gzip");
+    assert!(head.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+  }
+}
@@ -0,0 +1,128 @@
+use std::{error::Error, io::{self, BufRead, BufReader, Read}};
+
+use brotli::Decompressor;
+use flate2::read::{MultiGzDecoder, ZlibDecoder};
+
+use crate::{error::HttpError, protocol::StatusCode, router::Context, stream::error::Error as ParseError, Handle, Return};
+use super::{Encoding, BROTLI_BUFFER_SIZE};
+
+// fails like a chunked body over `max_body_size`, so
+// that reading it is answered with `413 Content Too Large`
+struct Limited<R> {
+  inner: R,
+  read: u64,
+  limit: u64
+}
+
+impl<R: Read> Read for Limited<R> {
+  fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+    let read = self.inner.read(out)?;
+    self.read += read as u64;
+    if self.read > self.limit {
+      return Err(io::Error::new(io::ErrorKind::InvalidData, ParseError::BodyTooLarge(self.limit as usize)))
+    }
+    Ok(read)
+  }
+}
+
+/// Decodes request bodies sent with a `Content-Encoding`, for the
+/// routes it is registered on.
+///
+/// The decoded body is bounded by `ServerConfig::max_body_size`, so that
+/// a small body cannot expand into an unbounded one. Bodies sent with
+/// another coding are answered with `415 Unsupported Media Type`.
+///
+/// ```no_run
+/// use webserver::{App, Handle, compression::Decompression};
+///
+/// let mut app = App::new();
+/// app.post("/events", Decompression::new().middleware());
+/// app.post("/events", Handle::main(|ctx| {
+///   let body = ctx.req.read_body()?;
+///   ctx.res.send_body(format!("{} bytes", body.len()).into_bytes())
+/// }));
+/// app.listen("127.0.0.1:8080").unwrap();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Decompression {
+  max_size: Option<u64>
+}
+
+impl Decompression {
+  pub fn new() -> Decompression {
+    Decompression::default()
+  }
+
+  /// Maximum size of a decoded body, instead of `ServerConfig::max_body_size`.
+  pub fn with_max_size(mut self, size: u64) -> Decompression {
+    self.max_size = Some(size);
+    self
+  }
+
+  pub fn middleware(self) -> Handle {
+    Handle::before(move |ctx: &mut Context| {
+      self.decode(ctx)?;
+      Ok(Return::Next)
+    })
+  }
+
+  fn decode(&self, ctx: &mut Context) -> Result<(), Box<dyn Error>> {
+    let Some(fields) = ctx.req.headers().get_all("content-encoding") else {
+      return Ok(())
+    };
+
+    // codings are listed in the order they were applied
+    let mut encodings = Vec::new();
+    for coding in fields.iter().flat_map(|f| f.split(',')).map(|c| c.trim_matches([' ', '\t'])) {
+      if coding.is_empty() || coding.eq_ignore_ascii_case("identity") {
+        continue
+      }
+
+      match Encoding::from_name(coding) {
+        Some(encoding) => encodings.push(encoding),
+        None => {
+          ctx.res.headers.set("Accept-Encoding", "br, gzip, deflate".to_string());
+          let message = format!("Unsupported content coding: {coding}");
+          return Err(Box::new(HttpError::new(StatusCode::UnsupportedMediaType, message)))
+        }
+      }
+    }
+
+    let req = &mut *ctx.req;
+    let limit = self.max_size.unwrap_or(req.max_body_size() as u64);
+    if let Some(mut body) = req.body().take() {
+      for encoding in encodings.iter().rev() {
+        body = Box::new(BufReader::new(encoding.decode_reader(body)));
+      }
+      let body: Box<dyn BufRead> = Box::new(BufReader::new(Limited { inner: body, read: 0, limit }));
+      *req.body() = Some(body);
+    }
+
+    // the body is now the decoded representation
+    let headers = req.mut_headers();
+    headers.remove("content-encoding");
+    headers.remove("content-length");
+    Ok(())
+  }
+}
+
+impl Encoding {
+  /// The coding called `name`, `x-gzip` being an alias of `gzip`.
+  pub fn from_name(name: &str) -> Option<Encoding> {
+    match name.to_ascii_lowercase().as_str() {
+      "br" => Some(Encoding::Brotli),
+      "gzip" | "x-gzip" => Some(Encoding::Gzip),
+      "deflate" => Some(Encoding::Deflate),
+      _ => None
+    }
+  }
+
+  /// Decompresses `reader` as it is read.
+  pub fn decode_reader(&self, reader: impl Read + 'static) -> Box<dyn Read> {
+    match self {
+      Encoding::Brotli => Box::new(Decompressor::new(reader, BROTLI_BUFFER_SIZE)),
+      Encoding::Gzip => Box::new(MultiGzDecoder::new(reader)),
+      Encoding::Deflate => Box::new(ZlibDecoder::new(reader))
+    }
+  }
+}
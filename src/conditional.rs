@@ -0,0 +1,196 @@
+//! Conditional requests, see RFC 9110, section 13.
+//!
+//! [`Conditional`] tags the bodies sent with `Response::send_body` and
+//! answers `GET` requests whose preconditions do not hold with
+//! `304 Not Modified` or `412 Precondition Failed`. The handlers of other
+//! methods evaluate the preconditions themselves with [`evaluate`], as
+//! by the time the middleware runs their change has already been made.
+//!
+//! ```no_run
+//! use webserver::{App, Handle, conditional::Conditional};
+//!
+//! let mut app = App::new();
+//! app.get("/api/**", Conditional::new().middleware());
+//! app.get("/api/users", Handle::main(|ctx| {
+//!   ctx.res.content_type("application/json").send_body(b"[]".to_vec())
+//! }));
+//! app.listen("127.0.0.1:8080").unwrap();
+//! ```
+use std::error::Error;
+
+use crate::{header::typed::{ETag, HttpDate, IfMatch, IfModifiedSince, IfNoneMatch, IfRange, IfUnmodifiedSince, LastModified, Range}, protocol::StatusCode, request::{Method, Request}, response::Response, router::Context, Handle, Return};
+
+#[cfg(test)]
+mod conditional_test;
+
+/// The outcome of evaluating the preconditions of a request.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Precondition {
+  /// The request can be performed.
+  Passed,
+  /// The client already has the representation, answered with `304 Not Modified`.
+  NotModified,
+  /// Answered with `412 Precondition Failed`.
+  Failed
+}
+
+/// Evaluates the preconditions of `req` against the validators of the
+/// selected representation, in the order of RFC 9110, section 13.2.2.
+///
+/// Handlers changing state should evaluate them before doing so, as
+/// `If-Match` is most useful to prevent lost updates.
+pub fn evaluate(req: &Request, etag: Option<&ETag>, last_modified: Option<HttpDate>) -> Precondition {
+  let headers = req.headers();
+  let is_get = req.location().0 == Method::Get;
+
+  // `If-Unmodified-Since` is ignored when `If-Match` is sent
+  match headers.typed::<IfMatch>() {
+    Some(if_match) => {
+      let is_matched = match (&if_match, etag) {
+        (IfMatch::Any, _) => true,
+        (_, Some(etag)) => if_match.matches(etag),
+        (_, None) => false
+      };
+      if !is_matched {
+        return Precondition::Failed
+      }
+    },
+    None => {
+      if let (Some(IfUnmodifiedSince(since)), Some(last_modified)) = (headers.typed::<IfUnmodifiedSince>(), last_modified) {
+        if last_modified > since {
+          return Precondition::Failed
+        }
+      }
+    }
+  }
+
+  // and `If-Modified-Since` when `If-None-Match` is
+  match headers.typed::<IfNoneMatch>() {
+    Some(if_none_match) => {
+      let is_matched = match (&if_none_match, etag) {
+        (IfNoneMatch::Any, _) => true,
+        (_, Some(etag)) => if_none_match.matches(etag),
+        (_, None) => false
+      };
+      match is_matched {
+        true if is_get => Precondition::NotModified,
+        true => Precondition::Failed,
+        false => Precondition::Passed
+      }
+    },
+    None => match (headers.typed::<IfModifiedSince>(), last_modified) {
+      (Some(IfModifiedSince(since)), Some(last_modified)) if is_get && last_modified <= since => Precondition::NotModified,
+      _ => Precondition::Passed
+    }
+  }
+}
+
+/// Whether the `Range` of `req` should be honoured, that is without
+/// `If-Range` or when it matches the representation, see RFC 9110,
+/// section 13.1.5.
+pub fn is_range_fresh(req: &Request, etag: Option<&ETag>, last_modified: Option<HttpDate>) -> bool {
+  req
+    .headers()
+    .typed::<IfRange>()
+    .is_none_or(|if_range| if_range.matches(etag, last_modified))
+}
+
+// FNV-1a, stable across builds unlike `DefaultHasher`
+fn hash(bytes: &[u8]) -> u64 {
+  bytes.iter().fold(0xcbf29ce484222325, |hash, byte| (hash ^ *byte as u64).wrapping_mul(0x100000001b3))
+}
+
+/// The conditional requests middleware, see the [module documentation](self).
+///
+/// Successful responses to `GET` sent with a body and without an `ETag`
+/// are tagged with a hash of the body. Their preconditions are then
+/// evaluated with that tag and their `Last-Modified`, if any, and a
+/// single byte range is answered with `206 Partial Content`. Streamed
+/// bodies are only evaluated when the handler sets their validators.
+#[derive(Debug, Clone, Default)]
+pub struct Conditional {
+  weak: bool
+}
+
+impl Conditional {
+  pub fn new() -> Conditional {
+    Conditional::default()
+  }
+
+  /// Generates weak entity tags, for bodies that are equivalent
+  /// but not always byte for byte the same.
+  pub fn with_weak(mut self, weak: bool) -> Conditional {
+    self.weak = weak;
+    self
+  }
+
+  pub fn middleware(self) -> Handle {
+    Handle::after(move |ctx: &mut Context| {
+      self.apply(ctx)?;
+      Ok(Return::Next)
+    })
+  }
+
+  fn apply(&self, ctx: &mut Context) -> Result<(), Box<dyn Error>> {
+    let res = &mut *ctx.res;
+    if ctx.req.location().0 != Method::Get || !res.is_sent() || !res.status.is_success() {
+      return Ok(())
+    }
+
+    if !res.headers.contains("ETag") {
+      if let Some(body) = res.body() {
+        let tag = format!("{:x}-{:016x}", body.len(), hash(body));
+        let etag = match self.weak {
+          true => ETag::weak(&tag)?,
+          false => ETag::strong(&tag)?
+        };
+        res.headers.set_typed(&etag);
+      }
+    }
+
+    let etag = res.headers.typed::<ETag>();
+    let last_modified = res.headers.typed::<LastModified>().map(|LastModified(date)| date);
+    match evaluate(ctx.req, etag.as_ref(), last_modified) {
+      Precondition::NotModified => strip(res, StatusCode::NotModified),
+      Precondition::Failed => strip(res, StatusCode::PreconditionFailed),
+      Precondition::Passed if res.status.to_u16() == 200 && is_range_fresh(ctx.req, etag.as_ref(), last_modified) => {
+        if let Some(range) = ctx.req.headers().typed::<Range>() {
+          send_range(res, &range)?;
+        }
+      },
+      Precondition::Passed => ()
+    }
+    Ok(())
+  }
+}
+
+// the validators are kept, so that a client can update its cache
+fn strip(res: &mut Response, status: StatusCode) {
+  res.take_body();
+  for name in ["Content-Length", "Content-Type", "Content-Range", "Transfer-Encoding"] {
+    res.headers.remove(name);
+  }
+  res.status(status);
+}
+
+// answers a single range of a body sent with `send_body`, others are
+// sent whole as multiple ranges are better served by `files::serve_dir`
+fn send_range(res: &mut Response, range: &Range) -> Result<(), Box<dyn Error>> {
+  let Some(length) = res.body().map(|body| body.len() as u64) else {
+    return Ok(())
+  };
+
+  match &range.resolve(length)[..] {
+    [] => {
+      strip(res, StatusCode::RangeNotSatisfiable);
+      res.headers.set("Content-Range", format!("bytes */{length}"));
+    },
+    [range] => {
+      let body = res.body().unwrap_or_default()[range.start as usize..range.end as usize].to_vec();
+      res.headers.set("Content-Range", format!("bytes {}-{}/{length}", range.start, range.end - 1));
+      res.status(StatusCode::PartialContent).send_body(body)?;
+    },
+    _ => ()
+  }
+  Ok(())
+}
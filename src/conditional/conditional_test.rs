@@ -0,0 +1,160 @@
+use crate::{app::app_test::{body, get, header, request}, App};
+use super::*;
+
+const BODY: &str = "0123456789";
+const MODIFIED: &str = "Wed, 21 Oct 2015 07:28:00 GMT";
+
+fn app(conditional: Conditional) -> App {
+  let mut app = App::new();
+  app.all("*", conditional.middleware());
+  app.get("/body", Handle::main(|ctx| ctx.res.content_type("text/plain").send_body(BODY.as_bytes().to_vec())));
+  app.get("/dated", Handle::main(|ctx| {
+    ctx.res.headers.set("Last-Modified", MODIFIED.to_string());
+    ctx.res.headers.set("ETag", "\"v1\"".to_string());
+    ctx.res.content_type("text/plain").send_body(BODY.as_bytes().to_vec())
+  }));
+  app.post("/body", Handle::main(|ctx| ctx.res.send_body(BODY.as_bytes().to_vec())));
+  app.get("/missing", Handle::main(|ctx| ctx.res.status(StatusCode::NotFound).send_body(b"missing".to_vec())));
+  app
+}
+
+mod etag_test {
+  use super::*;
+
+  #[test]
+  fn generated_test() {
+    let response = get(&app(Conditional::new()), "/body", "");
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+    let etag = header(&response, "ETag").unwrap();
+    assert!(etag.starts_with("\"a-"));
+    assert_eq!(body(&response), BODY);
+
+    // the same body is always tagged the same
+    assert_eq!(header(&get(&app(Conditional::new()), "/body", ""), "ETag"), Some(etag));
+
+    let weak = get(&app(Conditional::new().with_weak(true)), "/body", "");
+    assert_eq!(header(&weak, "ETag"), Some(format!("W/{etag}").as_str()));
+  }
+
+  #[test]
+  fn kept_test() {
+    assert_eq!(header(&get(&app(Conditional::new()), "/dated", ""), "ETag"), Some("\"v1\""));
+    assert_eq!(header(&get(&app(Conditional::new()), "/missing", ""), "ETag"), None);
+  }
+}
+
+mod precondition_test {
+  use super::*;
+
+  #[test]
+  fn if_none_match_test() {
+    let app = app(Conditional::new());
+    let etag = header(&get(&app, "/body", ""), "ETag").unwrap().to_string();
+
+    for value in [etag.clone(), format!("W/{etag}"), "*".to_string()] {
+      let response = get(&app, "/body", &format!("If-None-Match: {value}\r\n"));
+      assert!(response.starts_with("HTTP/1.1 304 Not Modified\r\n"), "for {value}");
+      assert_eq!(header(&response, "ETag"), Some(etag.as_str()));
+      assert_eq!(header(&response, "Content-Length"), None);
+      assert_eq!(header(&response, "Content-Type"), None);
+      assert_eq!(body(&response), "");
+    }
+
+    assert!(get(&app, "/body", "If-None-Match: \"other\"\r\n").starts_with("HTTP/1.1 200 OK\r\n"));
+  }
+
+  #[test]
+  fn other_methods_test() {
+    // left to the handlers, which have already made their change
+    let app = app(Conditional::new());
+    let response = request(&app, "POST", "/body", "If-None-Match: *\r\nIf-Match: \"v2\"\r\n");
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert_eq!(header(&response, "ETag"), None);
+    assert_eq!(body(&response), BODY);
+  }
+
+  #[test]
+  fn if_match_test() {
+    let app = app(Conditional::new());
+    assert!(get(&app, "/dated", "If-Match: \"v1\"\r\n").starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(get(&app, "/dated", "If-Match: *\r\n").starts_with("HTTP/1.1 200 OK\r\n"));
+
+    let response = get(&app, "/dated", "If-Match: \"v2\", W/\"v1\"\r\n");
+    assert!(response.starts_with("HTTP/1.1 412 Precondition Failed\r\n"));
+    assert_eq!(body(&response), "");
+
+    // weak tags never match
+    let app = super::app(Conditional::new().with_weak(true));
+    let etag = header(&get(&app, "/body", ""), "ETag").unwrap().to_string();
+    assert!(get(&app, "/body", &format!("If-Match: {etag}\r\n")).starts_with("HTTP/1.1 412 Precondition Failed\r\n"));
+  }
+
+  #[test]
+  fn dates_test() {
+    let app = app(Conditional::new());
+    let response = get(&app, "/dated", &format!("If-Modified-Since: {MODIFIED}\r\n"));
+    assert!(response.starts_with("HTTP/1.1 304 Not Modified\r\n"));
+    let response = get(&app, "/dated", "If-Modified-Since: Tue, 20 Oct 2015 07:28:00 GMT\r\n");
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+
+    let response = get(&app, "/dated", "If-Unmodified-Since: Tue, 20 Oct 2015 07:28:00 GMT\r\n");
+    assert!(response.starts_with("HTTP/1.1 412 Precondition Failed\r\n"));
+    let response = get(&app, "/dated", &format!("If-Unmodified-Since: {MODIFIED}\r\n"));
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+  }
+
+  #[test]
+  fn precedence_test() {
+    let app = app(Conditional::new());
+    // If-Match over If-Unmodified-Since
+    let headers = "If-Match: \"v1\"\r\nIf-Unmodified-Since: Tue, 20 Oct 2015 07:28:00 GMT\r\n";
+    assert!(get(&app, "/dated", headers).starts_with("HTTP/1.1 200 OK\r\n"));
+
+    // If-None-Match over If-Modified-Since
+    let headers = format!("If-None-Match: \"v2\"\r\nIf-Modified-Since: {MODIFIED}\r\n");
+    assert!(get(&app, "/dated", &headers).starts_with("HTTP/1.1 200 OK\r\n"));
+
+    // a failed If-Match over a matched If-None-Match
+    let headers = "If-Match: \"v2\"\r\nIf-None-Match: \"v1\"\r\n";
+    assert!(get(&app, "/dated", headers).starts_with("HTTP/1.1 412 Precondition Failed\r\n"));
+  }
+
+  #[test]
+  fn error_test() {
+    let response = get(&app(Conditional::new()), "/missing", "If-Match: \"v1\"\r\n");
+    assert!(response.starts_with("HTTP/1.1 404 Not Found\r\n"));
+    assert_eq!(body(&response), "missing");
+  }
+}
+
+mod range_test {
+  use super::*;
+
+  #[test]
+  fn single_range_test() {
+    let app = app(Conditional::new());
+    let response = get(&app, "/dated", "Range: bytes=2-4\r\n");
+    assert!(response.starts_with("HTTP/1.1 206 Partial Content\r\n"));
+    assert_eq!(header(&response, "Content-Range"), Some("bytes 2-4/10"));
+    assert_eq!(header(&response, "Content-Length"), Some("3"));
+    assert_eq!(body(&response), "234");
+
+    let response = get(&app, "/dated", "Range: bytes=10-\r\n");
+    assert!(response.starts_with("HTTP/1.1 416 Range Not Satisfiable\r\n"));
+    assert_eq!(header(&response, "Content-Range"), Some("bytes */10"));
+
+    // multiple ranges are sent whole
+    assert_eq!(body(&get(&app, "/dated", "Range: bytes=0-1, 4-5\r\n")), BODY);
+  }
+
+  #[test]
+  fn if_range_test() {
+    let app = app(Conditional::new());
+    assert_eq!(body(&get(&app, "/dated", "Range: bytes=-2\r\nIf-Range: \"v1\"\r\n")), "89");
+    assert_eq!(body(&get(&app, "/dated", &format!("Range: bytes=-2\r\nIf-Range: {MODIFIED}\r\n"))), "89");
+
+    let response = get(&app, "/dated", "Range: bytes=-2\r\nIf-Range: \"v0\"\r\n");
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert_eq!(body(&response), BODY);
+  }
+}
@@ -0,0 +1,41 @@
+/// Limits applied by the server while reading incoming requests.
+///
+/// Every limit is expressed in bytes, except `max_header_count`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServerConfig {
+  /// Maximum length of the request line (`GET /path HTTP/1.1\r\n`).
+  pub max_request_line: usize,
+  /// Maximum length of the request target.
+  pub max_uri_length: usize,
+  /// Maximum size of the whole header section, request line excluded.
+  pub max_header_bytes: usize,
+  /// Maximum number of header fields.
+  pub max_header_count: usize,
+  /// Maximum size of a single header field line.
+  pub max_header_size: usize,
+  /// Maximum size of the request body.
+  pub max_body_size: usize,
+  /// Maximum size of a request body read into memory to be parsed,
+  /// e.g. by `Request::json` or `Request::form`.
+  pub max_parsed_body_size: usize,
+  /// Accept some malformed, but unambiguous, input that strict RFC 9112
+  /// parsing rejects: bare `LF` line endings, obsolete line folding,
+  /// lowercase methods, repeated identical `Content-Length` and
+  /// `Content-Length` alongside `Transfer-Encoding` (which then wins).
+  pub lenient_parsing: bool
+}
+
+impl Default for ServerConfig {
+  fn default() -> Self {
+    ServerConfig {
+      max_request_line: 8 * 1024,
+      max_uri_length: 8 * 1024,
+      max_header_bytes: 64 * 1024,
+      max_header_count: 100,
+      max_header_size: 16 * 1024,
+      max_body_size: 2 * 1024 * 1024,
+      max_parsed_body_size: 1024 * 1024,
+      lenient_parsing: false
+    }
+  }
+}
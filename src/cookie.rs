@@ -0,0 +1,327 @@
+//! Cookies, as described in RFC 6265.
+//!
+//! Request cookies are read with [`Request::cookies`](crate::request::Request::cookies),
+//! response cookies are built with [`Cookie`] and sent with
+//! [`Response::set_cookie`](crate::response::Response::set_cookie).
+//!
+//! ```
+//! use webserver::cookie::{Cookie, SameSite};
+//!
+//! let cookie = Cookie::new("id", "a3fWa")
+//!   .with_path("/")
+//!   .with_max_age(3600)
+//!   .with_secure(true)
+//!   .with_http_only(true)
+//!   .with_same_site(SameSite::Lax);
+//!
+//! assert_eq!(cookie.to_string(), "id=a3fWa; Max-Age=3600; Path=/; Secure; HttpOnly; SameSite=Lax");
+//! ```
+use std::{fmt, time::Duration};
+
+use crate::{header::typed::HttpDate, protocol};
+
+#[cfg(feature = "secure-cookies")]
+mod jar;
+#[cfg(feature = "secure-cookies")]
+pub use jar::{Jar, Key};
+
+#[cfg(test)]
+mod cookie_test;
+
+#[derive(Debug, PartialEq)]
+pub enum Error {
+  InvalidName(String),
+  InvalidValue(String),
+  InvalidAttribute(&'static str),
+  /// `Partitioned` cookies must be `Secure`.
+  InsecurePartitioned,
+  /// Signed and private cookies need a key, see `App::cookie_key`.
+  MissingKey
+}
+
+impl fmt::Display for Error {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Error::InvalidName(name) => write!(f, "Invalid cookie name: {name:?}"),
+      Error::InvalidValue(name) => write!(f, "Invalid value for cookie {name}"),
+      Error::InvalidAttribute(attribute) => write!(f, "Invalid cookie {attribute} attribute"),
+      Error::InsecurePartitioned => write!(f, "Partitioned cookies must be secure"),
+      Error::MissingKey => write!(f, "No cookie key is configured")
+    }
+  }
+}
+
+impl std::error::Error for Error {}
+
+// cookie-octet, see RFC 6265, section 4.1.1
+fn is_cookie_octet(byte: u8) -> bool {
+  matches!(byte, 0x21 | 0x23..=0x2b | 0x2d..=0x3a | 0x3c..=0x5b | 0x5d..=0x7e)
+}
+
+fn is_cookie_value(value: &str) -> bool {
+  let value = value
+    .strip_prefix('"')
+    .and_then(|v| v.strip_suffix('"'))
+    .unwrap_or(value);
+  value.bytes().all(is_cookie_octet)
+}
+
+// attribute values cannot end the attribute early
+fn is_attribute_value(value: &str) -> bool {
+  !value.is_empty() && protocol::is_field_value(value.as_bytes()) && !value.contains(';')
+}
+
+/// The cookies sent with a request, in the order they were sent.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Cookies {
+  pairs: Vec<(String, String)>
+}
+
+impl Cookies {
+  /// Parses `Cookie` header fields. Malformed pairs are skipped,
+  /// values wrapped in double quotes are unwrapped.
+  pub fn parse<'a>(fields: impl IntoIterator<Item = &'a String>) -> Cookies {
+    let pairs = fields
+      .into_iter()
+      .flat_map(|field| field.split(';'))
+      .filter_map(|pair| {
+        let (name, value) = pair.trim_matches([' ', '\t']).split_once('=')?;
+        if !protocol::is_token(name.as_bytes()) || !is_cookie_value(value) {
+          return None
+        }
+
+        let value = value
+          .strip_prefix('"')
+          .and_then(|v| v.strip_suffix('"'))
+          .unwrap_or(value);
+        Some((name.to_string(), value.to_string()))
+      })
+      .collect();
+
+    Cookies { pairs }
+  }
+
+  /// The value of the first cookie called `name`. Names are case-sensitive.
+  pub fn get(&self, name: &str) -> Option<&str> {
+    self.pairs
+      .iter()
+      .find(|(key, _)| key == name)
+      .map(|(_, value)| value.as_str())
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+    self.pairs
+      .iter()
+      .map(|(name, value)| (name.as_str(), value.as_str()))
+  }
+
+  pub fn len(&self) -> usize {
+    self.pairs.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.pairs.is_empty()
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SameSite {
+  Strict,
+  Lax,
+  /// Browsers only accept it on `Secure` cookies.
+  None
+}
+
+impl fmt::Display for SameSite {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      SameSite::Strict => write!(f, "Strict"),
+      SameSite::Lax => write!(f, "Lax"),
+      SameSite::None => write!(f, "None")
+    }
+  }
+}
+
+/// A cookie to send in a `Set-Cookie` header field, see RFC 6265, section 4.1.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cookie {
+  name: String,
+  value: String,
+  expires: Option<HttpDate>,
+  max_age: Option<u64>,
+  domain: Option<String>,
+  path: Option<String>,
+  secure: bool,
+  http_only: bool,
+  same_site: Option<SameSite>,
+  partitioned: bool
+}
+
+impl Cookie {
+  pub fn new(name: &str, value: &str) -> Cookie {
+    Cookie {
+      name: name.to_string(),
+      value: value.to_string(),
+      expires: None,
+      max_age: None,
+      domain: None,
+      path: None,
+      secure: false,
+      http_only: false,
+      same_site: None,
+      partitioned: false
+    }
+  }
+
+  /// A cookie that makes the client delete the cookie called `name`.
+  /// `Domain` and `Path` must be the ones it was set with.
+  pub fn removal(name: &str) -> Cookie {
+    Cookie::new(name, "")
+      .with_max_age(0)
+      .with_expires(HttpDate::from(std::time::UNIX_EPOCH))
+  }
+
+  pub fn with_value(mut self, value: &str) -> Cookie {
+    self.value = value.to_string();
+    self
+  }
+
+  pub fn with_expires(mut self, expires: HttpDate) -> Cookie {
+    self.expires = Some(expires);
+    self
+  }
+
+  /// Lifetime in seconds, it takes precedence over `Expires`.
+  pub fn with_max_age(mut self, seconds: u64) -> Cookie {
+    self.max_age = Some(seconds);
+    self
+  }
+
+  pub fn with_domain(mut self, domain: &str) -> Cookie {
+    self.domain = Some(domain.to_string());
+    self
+  }
+
+  pub fn with_path(mut self, path: &str) -> Cookie {
+    self.path = Some(path.to_string());
+    self
+  }
+
+  pub fn with_secure(mut self, secure: bool) -> Cookie {
+    self.secure = secure;
+    self
+  }
+
+  pub fn with_http_only(mut self, http_only: bool) -> Cookie {
+    self.http_only = http_only;
+    self
+  }
+
+  pub fn with_same_site(mut self, same_site: SameSite) -> Cookie {
+    self.same_site = Some(same_site);
+    self
+  }
+
+  /// Stores the cookie per top-level site (CHIPS), it must be `Secure`.
+  pub fn with_partitioned(mut self, partitioned: bool) -> Cookie {
+    self.partitioned = partitioned;
+    self
+  }
+
+  pub fn name(&self) -> &str {
+    &self.name
+  }
+
+  pub fn value(&self) -> &str {
+    &self.value
+  }
+
+  pub fn expires(&self) -> Option<HttpDate> {
+    self.expires
+  }
+
+  pub fn max_age(&self) -> Option<Duration> {
+    self.max_age.map(Duration::from_secs)
+  }
+
+  pub fn domain(&self) -> Option<&str> {
+    self.domain.as_deref()
+  }
+
+  pub fn path(&self) -> Option<&str> {
+    self.path.as_deref()
+  }
+
+  pub fn is_secure(&self) -> bool {
+    self.secure
+  }
+
+  pub fn is_http_only(&self) -> bool {
+    self.http_only
+  }
+
+  pub fn same_site(&self) -> Option<SameSite> {
+    self.same_site
+  }
+
+  pub fn is_partitioned(&self) -> bool {
+    self.partitioned
+  }
+
+  /// Checks that the cookie can be sent as is.
+  pub fn validate(&self) -> Result<(), Error> {
+    if !protocol::is_token(self.name.as_bytes()) {
+      return Err(Error::InvalidName(self.name.clone()))
+    }
+
+    if !is_cookie_value(&self.value) {
+      return Err(Error::InvalidValue(self.name.clone()))
+    }
+
+    if self.domain.as_deref().is_some_and(|d| !is_attribute_value(d)) {
+      return Err(Error::InvalidAttribute("Domain"))
+    }
+
+    if self.path.as_deref().is_some_and(|p| !is_attribute_value(p)) {
+      return Err(Error::InvalidAttribute("Path"))
+    }
+
+    if self.partitioned && !self.secure {
+      return Err(Error::InsecurePartitioned)
+    }
+    Ok(())
+  }
+}
+
+/// Serializes as a `Set-Cookie` field value.
+impl fmt::Display for Cookie {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}={}", self.name, self.value)?;
+
+    if let Some(expires) = self.expires {
+      write!(f, "; Expires={expires}")?;
+    }
+    if let Some(max_age) = self.max_age {
+      write!(f, "; Max-Age={max_age}")?;
+    }
+    if let Some(domain) = &self.domain {
+      write!(f, "; Domain={domain}")?;
+    }
+    if let Some(path) = &self.path {
+      write!(f, "; Path={path}")?;
+    }
+    if self.secure {
+      write!(f, "; Secure")?;
+    }
+    if self.http_only {
+      write!(f, "; HttpOnly")?;
+    }
+    if let Some(same_site) = self.same_site {
+      write!(f, "; SameSite={same_site}")?;
+    }
+    if self.partitioned {
+      write!(f, "; Partitioned")?;
+    }
+    Ok(())
+  }
+}
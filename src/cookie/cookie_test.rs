@@ -0,0 +1,141 @@
+use super::*;
+
+fn fields(fields: &[&str]) -> Vec<String> {
+  fields.iter().map(|v| v.to_string()).collect()
+}
+
+mod cookies_test {
+  use super::*;
+
+  #[test]
+  fn parse_test() {
+    let cookies = Cookies::parse(&fields(&["SID=31d4d96e407aad42; lang=en-US", "Theme=\"Dark\";empty="]));
+    assert_eq!(cookies.len(), 4);
+    assert_eq!(cookies.get("SID"), Some("31d4d96e407aad42"));
+    assert_eq!(cookies.get("lang"), Some("en-US"));
+    assert_eq!(cookies.get("Theme"), Some("Dark"));
+    assert_eq!(cookies.get("theme"), None);
+    assert_eq!(cookies.get("empty"), Some(""));
+  }
+
+  #[test]
+  fn malformed_pairs_test() {
+    let cookies = Cookies::parse(&fields(&["a=1; novalue; b c=2; d=\"3; e=x y; f=ok; =g"]));
+    assert_eq!(cookies.iter().collect::<Vec<_>>(), vec![("a", "1"), ("f", "ok")]);
+    assert!(Cookies::parse(&fields(&[""])).is_empty());
+  }
+
+  #[test]
+  fn first_value_wins_test() {
+    let cookies = Cookies::parse(&fields(&["a=1; a=2"]));
+    assert_eq!(cookies.get("a"), Some("1"));
+  }
+}
+
+mod set_cookie_test {
+  use std::time::{Duration, UNIX_EPOCH};
+  use super::*;
+
+  #[test]
+  fn attributes_test() {
+    let expires = HttpDate::from(UNIX_EPOCH + Duration::from_secs(784111777));
+    let cookie = Cookie::new("id", "a3fWa")
+      .with_expires(expires)
+      .with_max_age(60)
+      .with_domain("example.com")
+      .with_path("/docs")
+      .with_secure(true)
+      .with_http_only(true)
+      .with_same_site(SameSite::None)
+      .with_partitioned(true);
+
+    assert!(cookie.validate().is_ok());
+    assert_eq!(
+      cookie.to_string(),
+      "id=a3fWa; Expires=Sun, 06 Nov 1994 08:49:37 GMT; Max-Age=60; Domain=example.com; Path=/docs; Secure; HttpOnly; SameSite=None; Partitioned"
+    );
+    assert_eq!(cookie.max_age(), Some(Duration::from_secs(60)));
+  }
+
+  #[test]
+  fn removal_test() {
+    assert_eq!(Cookie::removal("id").to_string(), "id=; Expires=Thu, 01 Jan 1970 00:00:00 GMT; Max-Age=0");
+  }
+
+  #[test]
+  fn validate_test() {
+    assert_eq!(Cookie::new("a b", "1").validate(), Err(Error::InvalidName("a b".to_string())));
+    assert_eq!(Cookie::new("", "1").validate(), Err(Error::InvalidName("".to_string())));
+    assert!(Cookie::new("a", "\"quoted\"").validate().is_ok());
+
+    for value in ["a;b", "a b", "a,b", "a\\b", "\"a", "a\r\nSet-Cookie: b=c", "\u{e9}"] {
+      assert_eq!(Cookie::new("a", value).validate(), Err(Error::InvalidValue("a".to_string())), "Value should be rejected: {value:?}");
+    }
+
+    assert_eq!(Cookie::new("a", "1").with_path("/; Domain=evil").validate(), Err(Error::InvalidAttribute("Path")));
+    assert_eq!(Cookie::new("a", "1").with_domain("").validate(), Err(Error::InvalidAttribute("Domain")));
+    assert_eq!(Cookie::new("a", "1").with_partitioned(true).validate(), Err(Error::InsecurePartitioned));
+  }
+}
+
+#[cfg(feature = "secure-cookies")]
+mod jar_test {
+  use super::*;
+
+  fn jar() -> Jar {
+    Jar::new(Key::from_bytes(&[7; 64]).unwrap())
+  }
+
+  #[test]
+  fn key_test() {
+    assert!(Key::from_bytes(&[0; 32]).is_none());
+    assert!(Key::from_bytes(&[0; 65]).is_none());
+    assert_eq!(format!("{:?}", Key::generate()), "Key(..)");
+  }
+
+  #[test]
+  fn signed_test() {
+    let jar = jar();
+    let signed = jar.sign(Cookie::new("user", "42").with_path("/"));
+    assert!(signed.validate().is_ok());
+    assert_eq!(signed.path(), Some("/"));
+    assert_eq!(jar.verify("user", signed.value()), Some("42".to_string()));
+
+    // tampered value or signature, other cookie name or key
+    let (signature, _) = signed.value().split_at(44);
+    assert_eq!(jar.verify("user", &format!("{signature}43")), None);
+    assert_eq!(jar.verify("user", &format!("A{}", &signed.value()[1..])), None);
+    assert_eq!(jar.verify("admin", signed.value()), None);
+    assert_eq!(Jar::new(Key::generate()).verify("user", signed.value()), None);
+    assert_eq!(jar.verify("user", "42"), None);
+  }
+
+  #[test]
+  fn private_test() {
+    let jar = jar();
+    // `:` is not a base64 character, the value cannot show by chance
+    let private = jar.encrypt(Cookie::new("user", "id:42"));
+    assert!(private.validate().is_ok());
+    assert!(!private.value().contains("id:42"));
+    assert_eq!(jar.decrypt("user", private.value()), Some("id:42".to_string()));
+
+    // each encryption uses another nonce
+    assert_ne!(jar.encrypt(Cookie::new("user", "id:42")).value(), private.value());
+
+    assert_eq!(jar.decrypt("admin", private.value()), None);
+    assert_eq!(Jar::new(Key::generate()).decrypt("user", private.value()), None);
+    assert_eq!(jar.decrypt("user", "AAAA"), None);
+    assert_eq!(jar.decrypt("user", "not base64"), None);
+  }
+
+  #[test]
+  fn verify_all_test() {
+    let jar = jar();
+    let signed = jar.sign(Cookie::new("a", "1"));
+    let private = jar.encrypt(Cookie::new("b", "2"));
+    let cookies = Cookies::parse(&fields(&[&format!("a={}; b={}; c=3", signed.value(), private.value())]));
+
+    assert_eq!(jar.verify_all(&cookies).iter().collect::<Vec<_>>(), vec![("a", "1")]);
+    assert_eq!(jar.decrypt_all(&cookies).iter().collect::<Vec<_>>(), vec![("b", "2")]);
+  }
+}
@@ -0,0 +1,155 @@
+use std::fmt;
+
+use aes_gcm::{aead::{Aead, KeyInit, Payload}, Aes256Gcm, Nonce};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::{base64, random};
+use super::{Cookie, Cookies};
+
+const KEY_LENGTH: usize = 64;
+const NONCE_LENGTH: usize = 12;
+// the base64 length of a SHA-256 MAC
+const SIGNATURE_LENGTH: usize = 44;
+
+/// Secret material for signed and private cookies: one half
+/// authenticates, the other encrypts.
+#[derive(Clone)]
+pub struct Key {
+  signing: [u8; 32],
+  encryption: [u8; 32]
+}
+
+impl Key {
+  /// Uses exactly 64 bytes of random secret material.
+  pub fn from_bytes(bytes: &[u8]) -> Option<Key> {
+    if bytes.len() != KEY_LENGTH {
+      return None
+    }
+
+    let (signing, encryption) = bytes.split_at(KEY_LENGTH / 2);
+    Some(Key {
+      signing: signing.try_into().ok()?,
+      encryption: encryption.try_into().ok()?
+    })
+  }
+
+  /// Generates a key with the operating system random number generator.
+  /// Cookies sent with a generated key do not survive a restart.
+  pub fn generate() -> Key {
+    let mut bytes = [0; KEY_LENGTH];
+    random::fill(&mut bytes);
+    Key::from_bytes(&bytes).unwrap()
+  }
+}
+
+// the key must never end up in logs
+impl fmt::Debug for Key {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "Key(..)")
+  }
+}
+
+/// Signs or encrypts cookie values with a [`Key`].
+///
+/// Signed cookies can be read but not tampered with by the client,
+/// private cookies can be neither. The name is authenticated along with
+/// the value, so a value cannot be moved to another cookie.
+///
+/// ```
+/// use webserver::cookie::{Cookie, Jar, Key};
+///
+/// let jar = Jar::new(Key::generate());
+///
+/// let signed = jar.sign(Cookie::new("user", "42"));
+/// assert_eq!(jar.verify("user", signed.value()), Some("42".to_string()));
+/// assert_eq!(jar.verify("admin", signed.value()), None);
+///
+/// let private = jar.encrypt(Cookie::new("user", "42"));
+/// assert_eq!(jar.decrypt("user", private.value()), Some("42".to_string()));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Jar {
+  key: Key
+}
+
+impl Jar {
+  pub fn new(key: Key) -> Jar {
+    Jar { key }
+  }
+
+  fn mac(&self, name: &str, value: &str) -> Hmac<Sha256> {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(&self.key.signing).unwrap();
+    mac.update(name.as_bytes());
+    mac.update(b"=");
+    mac.update(value.as_bytes());
+    mac
+  }
+
+  /// Prefixes the value with its signature.
+  pub fn sign(&self, cookie: Cookie) -> Cookie {
+    let signature = base64::encode(&self.mac(cookie.name(), cookie.value()).finalize().into_bytes());
+    let value = format!("{signature}{}", cookie.value());
+    cookie.with_value(&value)
+  }
+
+  /// The original value of a signed cookie, `None` if it was tampered with.
+  pub fn verify(&self, name: &str, value: &str) -> Option<String> {
+    if !value.is_char_boundary(SIGNATURE_LENGTH) {
+      return None
+    }
+
+    let (signature, value) = value.split_at(SIGNATURE_LENGTH);
+    let signature = base64::decode(signature)?;
+    self.mac(name, value).verify_slice(&signature).ok()?;
+    Some(value.to_string())
+  }
+
+  /// Replaces the value with its authenticated encryption.
+  pub fn encrypt(&self, cookie: Cookie) -> Cookie {
+    let mut nonce = [0; NONCE_LENGTH];
+    random::fill(&mut nonce);
+
+    let cipher = Aes256Gcm::new_from_slice(&self.key.encryption).unwrap();
+    let payload = Payload { msg: cookie.value().as_bytes(), aad: cookie.name().as_bytes() };
+    // only fails for messages far larger than a cookie
+    let encrypted = cipher.encrypt(Nonce::from_slice(&nonce), payload).unwrap();
+
+    let value = base64::encode(&[&nonce[..], &encrypted].concat());
+    cookie.with_value(&value)
+  }
+
+  /// The original value of a private cookie, `None` if it was tampered with.
+  pub fn decrypt(&self, name: &str, value: &str) -> Option<String> {
+    let decoded = base64::decode(value)?;
+    if decoded.len() < NONCE_LENGTH {
+      return None
+    }
+
+    let (nonce, encrypted) = decoded.split_at(NONCE_LENGTH);
+    let cipher = Aes256Gcm::new_from_slice(&self.key.encryption).unwrap();
+    let payload = Payload { msg: encrypted, aad: name.as_bytes() };
+    let decrypted = cipher.decrypt(Nonce::from_slice(nonce), payload).ok()?;
+    String::from_utf8(decrypted).ok()
+  }
+
+  /// Keeps the signed cookies that are authentic, with their original value.
+  pub fn verify_all(&self, cookies: &Cookies) -> Cookies {
+    Cookies {
+      pairs: cookies
+        .iter()
+        .filter_map(|(name, value)| Some((name.to_string(), self.verify(name, value)?)))
+        .collect()
+    }
+  }
+
+  /// Keeps the private cookies that are authentic, with their original value.
+  pub fn decrypt_all(&self, cookies: &Cookies) -> Cookies {
+    Cookies {
+      pairs: cookies
+        .iter()
+        .filter_map(|(name, value)| Some((name.to_string(), self.decrypt(name, value)?)))
+        .collect()
+    }
+  }
+}
@@ -0,0 +1,233 @@
+//! Cross-origin resource sharing, see the Fetch standard, section 3.2.
+//!
+//! ```no_run
+//! use std::time::Duration;
+//! use webserver::{App, cors::{Cors, Origins}};
+//!
+//! let mut app = App::new();
+//! app.all("/api/**", Cors::new()
+//!   .with_origins(Origins::List(vec!["https://example.com".to_string(), "https://admin.example.com".to_string()]))
+//!   .with_methods(&["GET", "POST"])
+//!   .with_headers(&["Content-Type", "Authorization"])
+//!   .with_credentials(true)
+//!   .with_max_age(Duration::from_secs(600))
+//!   .middleware());
+//! app.listen("127.0.0.1:8080").unwrap();
+//! ```
+use std::{error::Error, fmt, rc::Rc, time::Duration};
+
+use crate::{header::typed::Vary, protocol::StatusCode, request::{Method, Request}, router::Context, Handle, HookType, Return};
+
+#[cfg(test)]
+mod cors_test;
+
+/// The origins allowed to read responses.
+#[derive(Clone)]
+pub enum Origins {
+  /// Any origin, answered with `*` unless credentials are allowed.
+  Any,
+  Exact(String),
+  List(Vec<String>),
+  /// The origins the function returns `true` for.
+  Predicate(Rc<dyn Fn(&str) -> bool>)
+}
+
+impl Origins {
+  pub fn predicate(predicate: impl Fn(&str) -> bool + 'static) -> Origins {
+    Origins::Predicate(Rc::new(predicate))
+  }
+
+  /// Whether `origin`, the serialized origin of the request, is allowed.
+  pub fn allows(&self, origin: &str) -> bool {
+    match self {
+      Origins::Any => true,
+      Origins::Exact(allowed) => allowed == origin,
+      Origins::List(allowed) => allowed.iter().any(|allowed| allowed == origin),
+      Origins::Predicate(predicate) => predicate(origin)
+    }
+  }
+}
+
+impl fmt::Debug for Origins {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Origins::Any => f.write_str("Any"),
+      Origins::Exact(origin) => f.debug_tuple("Exact").field(origin).finish(),
+      Origins::List(origins) => f.debug_tuple("List").field(origins).finish(),
+      Origins::Predicate(_) => f.write_str("Predicate")
+    }
+  }
+}
+
+/// The CORS middleware, see the [module documentation](self).
+///
+/// Preflight requests are answered with `204 No Content`, without
+/// reaching the main handlers, so that no `OPTIONS` route is needed.
+/// Other responses to an allowed origin are sent with
+/// `Access-Control-Allow-Origin` and the fields configured, and with
+/// `Vary: Origin` whenever they depend on the origin.
+#[derive(Debug, Clone)]
+pub struct Cors {
+  origins: Origins,
+  methods: Vec<String>,
+  headers: Vec<String>,
+  exposed_headers: Vec<String>,
+  credentials: bool,
+  max_age: Option<Duration>
+}
+
+impl Default for Cors {
+  fn default() -> Self {
+    Cors::new()
+  }
+}
+
+fn strings(values: &[&str]) -> Vec<String> {
+  values.iter().map(|value| value.to_string()).collect()
+}
+
+// the elements of a comma separated list field
+fn elements(value: &str) -> impl Iterator<Item = &str> {
+  value
+    .split(',')
+    .map(|element| element.trim_matches([' ', '\t']))
+    .filter(|element| !element.is_empty())
+}
+
+impl Cors {
+  /// Any origin may send `GET` and `POST` requests, without credentials
+  /// and with the CORS-safelisted request fields only.
+  pub fn new() -> Cors {
+    Cors {
+      origins: Origins::Any,
+      methods: strings(&["GET", "POST"]),
+      headers: Vec::new(),
+      exposed_headers: Vec::new(),
+      credentials: false,
+      max_age: None
+    }
+  }
+
+  pub fn with_origins(mut self, origins: Origins) -> Cors {
+    self.origins = origins;
+    self
+  }
+
+  /// The methods allowed in preflight requests, compared case-sensitively.
+  pub fn with_methods(mut self, methods: &[&str]) -> Cors {
+    self.methods = strings(methods);
+    self
+  }
+
+  /// The request fields allowed in preflight requests, `*` allowing any
+  /// field when credentials are not allowed.
+  pub fn with_headers(mut self, headers: &[&str]) -> Cors {
+    self.headers = strings(headers);
+    self
+  }
+
+  /// The response fields scripts may read, besides the CORS-safelisted ones.
+  pub fn with_exposed_headers(mut self, headers: &[&str]) -> Cors {
+    self.exposed_headers = strings(headers);
+    self
+  }
+
+  /// Whether requests may be sent with cookies and `Authorization`.
+  pub fn with_credentials(mut self, credentials: bool) -> Cors {
+    self.credentials = credentials;
+    self
+  }
+
+  /// How long preflight responses may be cached.
+  pub fn with_max_age(mut self, max_age: Duration) -> Cors {
+    self.max_age = Some(max_age);
+    self
+  }
+
+  pub fn middleware(self) -> Handle {
+    Handle::middleware(move |ctx: &mut Context| {
+      match ctx.hook() {
+        HookType::Before if is_preflight(ctx.req) => return self.preflight(ctx),
+        HookType::Before => (),
+        _ => self.decorate(ctx)
+      }
+      Ok(Return::Next)
+    })
+  }
+
+  // the response does not depend on the origin when any is allowed with `*`
+  fn is_wildcard(&self) -> bool {
+    matches!(self.origins, Origins::Any) && !self.credentials
+  }
+
+  fn preflight(&self, ctx: &mut Context) -> Result<Return, Box<dyn Error>> {
+    let headers = ctx.req.headers();
+    let is_allowed_method = headers
+      .get("access-control-request-method")
+      .is_some_and(|method| self.methods.iter().any(|allowed| allowed == method.trim_matches([' ', '\t'])));
+
+    let any_header = !self.credentials && self.headers.iter().any(|header| header == "*");
+    let requested: Vec<String> = headers
+      .get_all("access-control-request-headers")
+      .into_iter()
+      .flatten()
+      .flat_map(|value| elements(value))
+      .map(String::from)
+      .collect();
+    let are_allowed_headers = any_header || requested
+      .iter()
+      .all(|name| self.headers.iter().any(|allowed| allowed.eq_ignore_ascii_case(name)));
+
+    // the client fails the preflight without these fields
+    let res = &mut *ctx.res;
+    if is_allowed_method && are_allowed_headers {
+      res.headers.set("Access-Control-Allow-Methods", self.methods.join(", "));
+      if !requested.is_empty() {
+        res.headers.set("Access-Control-Allow-Headers", requested.join(", "));
+      }
+      if let Some(max_age) = self.max_age {
+        res.headers.set("Access-Control-Max-Age", max_age.as_secs().to_string());
+      }
+    }
+
+    let mut vary = res.headers.typed::<Vary>().unwrap_or(Vary::Fields(Vec::new()));
+    vary.insert("Access-Control-Request-Method");
+    vary.insert("Access-Control-Request-Headers");
+    res.headers.set_typed(&vary);
+
+    res.status(StatusCode::NoContent).send_headers()
+  }
+
+  fn decorate(&self, ctx: &mut Context) {
+    let res = &mut *ctx.res;
+    if !self.is_wildcard() {
+      let mut vary = res.headers.typed::<Vary>().unwrap_or(Vary::Fields(Vec::new()));
+      vary.insert("Origin");
+      res.headers.set_typed(&vary);
+    }
+
+    let Some(origin) = ctx.req.headers().get("origin").filter(|origin| self.origins.allows(origin)) else {
+      return
+    };
+
+    let allowed = match self.is_wildcard() {
+      true => "*".to_string(),
+      false => origin.clone()
+    };
+    res.headers.set("Access-Control-Allow-Origin", allowed);
+    if self.credentials {
+      res.headers.set("Access-Control-Allow-Credentials", "true".to_string());
+    }
+    if !self.exposed_headers.is_empty() && !is_preflight(ctx.req) {
+      res.headers.set("Access-Control-Expose-Headers", self.exposed_headers.join(", "));
+    }
+  }
+}
+
+// see the Fetch standard, section 3.2.2
+fn is_preflight(req: &Request) -> bool {
+  let headers = req.headers();
+  req.location().0 == Method::Options
+    && headers.contains("origin")
+    && headers.contains("access-control-request-method")
+}
@@ -0,0 +1,129 @@
+use crate::{app::app_test::{header, request}, error::HttpError, App};
+use super::*;
+
+const ORIGIN: &str = "https://example.com";
+
+fn app(cors: Cors) -> App {
+  let mut app = App::new();
+  app.all("*", cors.middleware());
+  app.get("/items", Handle::main(|ctx| ctx.res.send_body(b"items".to_vec())));
+  app.options("/items", Handle::main(|ctx| ctx.res.send_body(b"options".to_vec())));
+  app.get("/fail", Handle::main(|_| Err(Box::new(HttpError::new(StatusCode::Conflict, "Already exists")))));
+  app
+}
+
+fn preflight(app: &App, origin: &str, method: &str, headers: Option<&str>) -> String {
+  let headers = headers.map_or(String::new(), |h| format!("Access-Control-Request-Headers: {h}\r\n"));
+  request(app, "OPTIONS", "/items", &format!("Origin: {origin}\r\nAccess-Control-Request-Method: {method}\r\n{headers}"))
+}
+
+mod preflight_test {
+  use super::*;
+
+  #[test]
+  fn allowed_test() {
+    let cors = Cors::new()
+      .with_origins(Origins::Exact(ORIGIN.to_string()))
+      .with_methods(&["GET", "POST"])
+      .with_headers(&["Content-Type", "X-Token"])
+      .with_max_age(Duration::from_secs(600));
+    let response = preflight(&app(cors), ORIGIN, "POST", Some("x-token,content-type"));
+
+    assert!(response.starts_with("HTTP/1.1 204 No Content\r\n"));
+    assert_eq!(header(&response, "Access-Control-Allow-Origin"), Some(ORIGIN));
+    assert_eq!(header(&response, "Access-Control-Allow-Methods"), Some("GET, POST"));
+    assert_eq!(header(&response, "Access-Control-Allow-Headers"), Some("x-token, content-type"));
+    assert_eq!(header(&response, "Access-Control-Max-Age"), Some("600"));
+    assert_eq!(header(&response, "Access-Control-Allow-Credentials"), None);
+    assert_eq!(header(&response, "Vary"), Some("Access-Control-Request-Method, Access-Control-Request-Headers, Origin"));
+    assert_eq!(header(&response, "Content-Length"), None);
+  }
+
+  #[test]
+  fn rejected_test() {
+    let app = app(Cors::new().with_origins(Origins::Exact(ORIGIN.to_string())).with_headers(&["X-Token"]));
+
+    let response = preflight(&app, ORIGIN, "DELETE", None);
+    assert!(response.starts_with("HTTP/1.1 204 No Content\r\n"));
+    assert_eq!(header(&response, "Access-Control-Allow-Methods"), None);
+
+    let response = preflight(&app, ORIGIN, "POST", Some("X-Token, X-Other"));
+    assert_eq!(header(&response, "Access-Control-Allow-Methods"), None);
+    assert_eq!(header(&response, "Access-Control-Allow-Headers"), None);
+
+    let response = preflight(&app, "https://evil.example", "GET", None);
+    assert_eq!(header(&response, "Access-Control-Allow-Origin"), None);
+  }
+
+  #[test]
+  fn any_header_test() {
+    let response = preflight(&app(Cors::new().with_headers(&["*"])), ORIGIN, "GET", Some("X-Anything"));
+    assert_eq!(header(&response, "Access-Control-Allow-Headers"), Some("X-Anything"));
+
+    // `*` is a field name when credentials are allowed
+    let cors = Cors::new().with_headers(&["*"]).with_credentials(true);
+    let response = preflight(&app(cors), ORIGIN, "GET", Some("X-Anything"));
+    assert_eq!(header(&response, "Access-Control-Allow-Headers"), None);
+  }
+
+  #[test]
+  fn options_route_test() {
+    // without `Access-Control-Request-Method`, it is not a preflight request
+    let response = request(&app(Cors::new()), "OPTIONS", "/items", &format!("Origin: {ORIGIN}\r\n"));
+    assert!(response.ends_with("options"));
+    assert_eq!(header(&response, "Access-Control-Allow-Origin"), Some("*"));
+  }
+}
+
+mod actual_test {
+  use super::*;
+
+  #[test]
+  fn wildcard_test() {
+    let response = request(&app(Cors::new()), "GET", "/items", &format!("Origin: {ORIGIN}\r\n"));
+    assert!(response.ends_with("items"));
+    assert_eq!(header(&response, "Access-Control-Allow-Origin"), Some("*"));
+    assert_eq!(header(&response, "Vary"), None);
+  }
+
+  #[test]
+  fn origins_test() {
+    let list = Origins::List(vec!["https://a.example".to_string(), ORIGIN.to_string()]);
+    let predicate = Origins::predicate(|origin| origin.ends_with(".example.com") || origin == ORIGIN);
+    for origins in [Origins::Exact(ORIGIN.to_string()), list, predicate] {
+      let app = app(Cors::new().with_origins(origins.clone()));
+      let response = request(&app, "GET", "/items", &format!("Origin: {ORIGIN}\r\n"));
+      assert_eq!(header(&response, "Access-Control-Allow-Origin"), Some(ORIGIN), "for {origins:?}");
+      assert_eq!(header(&response, "Vary"), Some("Origin"));
+
+      let response = request(&app, "GET", "/items", "Origin: https://evil.example\r\n");
+      assert_eq!(header(&response, "Access-Control-Allow-Origin"), None);
+      // caches must not reuse a response for another origin
+      assert_eq!(header(&response, "Vary"), Some("Origin"));
+
+      let response = request(&app, "GET", "/items", "");
+      assert_eq!(header(&response, "Vary"), Some("Origin"));
+    }
+  }
+
+  #[test]
+  fn credentials_test() {
+    let cors = Cors::new().with_credentials(true).with_exposed_headers(&["X-Total", "ETag"]);
+    let response = request(&app(cors), "GET", "/items", &format!("Origin: {ORIGIN}\r\n"));
+    assert_eq!(header(&response, "Access-Control-Allow-Origin"), Some(ORIGIN));
+    assert_eq!(header(&response, "Access-Control-Allow-Credentials"), Some("true"));
+    assert_eq!(header(&response, "Access-Control-Expose-Headers"), Some("X-Total, ETag"));
+    assert_eq!(header(&response, "Vary"), Some("Origin"));
+  }
+
+  #[test]
+  fn error_test() {
+    // so that the client can read why the request failed
+    let cors = Cors::new().with_origins(Origins::Exact(ORIGIN.to_string())).with_credentials(true);
+    let response = request(&app(cors), "GET", "/fail", &format!("Origin: {ORIGIN}\r\n"));
+    assert!(response.starts_with("HTTP/1.1 409 Conflict\r\n"));
+    assert_eq!(header(&response, "Access-Control-Allow-Origin"), Some(ORIGIN));
+    assert_eq!(header(&response, "Access-Control-Allow-Credentials"), Some("true"));
+    assert_eq!(header(&response, "Vary"), Some("Origin"));
+  }
+}
@@ -0,0 +1,50 @@
+use std::fmt;
+
+use crate::protocol::StatusCode;
+
+/// An error answered with its own status code instead of
+/// `500 Internal Server Error` when returned from a handler.
+///
+/// ```
+/// use webserver::{error::HttpError, protocol::StatusCode};
+///
+/// let err = HttpError::new(StatusCode::NotFound, "No such user");
+/// assert_eq!(err.status(), &StatusCode::NotFound);
+/// assert_eq!(err.to_string(), "No such user");
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct HttpError {
+  status: StatusCode,
+  message: String
+}
+
+impl HttpError {
+  pub fn new(status: StatusCode, message: impl Into<String>) -> HttpError {
+    HttpError {
+      status,
+      message: message.into()
+    }
+  }
+
+  /// Creates an error whose message is the status reason phrase.
+  pub fn from_status(status: StatusCode) -> HttpError {
+    let message = status.reason_phrase().to_string();
+    HttpError::new(status, message)
+  }
+
+  pub fn status(&self) -> &StatusCode {
+    &self.status
+  }
+
+  pub fn message(&self) -> &str {
+    &self.message
+  }
+}
+
+impl fmt::Display for HttpError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.message)
+  }
+}
+
+impl std::error::Error for HttpError {}
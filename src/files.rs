@@ -0,0 +1,247 @@
+//! Static files, served from a directory.
+//!
+//! ```no_run
+//! use webserver::{App, files::serve_dir};
+//!
+//! let mut app = App::new();
+//! // `/static/css/site.css` is served from `./public/css/site.css`
+//! app.get("/static/**", serve_dir("./public"));
+//! app.listen("127.0.0.1:8080").unwrap();
+//! ```
+use std::{collections::VecDeque, error::Error, fs::{File, Metadata}, io::{self, Cursor, Read, Seek, SeekFrom}, ops, path::{Path, PathBuf}, time::UNIX_EPOCH};
+
+use crate::{conditional::{self, Precondition}, error::HttpError, form, header::typed::{ETag, HttpDate, LastModified, Range}, protocol::StatusCode, random, request::Request, router::Context, Handle, Return};
+
+mod mime;
+pub use mime::mime_type;
+
+#[cfg(test)]
+mod files_test;
+
+// more ranges are answered with the whole file
+const MAX_RANGES: usize = 16;
+// more than two ranges shorter than this on average are answered
+// with the whole file, their part heads outweighing them
+const MIN_AVERAGE_RANGE: u64 = 128;
+
+/// Serves the files under `root`, mapped from the path matched by the
+/// trailing `**` of the route. It must be registered on such a route.
+///
+/// Paths that could lead out of `root`, through `..` segments or
+/// symbolic links, are rejected with `403 Forbidden`. Directories, the
+/// root included, are not listed and answer `404 Not Found`. Files are
+/// sent with `Last-Modified` and `ETag`, so that conditional requests
+/// are answered with `304 Not Modified` or `412 Precondition Failed`,
+/// and byte ranges are supported.
+pub fn serve_dir(root: impl Into<PathBuf>) -> Handle {
+  let root = root.into();
+  Handle::main(move |ctx| serve(&root, ctx))
+}
+
+fn serve(root: &Path, ctx: &mut Context) -> Result<Return, Box<dyn Error>> {
+  let wildcard = ctx.wildcard().ok_or("serve_dir must be registered on a route ending with /**")?;
+  let path = resolve(root, wildcard)?;
+
+  let not_found = || HttpError::from_status(StatusCode::NotFound);
+  let file = File::open(&path).map_err(|_| not_found())?;
+  let metadata = file.metadata()?;
+  if !metadata.is_file() {
+    return Err(Box::new(not_found()))
+  }
+
+  let length = metadata.len();
+  let last_modified = metadata.modified().ok().map(HttpDate::from);
+  let etag = etag(&metadata);
+
+  let res = &mut *ctx.res;
+  res.headers.set("Accept-Ranges", "bytes".to_string());
+  if let Some(etag) = &etag {
+    res.headers.set_typed(etag);
+  }
+  if let Some(last_modified) = last_modified {
+    res.headers.set_typed(&LastModified(last_modified));
+  }
+
+  match conditional::evaluate(ctx.req, etag.as_ref(), last_modified) {
+    Precondition::NotModified => return res.status(StatusCode::NotModified).send_headers(),
+    Precondition::Failed => return res.status(StatusCode::PreconditionFailed).send_headers(),
+    Precondition::Passed => ()
+  }
+
+  let content_type = mime_type(&path);
+  let Some(ranges) = ranges(ctx.req, length, etag.as_ref(), last_modified) else {
+    return res.content_type(content_type).send_stream(file, Some(length))
+  };
+
+  match &ranges[..] {
+    [] => {
+      res.headers.set("Content-Range", format!("bytes */{length}"));
+      res.status(StatusCode::RangeNotSatisfiable).send_headers()
+    },
+    [range] => {
+      res.headers.set("Content-Range", content_range(range, length));
+      res
+        .status(StatusCode::PartialContent)
+        .content_type(content_type)
+        .send_stream(Sections::new(file, vec![Section::File(range.clone())]), Some(range.end - range.start))
+    },
+    ranges => {
+      let boundary = random::hex(16);
+      let mut sections = Vec::new();
+      for (index, range) in ranges.iter().enumerate() {
+        let separator = if index == 0 { "" } else { "\r\n" };
+        let head = format!(
+          "{separator}--{boundary}\r\nContent-Type: {content_type}\r\nContent-Range: {}\r\n\r\n",
+          content_range(range, length)
+        );
+        sections.push(Section::Bytes(Cursor::new(head.into_bytes())));
+        sections.push(Section::File(range.clone()));
+      }
+      sections.push(Section::Bytes(Cursor::new(format!("\r\n--{boundary}--\r\n").into_bytes())));
+
+      let body_length = sections.iter().map(Section::len).sum();
+      res
+        .status(StatusCode::PartialContent)
+        .content_type(&format!("multipart/byteranges; boundary={boundary}"))
+        .send_stream(Sections::new(file, sections), Some(body_length))
+    }
+  }
+}
+
+// maps the request path to a file under `root`, rejecting
+// anything that could lead out of it
+fn resolve(root: &Path, wildcard: &str) -> Result<PathBuf, HttpError> {
+  let forbidden = || HttpError::from_status(StatusCode::Forbidden);
+  let not_found = || HttpError::from_status(StatusCode::NotFound);
+
+  let decoded = String::from_utf8(form::percent_decode(wildcard.as_bytes())).map_err(|_| not_found())?;
+  // the root itself, a directory
+  if decoded.is_empty() {
+    return Err(not_found())
+  }
+
+  let mut path = root.to_path_buf();
+  for segment in decoded.split('/') {
+    let is_safe = !segment.is_empty()
+      && segment != "."
+      && segment != ".."
+      && !segment.contains(['\\', '\0'])
+      && !(cfg!(windows) && segment.contains(':'));
+
+    if !is_safe {
+      return Err(forbidden())
+    }
+    path.push(segment);
+  }
+
+  // symbolic links must not lead out of the root either
+  let root = root.canonicalize().map_err(|_| not_found())?;
+  let path = path.canonicalize().map_err(|_| not_found())?;
+  if !path.starts_with(&root) {
+    return Err(forbidden())
+  }
+  Ok(path)
+}
+
+// a strong validator, as a file with the same modification time
+// and length is assumed to be unchanged
+fn etag(metadata: &Metadata) -> Option<ETag> {
+  let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+  ETag::strong(&format!("{:x}-{:x}", modified.as_nanos(), metadata.len())).ok()
+}
+
+// the satisfiable ranges requested, `None` when the whole file is sent
+fn ranges(req: &Request, length: u64, etag: Option<&ETag>, last_modified: Option<HttpDate>) -> Option<Vec<ops::Range<u64>>> {
+  let range = req.headers().typed::<Range>()?;
+  if !conditional::is_range_fresh(req, etag, last_modified) {
+    return None
+  }
+
+  if range.ranges().len() > MAX_RANGES {
+    return None
+  }
+
+  let mut ranges = range.resolve(length);
+  let requested: u64 = ranges.iter().map(|range| range.end - range.start).sum();
+  // overlapping and adjacent ranges are sent as one
+  ranges.sort_by_key(|range| range.start);
+  let mut merged: Vec<ops::Range<u64>> = Vec::with_capacity(ranges.len());
+  for range in ranges {
+    match merged.last_mut() {
+      Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+      _ => merged.push(range)
+    }
+  }
+
+  // ranges asking for more bytes than the file has, or for many small
+  // pieces of it, are ignored, see RFC 9110, section 14.2
+  let total: u64 = merged.iter().map(|range| range.end - range.start).sum();
+  if requested > length || (merged.len() > 2 && total / (merged.len() as u64) < MIN_AVERAGE_RANGE) {
+    return None
+  }
+  Some(merged)
+}
+
+fn content_range(range: &ops::Range<u64>, length: u64) -> String {
+  format!("bytes {}-{}/{length}", range.start, range.end - 1)
+}
+
+enum Section {
+  Bytes(Cursor<Vec<u8>>),
+  File(ops::Range<u64>)
+}
+
+impl Section {
+  fn len(&self) -> u64 {
+    match self {
+      Section::Bytes(bytes) => bytes.get_ref().len() as u64,
+      Section::File(range) => range.end - range.start
+    }
+  }
+}
+
+// reads byte strings and ranges of a file one after the other
+struct Sections {
+  file: File,
+  sections: VecDeque<Section>,
+  // the file position, when known
+  position: Option<u64>
+}
+
+impl Sections {
+  fn new(file: File, sections: Vec<Section>) -> Sections {
+    Sections { file, sections: sections.into(), position: None }
+  }
+}
+
+impl Read for Sections {
+  fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+    while let Some(section) = self.sections.front_mut() {
+      let read = match section {
+        Section::Bytes(bytes) => bytes.read(out)?,
+        Section::File(range) if range.is_empty() => 0,
+        Section::File(range) => {
+          if self.position != Some(range.start) {
+            self.file.seek(SeekFrom::Start(range.start))?;
+          }
+
+          let max = (range.end - range.start).min(out.len() as u64) as usize;
+          let read = self.file.read(&mut out[..max])?;
+          if read == 0 && max > 0 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "File was truncated"))
+          }
+
+          range.start += read as u64;
+          self.position = Some(range.start);
+          read
+        }
+      };
+
+      if read > 0 || out.is_empty() {
+        return Ok(read)
+      }
+      self.sections.pop_front();
+    }
+    Ok(0)
+  }
+}
@@ -0,0 +1,255 @@
+use std::{fs, path::PathBuf};
+
+use crate::{app::app_test::{body, get, header}, App};
+use super::*;
+
+const CONTENT: &str = "0123456789abcdefghij";
+
+// a fresh directory with `public/index.html`, `public/css/site.css`
+// and `secret.txt` next to `public`
+fn fixture() -> PathBuf {
+  let dir = std::env::temp_dir().join(format!("webserver-files-{}", random::hex(8)));
+  fs::create_dir_all(dir.join("public/css")).unwrap();
+  fs::write(dir.join("public/index.html"), CONTENT).unwrap();
+  fs::write(dir.join("public/css/site.css"), "body {}").unwrap();
+  fs::write(dir.join("public/my file.txt"), "spaced").unwrap();
+  fs::write(dir.join("secret.txt"), "secret").unwrap();
+  dir
+}
+
+fn app(dir: &Path) -> App {
+  let mut app = App::new();
+  app.get("/static/**", serve_dir(dir.join("public")));
+  app
+}
+
+mod serve_test {
+  use super::*;
+
+  #[test]
+  fn file_test() {
+    let dir = fixture();
+    let app = app(&dir);
+
+    let response = get(&app, "/static/index.html", "");
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert_eq!(header(&response, "Content-Type"), Some("text/html; charset=utf-8"));
+    assert_eq!(header(&response, "Content-Length"), Some("20"));
+    assert_eq!(header(&response, "Accept-Ranges"), Some("bytes"));
+    assert!(header(&response, "ETag").is_some());
+    assert!(header(&response, "Last-Modified").is_some());
+    assert_eq!(body(&response), CONTENT);
+
+    let response = get(&app, "/static/css/site.css", "");
+    assert_eq!(header(&response, "Content-Type"), Some("text/css; charset=utf-8"));
+    assert_eq!(body(&response), "body {}");
+
+    assert_eq!(body(&get(&app, "/static/my%20file.txt", "")), "spaced");
+    fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[test]
+  fn not_found_test() {
+    let dir = fixture();
+    let app = app(&dir);
+    assert!(get(&app, "/static/missing.html", "").starts_with("HTTP/1.1 404 Not Found\r\n"));
+    assert!(get(&app, "/static/css", "").starts_with("HTTP/1.1 404 Not Found\r\n"));
+    for path in ["/static", "/static/"] {
+      assert!(get(&app, path, "").starts_with("HTTP/1.1 404 Not Found\r\n"), "for {path}");
+    }
+
+    // the root itself is a directory, which is not listed
+    let mut app = App::new();
+    app.get("/**", serve_dir(dir.join("public")));
+    for path in ["/", "//"] {
+      assert!(get(&app, path, "").starts_with("HTTP/1.1 404 Not Found\r\n"), "for {path}");
+    }
+    fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[test]
+  fn traversal_test() {
+    let dir = fixture();
+    let app = app(&dir);
+    for path in ["/static/../secret.txt", "/static/%2e%2e/secret.txt", "/static/css/%2E%2E%2F..%2Fsecret.txt", "/static/.%2fcss/site.css"] {
+      let response = get(&app, path, "");
+      assert!(response.starts_with("HTTP/1.1 403 Forbidden\r\n"), "{path} should be forbidden");
+    }
+    fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn symlink_test() {
+    let dir = fixture();
+    std::os::unix::fs::symlink(dir.join("secret.txt"), dir.join("public/escape.txt")).unwrap();
+    std::os::unix::fs::symlink(dir.join("public/index.html"), dir.join("public/inside.html")).unwrap();
+
+    let app = app(&dir);
+    assert!(get(&app, "/static/escape.txt", "").starts_with("HTTP/1.1 403 Forbidden\r\n"));
+    assert_eq!(body(&get(&app, "/static/inside.html", "")), CONTENT);
+    fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[test]
+  fn mime_type_test() {
+    assert_eq!(mime_type(Path::new("a/b.JS")), "text/javascript; charset=utf-8");
+    assert_eq!(mime_type(Path::new("logo.svg")), "image/svg+xml");
+    assert_eq!(mime_type(Path::new("archive.tar.gz")), "application/gzip");
+    assert_eq!(mime_type(Path::new("README")), "application/octet-stream");
+    assert_eq!(mime_type(Path::new("data.unknown")), "application/octet-stream");
+  }
+}
+
+mod conditional_test {
+  use super::*;
+
+  #[test]
+  fn if_none_match_test() {
+    let dir = fixture();
+    let app = app(&dir);
+    let etag = header(&get(&app, "/static/index.html", ""), "ETag").unwrap().to_string();
+
+    let response = get(&app, "/static/index.html", &format!("If-None-Match: \"other\", {etag}\r\n"));
+    assert!(response.starts_with("HTTP/1.1 304 Not Modified\r\n"));
+    assert_eq!(header(&response, "ETag"), Some(etag.as_str()));
+    assert_eq!(header(&response, "Content-Length"), None);
+    assert_eq!(body(&response), "");
+
+    let response = get(&app, "/static/index.html", "If-None-Match: \"other\"\r\n");
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+    fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[test]
+  fn if_modified_since_test() {
+    let dir = fixture();
+    let app = app(&dir);
+    let last_modified = header(&get(&app, "/static/index.html", ""), "Last-Modified").unwrap().to_string();
+
+    let response = get(&app, "/static/index.html", &format!("If-Modified-Since: {last_modified}\r\n"));
+    assert!(response.starts_with("HTTP/1.1 304 Not Modified\r\n"));
+
+    let response = get(&app, "/static/index.html", "If-Modified-Since: Thu, 01 Jan 1970 00:00:00 GMT\r\n");
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+
+    // If-None-Match takes precedence
+    let headers = format!("If-None-Match: \"other\"\r\nIf-Modified-Since: {last_modified}\r\n");
+    assert!(get(&app, "/static/index.html", &headers).starts_with("HTTP/1.1 200 OK\r\n"));
+    fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[test]
+  fn if_match_test() {
+    let dir = fixture();
+    let app = app(&dir);
+    let etag = header(&get(&app, "/static/index.html", ""), "ETag").unwrap().to_string();
+
+    assert!(get(&app, "/static/index.html", &format!("If-Match: {etag}\r\n")).starts_with("HTTP/1.1 200 OK\r\n"));
+    let response = get(&app, "/static/index.html", "If-Match: \"other\"\r\n");
+    assert!(response.starts_with("HTTP/1.1 412 Precondition Failed\r\n"));
+    assert_eq!(body(&response), "");
+    fs::remove_dir_all(dir).unwrap();
+  }
+}
+
+mod range_test {
+  use super::*;
+
+  #[test]
+  fn single_range_test() {
+    let dir = fixture();
+    let app = app(&dir);
+
+    let response = get(&app, "/static/index.html", "Range: bytes=2-5\r\n");
+    assert!(response.starts_with("HTTP/1.1 206 Partial Content\r\n"));
+    assert_eq!(header(&response, "Content-Range"), Some("bytes 2-5/20"));
+    assert_eq!(header(&response, "Content-Length"), Some("4"));
+    assert_eq!(body(&response), "2345");
+
+    let response = get(&app, "/static/index.html", "Range: bytes=-3\r\n");
+    assert_eq!(header(&response, "Content-Range"), Some("bytes 17-19/20"));
+    assert_eq!(body(&response), "hij");
+
+    // unsatisfiable ranges are ignored while one is satisfiable
+    let response = get(&app, "/static/index.html", "Range: bytes=50-60, 18-\r\n");
+    assert_eq!(body(&response), "ij");
+    fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[test]
+  fn multiple_ranges_test() {
+    let dir = fixture();
+    let app = app(&dir);
+
+    let response = get(&app, "/static/index.html", "Range: bytes=0-1, 10-12\r\n");
+    assert!(response.starts_with("HTTP/1.1 206 Partial Content\r\n"));
+    let content_type = header(&response, "Content-Type").unwrap();
+    let boundary = content_type.strip_prefix("multipart/byteranges; boundary=").unwrap();
+    let expected = format!(
+      "--{boundary}\r\nContent-Type: text/html; charset=utf-8\r\nContent-Range: bytes 0-1/20\r\n\r\n01\r\n\
+       --{boundary}\r\nContent-Type: text/html; charset=utf-8\r\nContent-Range: bytes 10-12/20\r\n\r\nabc\r\n\
+       --{boundary}--\r\n"
+    );
+    assert_eq!(body(&response), expected);
+    assert_eq!(header(&response, "Content-Length"), Some(expected.len().to_string().as_str()));
+    fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[test]
+  fn overlapping_ranges_test() {
+    let dir = fixture();
+    let app = app(&dir);
+
+    // overlapping and adjacent ranges are merged
+    let response = get(&app, "/static/index.html", "Range: bytes=4-7, 2-5\r\n");
+    assert!(response.starts_with("HTTP/1.1 206 Partial Content\r\n"));
+    assert_eq!(header(&response, "Content-Range"), Some("bytes 2-7/20"));
+    assert_eq!(body(&response), "234567");
+
+    let response = get(&app, "/static/index.html", "Range: bytes=0-1, 2-3\r\n");
+    assert_eq!(header(&response, "Content-Range"), Some("bytes 0-3/20"));
+    assert_eq!(body(&response), "0123");
+
+    // the file is not sent once per range
+    let response = get(&app, "/static/index.html", &format!("Range: bytes={}\r\n", ["0-"; 16].join(",")));
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert_eq!(body(&response), CONTENT);
+
+    // nor in many small pieces
+    let response = get(&app, "/static/index.html", "Range: bytes=0-0, 2-2, 4-4\r\n");
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert_eq!(body(&response), CONTENT);
+    fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[test]
+  fn unsatisfiable_range_test() {
+    let dir = fixture();
+    let app = app(&dir);
+
+    let response = get(&app, "/static/index.html", "Range: bytes=20-\r\n");
+    assert!(response.starts_with("HTTP/1.1 416 Range Not Satisfiable\r\n"));
+    assert_eq!(header(&response, "Content-Range"), Some("bytes */20"));
+
+    // invalid ranges are ignored
+    let response = get(&app, "/static/index.html", "Range: bytes=5-1\r\n");
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+    fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[test]
+  fn if_range_test() {
+    let dir = fixture();
+    let app = app(&dir);
+    let etag = header(&get(&app, "/static/index.html", ""), "ETag").unwrap().to_string();
+
+    let response = get(&app, "/static/index.html", &format!("Range: bytes=0-1\r\nIf-Range: {etag}\r\n"));
+    assert_eq!(body(&response), "01");
+
+    let response = get(&app, "/static/index.html", "Range: bytes=0-1\r\nIf-Range: \"stale\"\r\n");
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert_eq!(body(&response), CONTENT);
+    fs::remove_dir_all(dir).unwrap();
+  }
+}
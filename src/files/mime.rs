@@ -0,0 +1,57 @@
+use std::path::Path;
+
+// common web formats, text formats are sent as UTF-8
+const TYPES: &[(&str, &str)] = &[
+  ("html", "text/html; charset=utf-8"),
+  ("htm", "text/html; charset=utf-8"),
+  ("css", "text/css; charset=utf-8"),
+  ("js", "text/javascript; charset=utf-8"),
+  ("mjs", "text/javascript; charset=utf-8"),
+  ("json", "application/json"),
+  ("map", "application/json"),
+  ("webmanifest", "application/manifest+json"),
+  ("txt", "text/plain; charset=utf-8"),
+  ("md", "text/markdown; charset=utf-8"),
+  ("csv", "text/csv; charset=utf-8"),
+  ("xml", "application/xml"),
+  ("rss", "application/rss+xml"),
+  ("atom", "application/atom+xml"),
+  ("wasm", "application/wasm"),
+  ("pdf", "application/pdf"),
+  ("zip", "application/zip"),
+  ("gz", "application/gzip"),
+  ("tar", "application/x-tar"),
+  ("png", "image/png"),
+  ("jpg", "image/jpeg"),
+  ("jpeg", "image/jpeg"),
+  ("gif", "image/gif"),
+  ("webp", "image/webp"),
+  ("avif", "image/avif"),
+  ("svg", "image/svg+xml"),
+  ("ico", "image/vnd.microsoft.icon"),
+  ("bmp", "image/bmp"),
+  ("woff", "font/woff"),
+  ("woff2", "font/woff2"),
+  ("ttf", "font/ttf"),
+  ("otf", "font/otf"),
+  ("mp3", "audio/mpeg"),
+  ("ogg", "audio/ogg"),
+  ("wav", "audio/wav"),
+  ("flac", "audio/flac"),
+  ("mp4", "video/mp4"),
+  ("webm", "video/webm"),
+  ("ogv", "video/ogg")
+];
+
+/// The media type of a file, inferred from its extension.
+/// Unknown extensions are `application/octet-stream`.
+pub fn mime_type(path: &Path) -> &'static str {
+  let Some(extension) = path.extension().and_then(|e| e.to_str()) else {
+    return "application/octet-stream"
+  };
+
+  TYPES
+    .iter()
+    .find(|(known, _)| known.eq_ignore_ascii_case(extension))
+    .map_or("application/octet-stream", |(_, media_type)| media_type)
+}
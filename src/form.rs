@@ -0,0 +1,182 @@
+//! `application/x-www-form-urlencoded` parsing, as described in the
+//! WHATWG URL standard, section 5. It is used for both HTML form bodies
+//! ([`Request::form`](crate::request::Request::form)) and query strings
+//! ([`Request::query`](crate::request::Request::query)).
+//!
+//! ```
+//! use webserver::form::Form;
+//!
+//! let form = Form::parse("name=J%C3%A9r%C3%B4me+D&tag=a&tag=b&empty");
+//! assert_eq!(form.get("name"), Some("Jérôme D"));
+//! assert_eq!(form.get_all("tag").collect::<Vec<_>>(), vec!["a", "b"]);
+//! assert_eq!(form.get("empty"), Some(""));
+//! ```
+#[cfg(feature = "serde")]
+mod de;
+#[cfg(feature = "serde")]
+pub use de::Error;
+
+#[cfg(test)]
+mod form_test;
+
+fn hex(byte: u8) -> Option<u8> {
+  (byte as char).to_digit(16).map(|d| d as u8)
+}
+
+/// Decodes `%XX` sequences, invalid sequences are kept as is.
+pub(crate) fn percent_decode(value: &[u8]) -> Vec<u8> {
+  let mut decoded = Vec::with_capacity(value.len());
+  let mut index = 0;
+  while index < value.len() {
+    let escaped = match value[index] {
+      b'%' => value
+        .get(index + 1..index + 3)
+        .and_then(|hexes| Some(hex(hexes[0])? << 4 | hex(hexes[1])?)),
+      _ => None
+    };
+
+    match escaped {
+      Some(byte) => {
+        decoded.push(byte);
+        index += 3;
+      },
+      None => {
+        decoded.push(value[index]);
+        index += 1;
+      }
+    }
+  }
+  decoded
+}
+
+/// Decodes `+` as a space and `%XX` sequences, invalid sequences are kept
+/// as is and invalid UTF-8 is replaced.
+pub fn decode(value: &[u8]) -> String {
+  let value: Vec<u8> = value
+    .iter()
+    .map(|b| if *b == b'+' { b' ' } else { *b })
+    .collect();
+  String::from_utf8_lossy(&percent_decode(&value)).into_owned()
+}
+
+/// Encodes everything but alphanumerics and `*-._`, spaces become `+`.
+pub fn encode(value: &str) -> String {
+  let mut encoded = String::with_capacity(value.len());
+  for byte in value.bytes() {
+    match byte {
+      b' ' => encoded.push('+'),
+      b'*' | b'-' | b'.' | b'_' => encoded.push(byte as char),
+      byte if byte.is_ascii_alphanumeric() => encoded.push(byte as char),
+      byte => encoded.push_str(&format!("%{byte:02X}"))
+    }
+  }
+  encoded
+}
+
+/// Decoded name and value pairs, in the order they were sent.
+/// A name can be repeated.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Form {
+  pairs: Vec<(String, String)>
+}
+
+impl Form {
+  pub fn new() -> Form {
+    Form::default()
+  }
+
+  pub fn parse(input: &str) -> Form {
+    Form::parse_bytes(input.as_bytes())
+  }
+
+  pub fn parse_bytes(input: &[u8]) -> Form {
+    let pairs = input
+      .split(|b| *b == b'&')
+      .filter(|pair| !pair.is_empty())
+      .map(|pair| {
+        let (name, value) = match pair.iter().position(|b| *b == b'=') {
+          Some(index) => (&pair[..index], &pair[index + 1..]),
+          None => (pair, &b""[..])
+        };
+        (decode(name), decode(value))
+      })
+      .collect();
+
+    Form { pairs }
+  }
+
+  pub fn append(&mut self, name: &str, value: &str) -> &mut Self {
+    self.pairs.push((name.to_string(), value.to_string()));
+    self
+  }
+
+  /// The first value of `name`.
+  pub fn get(&self, name: &str) -> Option<&str> {
+    self.pairs
+      .iter()
+      .find(|(key, _)| key == name)
+      .map(|(_, value)| value.as_str())
+  }
+
+  /// Every value of `name`, in order.
+  pub fn get_all<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+    self.pairs
+      .iter()
+      .filter(move |(key, _)| key == name)
+      .map(|(_, value)| value.as_str())
+  }
+
+  pub fn contains(&self, name: &str) -> bool {
+    self.get(name).is_some()
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+    self.pairs
+      .iter()
+      .map(|(name, value)| (name.as_str(), value.as_str()))
+  }
+
+  pub fn len(&self) -> usize {
+    self.pairs.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.pairs.is_empty()
+  }
+
+  /// Deserializes into a typed value, usually a struct whose fields are
+  /// the form names. Repeated names deserialize into sequences, other
+  /// fields take the first value.
+  ///
+  /// ```
+  /// use serde::Deserialize;
+  /// use webserver::form::Form;
+  ///
+  /// #[derive(Deserialize)]
+  /// struct Search {
+  ///   q: String,
+  ///   page: Option<u32>,
+  ///   tag: Vec<String>
+  /// }
+  ///
+  /// let search: Search = Form::parse("q=rust&tag=a&tag=b").deserialize().unwrap();
+  /// assert_eq!((search.q.as_str(), search.page, search.tag.len()), ("rust", None, 2));
+  /// ```
+  #[cfg(feature = "serde")]
+  pub fn deserialize<T: serde::de::DeserializeOwned>(&self) -> Result<T, Error> {
+    T::deserialize(de::Deserializer::new(self))
+  }
+}
+
+/// Serializes back into `application/x-www-form-urlencoded`.
+impl std::fmt::Display for Form {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    for (index, (name, value)) in self.pairs.iter().enumerate() {
+      if index > 0 {
+        write!(f, "&")?;
+      }
+      write!(f, "{}={}", encode(name), encode(value))?;
+    }
+    Ok(())
+  }
+}
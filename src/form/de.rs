@@ -0,0 +1,146 @@
+use std::fmt;
+
+use serde::{de::{self, value::{MapDeserializer, SeqDeserializer}, IntoDeserializer, Visitor}, forward_to_deserialize_any};
+
+use super::Form;
+
+/// A form that does not match the type it is deserialized into.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Error(String);
+
+impl fmt::Display for Error {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.0)
+  }
+}
+
+impl std::error::Error for Error {}
+
+impl de::Error for Error {
+  fn custom<T: fmt::Display>(msg: T) -> Self {
+    Error(msg.to_string())
+  }
+}
+
+pub struct Deserializer<'a> {
+  form: &'a Form
+}
+
+impl<'a> Deserializer<'a> {
+  pub fn new(form: &'a Form) -> Deserializer<'a> {
+    Deserializer { form }
+  }
+
+  // the values of every name, names in the order they first appear
+  fn fields(&self) -> Vec<(&'a str, Values<'a>)> {
+    let mut fields: Vec<(&str, Values)> = Vec::new();
+    for (name, value) in self.form.iter() {
+      match fields.iter_mut().find(|(key, _)| *key == name) {
+        Some((_, values)) => values.0.push(value),
+        None => fields.push((name, Values(vec![value])))
+      }
+    }
+    fields
+  }
+}
+
+impl<'de> de::Deserializer<'de> for Deserializer<'de> {
+  type Error = Error;
+
+  fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+    visitor.visit_map(MapDeserializer::new(self.fields().into_iter()))
+  }
+
+  forward_to_deserialize_any! {
+    bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+    bytes byte_buf option unit unit_struct newtype_struct seq tuple
+    tuple_struct map struct enum identifier ignored_any
+  }
+}
+
+// the values sent for a name
+struct Values<'a>(Vec<&'a str>);
+
+impl<'a> Values<'a> {
+  fn first(&self) -> Result<&'a str, Error> {
+    self.0.first().copied().ok_or_else(|| Error("Missing value".to_string()))
+  }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for Values<'de> {
+  type Deserializer = Self;
+
+  fn into_deserializer(self) -> Self::Deserializer {
+    self
+  }
+}
+
+macro_rules! parse {
+  ($($method:ident => $visit:ident),*) => {
+    $(
+      fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let value = self.first()?;
+        let parsed = value
+          .parse()
+          .map_err(|_| Error(format!("Invalid value: {value:?}")))?;
+        visitor.$visit(parsed)
+      }
+    )*
+  };
+}
+
+impl<'de> de::Deserializer<'de> for Values<'de> {
+  type Error = Error;
+
+  fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+    visitor.visit_borrowed_str(self.first()?)
+  }
+
+  parse! {
+    deserialize_bool => visit_bool,
+    deserialize_i8 => visit_i8,
+    deserialize_i16 => visit_i16,
+    deserialize_i32 => visit_i32,
+    deserialize_i64 => visit_i64,
+    deserialize_i128 => visit_i128,
+    deserialize_u8 => visit_u8,
+    deserialize_u16 => visit_u16,
+    deserialize_u32 => visit_u32,
+    deserialize_u64 => visit_u64,
+    deserialize_u128 => visit_u128,
+    deserialize_f32 => visit_f32,
+    deserialize_f64 => visit_f64,
+    deserialize_char => visit_char
+  }
+
+  fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+    visitor.visit_some(self)
+  }
+
+  fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, Error> {
+    visitor.visit_newtype_struct(self)
+  }
+
+  fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+    let values = self.0.into_iter().map(|value| Values(vec![value]));
+    visitor.visit_seq(SeqDeserializer::new(values))
+  }
+
+  fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Error> {
+    self.deserialize_seq(visitor)
+  }
+
+  // only unit variants can be sent in a form
+  fn deserialize_enum<V: Visitor<'de>>(
+    self,
+    _name: &'static str,
+    _variants: &'static [&'static str],
+    visitor: V
+  ) -> Result<V::Value, Error> {
+    visitor.visit_enum(self.first()?.into_deserializer())
+  }
+
+  forward_to_deserialize_any! {
+    str string bytes byte_buf unit unit_struct tuple_struct map struct identifier ignored_any
+  }
+}
@@ -0,0 +1,94 @@
+use super::*;
+
+mod parse_test {
+  use super::*;
+
+  #[test]
+  fn pairs_test() {
+    let form = Form::parse("a=1&b=two+words&c=&d&=e&&a=3");
+    assert_eq!(
+      form.iter().collect::<Vec<_>>(),
+      vec![("a", "1"), ("b", "two words"), ("c", ""), ("d", ""), ("", "e"), ("a", "3")]
+    );
+    assert_eq!(form.get("a"), Some("1"));
+    assert_eq!(form.get_all("a").collect::<Vec<_>>(), vec!["1", "3"]);
+    assert!(form.contains("d"));
+    assert!(!form.contains("z"));
+    assert!(Form::parse("").is_empty());
+  }
+
+  #[test]
+  fn value_with_equals_test() {
+    assert_eq!(Form::parse("a=b=c").get("a"), Some("b=c"));
+  }
+
+  #[test]
+  fn decode_test() {
+    assert_eq!(decode(b"%41%2b%2B+%20"), "A++  ");
+    assert_eq!(decode(b"caf%C3%A9"), "café");
+    assert_eq!(decode(b"100%"), "100%");
+    assert_eq!(decode(b"%zz%4"), "%zz%4");
+    assert_eq!(decode(b"%FF"), "\u{FFFD}");
+  }
+
+  #[test]
+  fn round_trip_test() {
+    let mut form = Form::new();
+    form.append("name", "Jérôme D").append("q", "a&b=c+d%");
+    assert_eq!(form.to_string(), "name=J%C3%A9r%C3%B4me+D&q=a%26b%3Dc%2Bd%25");
+    assert_eq!(Form::parse(&form.to_string()), form);
+  }
+}
+
+#[cfg(feature = "serde")]
+mod deserialize_test {
+  use serde::Deserialize;
+  use super::*;
+
+  #[derive(Debug, Deserialize, PartialEq)]
+  #[serde(rename_all = "lowercase")]
+  enum Order {
+    Asc,
+    Desc
+  }
+
+  #[derive(Debug, Deserialize, PartialEq)]
+  struct Search {
+    q: String,
+    page: Option<u32>,
+    exact: bool,
+    order: Order,
+    #[serde(default)]
+    tag: Vec<String>
+  }
+
+  #[test]
+  fn struct_test() {
+    let search: Search = Form::parse("q=a+b&exact=true&order=desc&tag=x&tag=y&page=2").deserialize().unwrap();
+    assert_eq!(search, Search {
+      q: "a b".to_string(),
+      page: Some(2),
+      exact: true,
+      order: Order::Desc,
+      tag: vec!["x".to_string(), "y".to_string()]
+    });
+
+    let search: Search = Form::parse("q=&exact=false&order=asc").deserialize().unwrap();
+    assert_eq!((search.page, search.tag.len()), (None, 0));
+  }
+
+  #[test]
+  fn map_test() {
+    let map: std::collections::HashMap<String, String> = Form::parse("a=1&b=2&a=3").deserialize().unwrap();
+    assert_eq!(map.get("a").map(String::as_str), Some("1"));
+    assert_eq!(map.len(), 2);
+  }
+
+  #[test]
+  fn errors_test() {
+    assert!(Form::parse("exact=true&order=asc").deserialize::<Search>().is_err());
+    assert!(Form::parse("q=&exact=yes&order=asc").deserialize::<Search>().is_err());
+    assert!(Form::parse("q=&exact=true&order=up").deserialize::<Search>().is_err());
+    assert!(Form::parse("q=&exact=true&order=asc&page=-1").deserialize::<Search>().is_err());
+  }
+}
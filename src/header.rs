@@ -1,11 +1,22 @@
 use std::collections::HashMap;
 
-mod parser;
+use crate::protocol;
+
+pub(crate) mod parser;
+mod error;
+mod value;
+pub mod typed;
+pub use error::Error;
+pub use value::Value;
+pub use typed::TypedHeader;
+
+#[cfg(test)]
+mod header_test;
 
 macro_rules! values {
   ($source:expr, $key:expr) => {
       {
-          $source.get($key).and_then(|v| Some(parser::multi_values_field(v)))
+          $source.get($key).and_then(|v| parser::multi_values_field(v).ok())
       }
   };
 }
@@ -13,14 +24,42 @@ macro_rules! values {
 macro_rules! value {
 ($source:expr, $key:expr) => {
     {
-        $source.get($key).and_then(|v| parser::single_value_field(v))
+        $source.get($key).and_then(|v| parser::single_value_field(v).ok().flatten())
     }
 };
 }
 
+// a field keeps the name it was first set with, lookups ignore case
+#[derive(Debug, Clone)]
+struct Field {
+  name: String,
+  values: Vec<String>
+}
+
+/// Checks that a field can be written as is: its name must be a token
+/// and its value `field-content`, so that neither can end the field early.
+pub fn validate(name: &str, value: &str) -> Result<(), Error> {
+  if !protocol::is_token(name.as_bytes()) {
+    return Err(Error::InvalidName(name.to_string()))
+  }
+
+  let is_trimmed = !value.starts_with([' ', '\t']) && !value.ends_with([' ', '\t']);
+  if !is_trimmed || !protocol::is_field_value(value.as_bytes()) {
+    return Err(Error::InvalidFieldValue(name.to_string()))
+  }
+  Ok(())
+}
+
+/// Header fields of a message. Names are case-insensitive.
 #[derive(Debug)]
 pub struct Headers {
-  headers: HashMap<String, Vec<String>>
+  headers: HashMap<String, Field>
+}
+
+impl Default for Headers {
+  fn default() -> Self {
+    Self::new()
+  }
 }
 
 impl Headers {
@@ -31,91 +70,136 @@ impl Headers {
   }
 
   pub fn set(&mut self, key: &str, value: String) -> &Self {
-    let value = vec![value];
-    self.headers.insert(key.to_string(), value);
+    let field = Field { name: key.to_string(), values: vec![value] };
+    self.headers.insert(key.to_ascii_lowercase(), field);
     self
   }
 
+  /// Same as `set`, but rejects fields that could not be written as is.
+  ///
+  /// ```
+  /// use webserver::header::Headers;
+  ///
+  /// let mut headers = Headers::new();
+  /// assert!(headers.try_set("X-Name", "value").is_ok());
+  /// assert!(headers.try_set("X-Name", "value\r\nSet-Cookie: a=b").is_err());
+  /// assert!(headers.try_set("X Name", "value").is_err());
+  /// ```
+  pub fn try_set(&mut self, key: &str, value: &str) -> Result<&Self, Error> {
+    validate(key, value)?;
+    Ok(self.set(key, value.to_string()))
+  }
+
   pub fn get(&self, key: &str) -> Option<&String> {
-    self.headers.get(key).and_then(|v| Some(&v[0]))
+    self.get_all(key).map(|v| &v[0])
   }
 
   pub fn get_all(&self, key: &str) -> Option<&Vec<String>> {
-    self.headers.get(key)
+    self.headers
+      .get(&key.to_ascii_lowercase())
+      .map(|field| &field.values)
   }
 
   pub fn append(&mut self, key: String, value: String) -> &Self {
     self.headers
-      .entry(key)
-      .or_default()
+      .entry(key.to_ascii_lowercase())
+      .or_insert_with(|| Field { name: key, values: Vec::new() })
+      .values
       .push(value);
     self
   }
 
-  pub fn accept(&self) -> Option<Vec<Value>> {
+  /// Same as `append`, but rejects fields that could not be written as is.
+  pub fn try_append(&mut self, key: &str, value: &str) -> Result<&Self, Error> {
+    validate(key, value)?;
+    Ok(self.append(key.to_string(), value.to_string()))
+  }
+
+  /// Checks every field with [`validate`].
+  pub fn validate(&self) -> Result<(), Error> {
+    for (key, values) in self.iter() {
+      for value in values {
+        validate(key, value)?;
+      }
+    }
+    Ok(())
+  }
+
+  pub fn remove(&mut self, key: &str) -> Option<Vec<String>> {
+    self.headers
+      .remove(&key.to_ascii_lowercase())
+      .map(|field| field.values)
+  }
+
+  pub fn contains(&self, key: &str) -> bool {
+    self.headers.contains_key(&key.to_ascii_lowercase())
+  }
+
+  /// Decodes a typed header, `None` if it is missing or malformed.
+  ///
+  /// ```
+  /// use webserver::header::{Headers, typed::CacheControl};
+  ///
+  /// let mut headers = Headers::new();
+  /// headers.set("cache-control", "public, max-age=60".to_string());
+  ///
+  /// let cache_control = headers.typed::<CacheControl>().unwrap();
+  /// assert!(cache_control.is_public());
+  /// assert_eq!(cache_control.max_age(), Some(60));
+  /// ```
+  pub fn typed<H: TypedHeader>(&self) -> Option<H> {
+    self.try_typed().and_then(Result::ok)
+  }
+
+  /// Decodes a typed header, keeping the reason it is malformed.
+  pub fn try_typed<H: TypedHeader>(&self) -> Option<Result<H, Error>> {
+    self.get_all(H::NAME).map(|values| H::decode(values))
+  }
+
+  /// Encodes a typed header, replacing any previous value.
+  pub fn set_typed<H: TypedHeader>(&mut self, header: &H) -> &Self {
+    self.set(H::NAME, header.encode())
+  }
+
+  pub fn accept(&self) -> Option<Vec<Value<'_>>> {
     values!(self, "accept")
   }
 
-  pub fn accept_encoding(&self) -> Option<Vec<Value>> {
+  pub fn accept_encoding(&self) -> Option<Vec<Value<'_>>> {
     values!(self, "accept-encoding")
   }
 
-  pub fn content_type(&self) -> Option<Value> {
+  pub fn content_type(&self) -> Option<Value<'_>> {
     value!(self, "content-type")
   }
 
   pub fn content_length(&self) -> Option<usize> {
     value!(self, "content-length")
-      .and_then(|v| v.0.parse().ok())
+      .and_then(|v| v.value().parse().ok())
   }
 
-  pub fn get_value(&self, key: &str) -> Option<Value> {
+  pub fn get_value(&self, key: &str) -> Option<Value<'_>> {
     self
       .get(key)
       .and_then(|v| 
-        parser::single_value_field(v)
+        parser::single_value_field(v).ok().flatten()
       )
   }
 
-  pub fn get_multi_values(&self, key: &str) -> Option<Vec<Value>> {
+  pub fn get_multi_values(&self, key: &str) -> Option<Vec<Value<'_>>> {
     values!(self, key)
   }
 
-  pub fn get_multi_values_all(&self, key: &str) -> Option<Vec<Vec<Value>>> {
+  pub fn get_multi_values_all(&self, key: &str) -> Option<Vec<Vec<Value<'_>>>> {
     self
       .get_all(key)
       .and_then(|v| v.iter()
-        .map(|v| Some(parser::multi_values_field(v)))
+        .map(|v| parser::multi_values_field(v).ok())
         .collect()
       )
   }
   
-  pub(crate) fn move_to(self, to: &mut Headers) {
-    for (k, v) in self.headers.into_iter() {
-      let entry = to.headers.get_mut(&k);
-      if let Some(vec) = entry {
-        vec.extend_from_slice(&v);
-      } else {
-        to.headers.insert(k, v);
-      }
-    }
-  }
-
-  pub(crate) fn copy_to(&self, to: &mut Headers) {
-    for (k, v) in self.headers.iter() {
-      let entry = to.headers.get_mut(k);
-      if let Some(vec) = entry {
-        vec.extend_from_slice(&v);
-      } else {
-        to.headers.insert(k.clone(), v.clone());
-      }
-    }
-  }
-
   pub fn iter(&self) -> Box<dyn Iterator<Item = (&String, &Vec<String>)>  + '_> {
-    Box::new(self.headers.iter())
+    Box::new(self.headers.values().map(|field| (&field.name, &field.values)))
   }
 }
-
-#[derive(Debug)]
-pub struct Value<'a>(&'a str, HashMap<&'a str, &'a str>);
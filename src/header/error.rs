@@ -0,0 +1,24 @@
+#[derive(Debug, PartialEq)]
+pub enum Error {
+  UnterminatedQuote,
+  UnexpectedCharacter(char),
+  InvalidParameterName(String),
+  InvalidValue(&'static str),
+  InvalidName(String),
+  InvalidFieldValue(String)
+}
+
+impl std::fmt::Display for Error {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+      match self {
+        Error::UnterminatedQuote => write!(f, "Quoted string is not terminated"),
+        Error::UnexpectedCharacter(char) => write!(f, "Unexpected character {char:?}"),
+        Error::InvalidParameterName(name) => write!(f, "Invalid parameter name: {name}"),
+        Error::InvalidValue(name) => write!(f, "Invalid {name} header value"),
+        Error::InvalidName(name) => write!(f, "Invalid header name: {name:?}"),
+        Error::InvalidFieldValue(name) => write!(f, "Invalid value for header {name}")
+      }
+  }
+}
+
+impl std::error::Error for Error {}
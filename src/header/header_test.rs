@@ -0,0 +1,61 @@
+use crate::header::typed::ContentLength;
+use super::*;
+
+fn values(values: &[&str]) -> Vec<String> {
+  values.iter().map(|v| v.to_string()).collect()
+}
+
+#[test]
+fn case_insensitive_test() {
+  let mut headers = Headers::new();
+  headers.set("Content-Type", "text/plain".to_string());
+  headers.set("content-type", "text/html".to_string());
+  headers.append("CONTENT-TYPE".to_string(), "text/css".to_string());
+
+  assert_eq!(headers.get_all("Content-type").unwrap(), &values(&["text/html", "text/css"]));
+  assert_eq!(headers.iter().count(), 1);
+  assert!(headers.contains("content-TYPE"));
+  assert!(headers.remove("Content-Type").is_some());
+  assert!(!headers.contains("content-type"));
+}
+
+#[test]
+fn typed_test() {
+  let mut headers = Headers::new();
+  assert_eq!(headers.typed::<ContentLength>(), None);
+
+  headers.set_typed(&ContentLength(10));
+  let (name, value) = headers.iter().next().unwrap();
+  assert_eq!((name.as_str(), value[0].as_str()), ("Content-Length", "10"));
+  assert_eq!(headers.typed::<ContentLength>(), Some(ContentLength(10)));
+
+  headers.set("content-length", "ten".to_string());
+  assert_eq!(headers.typed::<ContentLength>(), None);
+  assert_eq!(headers.try_typed::<ContentLength>(), Some(Err(Error::InvalidValue("Content-Length"))));
+}
+
+#[test]
+fn validate_test() {
+  assert!(validate("X-Name", "").is_ok());
+  assert!(validate("X-Name", "a\tb c\u{e9}").is_ok());
+  assert_eq!(validate("X Name", "a"), Err(Error::InvalidName("X Name".to_string())));
+  assert_eq!(validate("", "a"), Err(Error::InvalidName("".to_string())));
+  assert_eq!(validate("X-Name:", "a"), Err(Error::InvalidName("X-Name:".to_string())));
+
+  for value in ["a\r\nSet-Cookie: a=b", "a\nb", "a\rb", "a\0b", " a", "a\t"] {
+    assert_eq!(validate("X-Name", value), Err(Error::InvalidFieldValue("X-Name".to_string())), "Value should be rejected: {value:?}");
+  }
+}
+
+#[test]
+fn checked_test() {
+  let mut headers = Headers::new();
+  assert!(headers.try_append("Set-Cookie", "a=b").is_ok());
+  assert!(headers.try_append("Set-Cookie", "c=d\r\n\r\n<html>").is_err());
+  assert_eq!(headers.get_all("set-cookie").unwrap(), &values(&["a=b"]));
+  assert!(headers.validate().is_ok());
+
+  // unchecked fields are caught before the response is written
+  headers.set("Location", "/\r\nX-Injected: 1".to_string());
+  assert_eq!(headers.validate(), Err(Error::InvalidFieldValue("Location".to_string())));
+}
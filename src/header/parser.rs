@@ -1,85 +1,165 @@
-use std::collections::HashMap;
-
-use super::Value;
-
-pub fn value(value: &str) -> Value {
-  let splitted = field_string_split_all(value, ';');
-  // got only the field value
-  if splitted.len() == 1 {
-    Value(trim_field_value(splitted[0]), HashMap::new())
-  } 
-  // got field value and parameters
-  else {
-    let mut map: HashMap<&str, &str> = HashMap::new();
-    for entry in splitted[1..].iter() {
-      // split parameter key and value
-      let parameter = field_string_split_max_n(entry, '=', 2);
-      if parameter.len() == 1 {
-        map.insert(trim_field_value(parameter[0]), "");
-      } else {
-        map.insert(trim_field_value(parameter[0]), trim_field_value(parameter[1]));
-      }
-    }
-    Value(trim_field_value(splitted[0]), map)
-  }
-}
+//! Tokenizer for header field values, following RFC 9110, section 5.6:
+//! comma separated lists, `;` separated parameters and quoted strings.
+use std::borrow::Cow;
 
-pub fn multi_values_field(field: &str) -> Vec<Value> {
-  field_string_split_all(field, ',')
-    .iter()
-    .map(|v| value(v))
-    .collect()
-}
+use crate::protocol;
+use super::{Error, Value};
+
+#[cfg(test)]
+mod parser_test;
 
-pub fn single_value_field(field: &str) -> Option<Value> {
-  field_string_split_max_n(field, ',', 1)
-    .get(0)
-    .and_then(|v| Some(value(v)))
+fn is_whitespace(char: char) -> bool {
+  char == ' ' || char == '\t'
 }
 
-fn field_string_split_all(value: &str, delimiter: char) -> Vec<&str> {
-  field_string_split_max_n(value, delimiter, usize::MAX)
+fn trim(value: &str) -> &str {
+  value.trim_matches(is_whitespace)
 }
 
-fn field_string_split_max_n(value: &str, delimiter: char, n: usize) -> Vec<&str> {
-  if n == 0 {
-    panic!("n must be greater than 0");
+// splits on every delimiter found outside of quoted strings,
+// empty elements are skipped as required for lists
+fn split(field: &str, delimiter: char) -> Result<Vec<&str>, Error> {
+  let mut values = Vec::new();
+  let mut quoted = false;
+  let mut escaped = false;
+  let mut start = 0;
+
+  for (index, char) in field.char_indices() {
+    if escaped {
+      escaped = false;
+    } else if quoted && char == '\\' {
+      escaped = true;
+    } else if char == '"' {
+      quoted = !quoted;
+    } else if !quoted && char == delimiter {
+      values.push(trim(&field[start..index]));
+      start = index + char.len_utf8();
+    }
   }
 
-  let mut values: Vec<&str> = Vec::new();
-  
-  let mut should_skip = false;
-  
-  let mut start = 0;
-  for (index, char) in value.char_indices() {
-    if char == '\"' {
-      if index == 0 || value.get(index-1..index-1) != Some("\\") {
-        should_skip = !should_skip;
-        continue;
+  if quoted {
+    return Err(Error::UnterminatedQuote)
+  }
+
+  values.push(trim(&field[start..]));
+  values.retain(|v| !v.is_empty());
+  Ok(values)
+}
+
+/// Parses a `quoted-string`, which must span the whole `value`,
+/// into its unescaped content. Only allocates if there is something to unescape.
+pub fn unquote(value: &str) -> Result<Cow<'_, str>, Error> {
+  let Some(inner) = value.strip_prefix('"') else {
+    return Ok(Cow::Borrowed(value))
+  };
+
+  let mut unescaped: Option<String> = None;
+  let mut chars = inner.char_indices();
+  while let Some((index, char)) = chars.next() {
+    match char {
+      '\\' => {
+        let Some((_, escaped)) = chars.next() else {
+          return Err(Error::UnterminatedQuote)
+        };
+        unescaped
+          .get_or_insert_with(|| inner[..index].to_string())
+          .push(escaped);
+      },
+      '"' => {
+        if let Some((_, char)) = chars.next() {
+          return Err(Error::UnexpectedCharacter(char))
+        }
+
+        return Ok(match unescaped {
+          Some(unescaped) => Cow::Owned(unescaped),
+          None => Cow::Borrowed(&inner[..index])
+        })
+      },
+      char => {
+        if let Some(unescaped) = unescaped.as_mut() {
+          unescaped.push(char);
+        }
       }
     }
+  }
 
-    if char == delimiter && !should_skip {
-      values.push(&value[start..index]);
-      if values.len() >= n {
-        return values;
-      }
-      start = index + 1;
+  Err(Error::UnterminatedQuote)
+}
+
+/// Serializes `value` as a `token` if possible, as a `quoted-string` otherwise.
+pub fn quote(value: &str) -> Cow<'_, str> {
+  if protocol::is_token(value.as_bytes()) {
+    return Cow::Borrowed(value)
+  }
+
+  let mut quoted = String::with_capacity(value.len() + 2);
+  quoted.push('"');
+  for char in value.chars() {
+    if char == '"' || char == '\\' {
+      quoted.push('\\');
     }
+    quoted.push(char);
+  }
+  quoted.push('"');
+  Cow::Owned(quoted)
+}
+
+fn parameter_value(value: &str) -> Result<Cow<'_, str>, Error> {
+  if value.starts_with('"') {
+    return unquote(value)
   }
 
-  if start != value.len() {
-    values.push(&value[start..]);
+  // an unquoted value cannot contain quotes
+  if value.contains('"') {
+    return Err(Error::UnexpectedCharacter('"'))
   }
 
-  values
+  Ok(Cow::Borrowed(value))
 }
 
-fn trim_field_value(value: &str) -> &str {
-  let str = value.trim();
-  if str.starts_with('\"') && str.ends_with('\"') {
-    &str[1..str.len()-1]
+pub fn value(value: &str) -> Result<Value<'_>, Error> {
+  let splitted = split(value, ';')?;
+  let Some((value, parameters)) = splitted.split_first() else {
+    return Ok(Value::new(""))
+  };
+
+  // a value wrapped in quotes as a whole is unquoted (e.g. `"etag"`),
+  // anything else (e.g. `W/"etag"`) is kept as is
+  let value = if value.starts_with('"') {
+    unquote(value)?
   } else {
-    &str
+    Cow::Borrowed(*value)
+  };
+
+  let mut result = Value::new(value);
+  for parameter in parameters {
+    // a parameter without value is accepted (e.g. `; secure`)
+    let (key, value) = match parameter.split_once('=') {
+      Some((key, value)) => (trim(key), parameter_value(trim(value))?),
+      None => (*parameter, Cow::Borrowed(""))
+    };
+
+    if !protocol::is_token(key.as_bytes()) {
+      return Err(Error::InvalidParameterName(key.to_string()))
+    }
+
+    result = result.with_param(key, value);
   }
-}
\ No newline at end of file
+
+  Ok(result)
+}
+
+pub fn multi_values_field(field: &str) -> Result<Vec<Value<'_>>, Error> {
+  split(field, ',')?
+    .into_iter()
+    .map(value)
+    .collect()
+}
+
+pub fn single_value_field(field: &str) -> Result<Option<Value<'_>>, Error> {
+  split(field, ',')?
+    .into_iter()
+    .next()
+    .map(value)
+    .transpose()
+}
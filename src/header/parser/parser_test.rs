@@ -0,0 +1,220 @@
+use proptest::prelude::*;
+
+use super::*;
+
+fn serialize(values: &[Value]) -> String {
+  values
+    .iter()
+    .map(|v| v.to_string())
+    .collect::<Vec<_>>()
+    .join(", ")
+}
+
+fn owned(values: &[Value]) -> Vec<(String, Vec<(String, String)>)> {
+  values
+    .iter()
+    .map(|v| (
+      v.value().to_string(),
+      v.params().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    ))
+    .collect()
+}
+
+prop_compose! {
+  fn field_value()(
+    value in "[a-zA-Z0-9!#$%&'*+.^_`|~/-]{1,12}",
+    params in prop::collection::hash_map("[a-z0-9-]{1,8}", r#"[a-zA-Z0-9 ,;=/:"\\-]{0,10}"#, 0..4)
+  ) -> String {
+    let mut params: Vec<_> = params.into_iter().collect();
+    params.sort();
+
+    let mut field = value;
+    for (key, value) in params {
+      field.push_str(&format!(";{key}={}", quote(&value)));
+    }
+    field
+  }
+}
+
+mod tokenizer_test {
+  use super::*;
+
+  #[test]
+  fn parameters_test() {
+    let value = value("text/html; charset=utf-8; q=0.5; secure").unwrap();
+    assert_eq!(value.value(), "text/html");
+    assert_eq!(value.param("charset").unwrap(), "utf-8");
+    assert_eq!(value.param("q").unwrap(), "0.5");
+    assert_eq!(value.param("secure").unwrap(), "");
+  }
+
+  #[test]
+  fn requoted_value_test() {
+    let field = r#""a, b"; q=1"#;
+    let value = Value::parse(field).unwrap();
+    assert_eq!(value.value(), "a, b");
+    assert_eq!(value.to_string(), field);
+    assert_eq!(Value::parse(r#"W/"tag""#).unwrap().to_string(), r#"W/"tag""#);
+  }
+
+  #[test]
+  fn quoted_parameter_test() {
+    let value = value(r#"form-data; name="a;b,c=d"; filename="say \"hi\".txt""#).unwrap();
+    assert_eq!(value.param("name").unwrap(), "a;b,c=d");
+    assert_eq!(value.param("filename").unwrap(), r#"say "hi".txt"#);
+    assert!(matches!(unquote(r#""a;b,c=d""#).unwrap(), Cow::Borrowed(_)));
+    assert!(matches!(unquote(r#""say \"hi\".txt""#).unwrap(), Cow::Owned(_)));
+  }
+
+  #[test]
+  fn escaped_quote_does_not_end_quoting_test() {
+    let values = multi_values_field(r#"a; p="x\", y", b"#).unwrap();
+    assert_eq!(values.len(), 2);
+    assert_eq!(values[0].param("p").unwrap(), r#"x", y"#);
+    assert_eq!(values[1].value(), "b");
+  }
+
+  #[test]
+  fn equal_sign_in_parameter_value_test() {
+    let value = value("a; b=c=d").unwrap();
+    assert_eq!(value.param("b").unwrap(), "c=d");
+  }
+
+  #[test]
+  fn quoted_value_test() {
+    let values = multi_values_field(r#""abc", W/"def", "g\"h""#).unwrap();
+    assert_eq!(values[0].value(), "abc");
+    assert_eq!(values[1].value(), r#"W/"def""#);
+    assert_eq!(values[2].value(), r#"g"h"#);
+  }
+
+  #[test]
+  fn empty_elements_test() {
+    let values = multi_values_field(" , a ,, b;, ").unwrap();
+    assert_eq!(values.len(), 2);
+    assert_eq!(values[0].value(), "a");
+    assert_eq!(values[1].value(), "b");
+    assert!(single_value_field(" , ").unwrap().is_none());
+  }
+
+  #[test]
+  fn errors_test() {
+    assert_eq!(value(r#"a; b="c"#).unwrap_err(), Error::UnterminatedQuote);
+    assert_eq!(value(r#"a; b="c\""#).unwrap_err(), Error::UnterminatedQuote);
+    assert_eq!(multi_values_field(r#"a, "b"#).unwrap_err(), Error::UnterminatedQuote);
+    assert_eq!(value(r#"a; b="c"d"#).unwrap_err(), Error::UnexpectedCharacter('d'));
+    assert_eq!(value(r#"a; b=c"d""#).unwrap_err(), Error::UnexpectedCharacter('"'));
+    assert_eq!(value("a; b c=d").unwrap_err(), Error::InvalidParameterName("b c".to_string()));
+  }
+
+  #[test]
+  fn quote_test() {
+    assert_eq!(quote("utf-8"), "utf-8");
+    assert_eq!(quote(""), r#""""#);
+    assert_eq!(quote(r#"a "b" \c"#), r#""a \"b\" \\c""#);
+    assert_eq!(unquote(&quote(r#"a "b" \c"#)).unwrap(), r#"a "b" \c"#);
+  }
+}
+
+mod value_test {
+  use super::*;
+
+  #[test]
+  fn param_lookup_ignores_case_test() {
+    let value = Value::parse("text/html; Charset=UTF-8").unwrap();
+    assert_eq!(value.param("charset"), Some("UTF-8"));
+    assert_eq!(value.param("CHARSET"), Some("UTF-8"));
+    assert_eq!(value.param("boundary"), None);
+  }
+
+  #[test]
+  fn params_order_test() {
+    let value = Value::parse("a; z=1; y=2; x").unwrap();
+    let params: Vec<_> = value.params().collect();
+    assert_eq!(params, vec![("z", "1"), ("y", "2"), ("x", "")]);
+  }
+
+  #[test]
+  fn into_owned_test() {
+    let value = {
+      let field = String::from(r#"attachment; filename="a b.txt""#);
+      Value::parse(&field).unwrap().into_owned()
+    };
+    assert_eq!(value.value(), "attachment");
+    assert_eq!(value.param("filename"), Some("a b.txt"));
+  }
+
+  #[test]
+  fn display_test() {
+    let value = Value::new("text/plain")
+      .with_param("charset", "utf-8")
+      .with_param("name", "a \"b\"")
+      .with_param("flag", "");
+    assert_eq!(value.to_string(), r#"text/plain; charset=utf-8; name="a \"b\""; flag"#);
+    assert_eq!(Value::parse(&value.to_string()).unwrap(), value);
+  }
+
+  #[test]
+  fn parse_list_test() {
+    let values = Value::parse_list("gzip;q=1.0, br;q=0.5, *;q=0").unwrap();
+    let encodings: Vec<_> = values.iter().map(|v| v.value()).collect();
+    assert_eq!(encodings, vec!["gzip", "br", "*"]);
+    assert_eq!(values[2].param("q"), Some("0"));
+  }
+}
+
+mod property_test {
+  use super::*;
+
+  proptest! {
+    #[test]
+    fn never_panics_test(field in any::<String>()) {
+      let _ = value(&field);
+      let _ = multi_values_field(&field);
+      let _ = single_value_field(&field);
+      let _ = unquote(&field);
+    }
+
+    #[test]
+    fn never_panics_on_quotes_test(field in r#"[a-z"\;,= ]{0,16}"#) {
+      let _ = value(&field);
+      let _ = multi_values_field(&field);
+      let _ = single_value_field(&field);
+      let _ = unquote(&field);
+    }
+
+    #[test]
+    fn quote_round_trip_test(value in any::<String>()) {
+      let quoted = quote(&value);
+      prop_assert_eq!(unquote(&quoted).unwrap(), value.as_str());
+    }
+
+    #[test]
+    fn value_round_trip_test(value in r#"[a-zA-Z0-9 ,;=/:"\\-]{0,10}"#) {
+      let serialized = Value::new(value.as_str()).with_param("q", "1").to_string();
+      let parsed = Value::parse(&serialized).unwrap();
+      prop_assert_eq!(parsed.value(), value.as_str());
+      prop_assert_eq!(parsed.param("q"), Some("1"));
+    }
+
+    #[test]
+    fn round_trip_test(fields in prop::collection::vec(field_value(), 1..4)) {
+      let field = fields.join(", ");
+      let values = multi_values_field(&field).unwrap();
+      prop_assert_eq!(values.len(), fields.len());
+
+      let serialized = serialize(&values);
+      let reparsed = multi_values_field(&serialized).unwrap();
+      prop_assert_eq!(owned(&values), owned(&reparsed));
+      prop_assert_eq!(serialize(&reparsed), serialized);
+    }
+
+    #[test]
+    fn single_value_test(fields in prop::collection::vec(field_value(), 1..4)) {
+      let field = fields.join(", ");
+      let first = single_value_field(&field).unwrap().unwrap();
+      let values = multi_values_field(&field).unwrap();
+      prop_assert_eq!(owned(&[first]), owned(&values[..1]));
+    }
+  }
+}
@@ -0,0 +1,330 @@
+//! Typed header fields, decoded from and encoded to their
+//! string values through [`Headers::typed`](super::Headers::typed)
+//! and [`Headers::set_typed`](super::Headers::set_typed).
+//!
+//! ```
+//! use webserver::header::{Headers, typed::{ContentLength, ContentType}};
+//!
+//! let mut headers = Headers::new();
+//! headers.set_typed(&ContentType::new("text/html").with_param("charset", "utf-8"));
+//! headers.set_typed(&ContentLength(42));
+//!
+//! assert_eq!(headers.get("content-type").unwrap(), "text/html; charset=utf-8");
+//! assert_eq!(headers.typed::<ContentType>().unwrap().charset(), Some("utf-8"));
+//! assert_eq!(headers.typed::<ContentLength>(), Some(ContentLength(42)));
+//! ```
+use std::fmt;
+
+use crate::protocol;
+use super::{Error, Value};
+
+mod authorization;
+mod cache_control;
+mod date;
+mod entity_tag;
+mod range;
+pub use authorization::Authorization;
+pub use cache_control::CacheControl;
+pub use date::{Date, HttpDate, IfModifiedSince, IfUnmodifiedSince, LastModified};
+pub use entity_tag::{ETag, IfMatch, IfNoneMatch};
+pub use range::{ByteRange, IfRange, Range};
+
+#[cfg(test)]
+mod typed_test;
+
+/// A header field with a typed representation.
+pub trait TypedHeader: Sized {
+  /// The field name, in its canonical case.
+  const NAME: &'static str;
+
+  /// Decodes every line the field was sent on.
+  fn decode(values: &[String]) -> Result<Self, Error>;
+
+  /// Encodes the field as a single line.
+  fn encode(&self) -> String;
+}
+
+fn trim(value: &str) -> &str {
+  value.trim_matches([' ', '\t'])
+}
+
+// a field that is not a list must be sent on a single line
+fn single<H: TypedHeader>(values: &[String]) -> Result<&str, Error> {
+  match values {
+    [value] => Ok(trim(value)),
+    _ => Err(Error::InvalidValue(H::NAME))
+  }
+}
+
+// the elements of a list field, which may be split over several lines
+fn list(values: &[String]) -> Result<Vec<Value<'_>>, Error> {
+  let mut elements = Vec::new();
+  for value in values {
+    elements.extend(Value::parse_list(value)?);
+  }
+  Ok(elements)
+}
+
+// a list of tokens without parameters, such as `Vary` or `Allow`
+fn tokens<H: TypedHeader>(values: &[String]) -> Result<Vec<String>, Error> {
+  list(values)?
+    .into_iter()
+    .map(|element| match element.params().next() {
+      None if protocol::is_token(element.value().as_bytes()) => Ok(element.value().to_string()),
+      _ => Err(Error::InvalidValue(H::NAME))
+    })
+    .collect()
+}
+
+/// `Content-Type`, a media type with its parameters.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContentType(Value<'static>);
+
+impl ContentType {
+  /// Creates a content type from a `type/subtype` media type.
+  pub fn new(media_type: &str) -> ContentType {
+    ContentType(Value::new(media_type.to_string()))
+  }
+
+  /// Appends a parameter, e.g. `charset`.
+  pub fn with_param(self, name: &str, value: &str) -> ContentType {
+    ContentType(self.0.with_param(name.to_string(), value.to_string()))
+  }
+
+  /// The media type without parameters, e.g. `text/html`.
+  pub fn media_type(&self) -> &str {
+    self.0.value()
+  }
+
+  pub fn main_type(&self) -> &str {
+    self.media_type().split_once('/').map_or(self.media_type(), |(main, _)| main)
+  }
+
+  pub fn subtype(&self) -> &str {
+    self.media_type().split_once('/').map_or("", |(_, sub)| sub)
+  }
+
+  pub fn param(&self, name: &str) -> Option<&str> {
+    self.0.param(name)
+  }
+
+  pub fn params(&self) -> impl Iterator<Item = (&str, &str)> {
+    self.0.params()
+  }
+
+  pub fn charset(&self) -> Option<&str> {
+    self.param("charset")
+  }
+}
+
+impl TypedHeader for ContentType {
+  const NAME: &'static str = "Content-Type";
+
+  fn decode(values: &[String]) -> Result<Self, Error> {
+    let value = Value::parse(single::<Self>(values)?)?;
+    let is_valid = value
+      .value()
+      .split_once('/')
+      .is_some_and(|(main, sub)| protocol::is_token(main.as_bytes()) && protocol::is_token(sub.as_bytes()));
+
+    if !is_valid {
+      return Err(Error::InvalidValue(Self::NAME))
+    }
+
+    // types are case-insensitive, parameter values are kept as is
+    let mut content_type = ContentType::new(&value.value().to_ascii_lowercase());
+    for (name, value) in value.params() {
+      content_type = content_type.with_param(&name.to_ascii_lowercase(), value);
+    }
+    Ok(content_type)
+  }
+
+  fn encode(&self) -> String {
+    self.0.to_string()
+  }
+}
+
+/// `Content-Length`, in bytes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ContentLength(pub u64);
+
+impl TypedHeader for ContentLength {
+  const NAME: &'static str = "Content-Length";
+
+  // identical values sent more than once are accepted, see RFC 9110, section 8.6
+  fn decode(values: &[String]) -> Result<Self, Error> {
+    let lengths: Vec<&str> = values
+      .iter()
+      .flat_map(|v| v.split(','))
+      .map(trim)
+      .collect();
+
+    let is_valid = lengths
+      .iter()
+      .all(|v| !v.is_empty() && v.bytes().all(|b| b.is_ascii_digit()) && *v == lengths[0]);
+
+    match lengths.first() {
+      Some(length) if is_valid => length.parse().map(ContentLength).map_err(|_| Error::InvalidValue(Self::NAME)),
+      _ => Err(Error::InvalidValue(Self::NAME))
+    }
+  }
+
+  fn encode(&self) -> String {
+    self.0.to_string()
+  }
+}
+
+/// `Host`, the authority of the target URI.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Host {
+  pub hostname: String,
+  pub port: Option<u16>
+}
+
+fn is_host_char(byte: u8) -> bool {
+  byte.is_ascii_alphanumeric() || b"-._~!$&'()*+,;=%".contains(&byte)
+}
+
+impl TypedHeader for Host {
+  const NAME: &'static str = "Host";
+
+  fn decode(values: &[String]) -> Result<Self, Error> {
+    let value = single::<Self>(values)?;
+    let invalid = || Error::InvalidValue(Self::NAME);
+
+    // IP literals are enclosed in brackets, e.g. `[::1]:8080`
+    let (hostname, port) = if let Some(rest) = value.strip_prefix('[') {
+      let (literal, rest) = rest.split_once(']').ok_or_else(invalid)?;
+      if literal.is_empty() || !literal.bytes().all(|b| is_host_char(b) || b == b':') {
+        return Err(invalid())
+      }
+
+      match rest {
+        "" => (&value[..literal.len() + 2], None),
+        rest => (&value[..literal.len() + 2], Some(rest.strip_prefix(':').ok_or_else(invalid)?))
+      }
+    } else {
+      let (hostname, port) = match value.split_once(':') {
+        Some((hostname, port)) => (hostname, Some(port)),
+        None => (value, None)
+      };
+
+      if hostname.is_empty() || !hostname.bytes().all(is_host_char) {
+        return Err(invalid())
+      }
+      (hostname, port)
+    };
+
+    let port = match port {
+      Some(port) if !port.is_empty() && port.bytes().all(|b| b.is_ascii_digit()) => Some(port.parse().map_err(|_| invalid())?),
+      Some(_) => return Err(invalid()),
+      None => None
+    };
+
+    Ok(Host { hostname: hostname.to_string(), port })
+  }
+
+  fn encode(&self) -> String {
+    match self.port {
+      Some(port) => format!("{}:{port}", self.hostname),
+      None => self.hostname.clone()
+    }
+  }
+}
+
+/// `Location`, a URI reference.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Location(pub String);
+
+impl TypedHeader for Location {
+  const NAME: &'static str = "Location";
+
+  fn decode(values: &[String]) -> Result<Self, Error> {
+    let value = single::<Self>(values)?;
+    if value.is_empty() || !value.bytes().all(|b| b.is_ascii_graphic() || b >= 0x80) {
+      return Err(Error::InvalidValue(Self::NAME))
+    }
+    Ok(Location(value.to_string()))
+  }
+
+  fn encode(&self) -> String {
+    self.0.clone()
+  }
+}
+
+/// `Vary`, the request fields a response was selected with.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Vary {
+  /// `*`, the response varies on more than request fields.
+  Any,
+  Fields(Vec<String>)
+}
+
+impl Vary {
+  /// Whether the response varies on the `name` field, ignoring case.
+  pub fn contains(&self, name: &str) -> bool {
+    match self {
+      Vary::Any => true,
+      Vary::Fields(fields) => fields.iter().any(|f| f.eq_ignore_ascii_case(name))
+    }
+  }
+
+  /// Adds the `name` field, unless the response already varies on it.
+  pub fn insert(&mut self, name: &str) {
+    if let Vary::Fields(fields) = self {
+      if !fields.iter().any(|f| f.eq_ignore_ascii_case(name)) {
+        fields.push(name.to_string());
+      }
+    }
+  }
+}
+
+impl TypedHeader for Vary {
+  const NAME: &'static str = "Vary";
+
+  fn decode(values: &[String]) -> Result<Self, Error> {
+    let elements = list(values)?;
+    if elements.iter().any(|e| e.value() == "*") {
+      return Ok(Vary::Any)
+    }
+    tokens::<Self>(values).map(Vary::Fields)
+  }
+
+  fn encode(&self) -> String {
+    match self {
+      Vary::Any => "*".to_string(),
+      Vary::Fields(fields) => fields.join(", ")
+    }
+  }
+}
+
+/// `Allow`, the methods supported by the target resource.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Allow(pub Vec<String>);
+
+impl TypedHeader for Allow {
+  const NAME: &'static str = "Allow";
+
+  fn decode(values: &[String]) -> Result<Self, Error> {
+    tokens::<Self>(values).map(Allow)
+  }
+
+  fn encode(&self) -> String {
+    self.0.join(", ")
+  }
+}
+
+// typed headers display as their encoded value
+macro_rules! display {
+  ($($header:ty),*) => {
+    $(
+      impl fmt::Display for $header {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+          f.write_str(&self.encode())
+        }
+      }
+    )*
+  };
+}
+
+display!(ContentType, ContentLength, Host, Location, Vary, Allow, Date, LastModified, IfModifiedSince, IfUnmodifiedSince, CacheControl, ETag, IfMatch, IfNoneMatch, Range, IfRange, Authorization);
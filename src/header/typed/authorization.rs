@@ -0,0 +1,83 @@
+use crate::{base64, protocol};
+use super::{single, Error, TypedHeader};
+
+/// `Authorization`, an authentication scheme with its credentials.
+///
+/// ```
+/// use webserver::header::typed::{Authorization, TypedHeader};
+///
+/// let authorization = Authorization::basic("Aladdin", "open sesame");
+/// assert_eq!(authorization.encode(), "Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ==");
+/// assert_eq!(authorization.as_basic(), Some(("Aladdin".to_string(), "open sesame".to_string())));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Authorization {
+  scheme: String,
+  credentials: String
+}
+
+impl Authorization {
+  pub fn new(scheme: &str, credentials: &str) -> Authorization {
+    Authorization {
+      scheme: scheme.to_string(),
+      credentials: credentials.to_string()
+    }
+  }
+
+  /// The `Basic` scheme, see RFC 7617.
+  pub fn basic(user: &str, password: &str) -> Authorization {
+    Authorization::new("Basic", &base64::encode(format!("{user}:{password}").as_bytes()))
+  }
+
+  /// The `Bearer` scheme, see RFC 6750.
+  pub fn bearer(token: &str) -> Authorization {
+    Authorization::new("Bearer", token)
+  }
+
+  pub fn scheme(&self) -> &str {
+    &self.scheme
+  }
+
+  pub fn credentials(&self) -> &str {
+    &self.credentials
+  }
+
+  /// The user and password of `Basic` credentials.
+  pub fn as_basic(&self) -> Option<(String, String)> {
+    if !self.scheme.eq_ignore_ascii_case("basic") {
+      return None
+    }
+
+    let decoded = String::from_utf8(base64::decode(&self.credentials)?).ok()?;
+    let (user, password) = decoded.split_once(':')?;
+    Some((user.to_string(), password.to_string()))
+  }
+
+  /// The token of `Bearer` credentials.
+  pub fn as_bearer(&self) -> Option<&str> {
+    self.scheme
+      .eq_ignore_ascii_case("bearer")
+      .then_some(self.credentials.as_str())
+  }
+}
+
+impl TypedHeader for Authorization {
+  const NAME: &'static str = "Authorization";
+
+  fn decode(values: &[String]) -> Result<Self, Error> {
+    let value = single::<Self>(values)?;
+    let (scheme, credentials) = value.split_once(' ').unwrap_or((value, ""));
+
+    if !protocol::is_token(scheme.as_bytes()) {
+      return Err(Error::InvalidValue(Self::NAME))
+    }
+    Ok(Authorization::new(scheme, credentials.trim_start_matches(' ')))
+  }
+
+  fn encode(&self) -> String {
+    match self.credentials.is_empty() {
+      true => self.scheme.clone(),
+      false => format!("{} {}", self.scheme, self.credentials)
+    }
+  }
+}
@@ -0,0 +1,138 @@
+use crate::{header::parser, protocol};
+use super::{list, Error, TypedHeader};
+
+/// `Cache-Control`, a list of directives with optional arguments,
+/// see RFC 9111, section 5.2.
+///
+/// ```
+/// use webserver::header::typed::{CacheControl, TypedHeader};
+///
+/// let cache_control = CacheControl::new().with("private").with_max_age(3600);
+/// assert_eq!(cache_control.encode(), "private, max-age=3600");
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CacheControl {
+  directives: Vec<(String, Option<String>)>
+}
+
+impl CacheControl {
+  pub fn new() -> CacheControl {
+    CacheControl::default()
+  }
+
+  /// Appends a directive without argument, e.g. `no-store`.
+  pub fn with(mut self, name: &str) -> CacheControl {
+    self.directives.push((name.to_ascii_lowercase(), None));
+    self
+  }
+
+  /// Appends a directive with an argument, e.g. `stale-if-error=60`.
+  pub fn with_value(mut self, name: &str, value: impl ToString) -> CacheControl {
+    self.directives.push((name.to_ascii_lowercase(), Some(value.to_string())));
+    self
+  }
+
+  pub fn with_max_age(self, seconds: u64) -> CacheControl {
+    self.with_value("max-age", seconds)
+  }
+
+  /// Looks up a directive by name, ignoring case. The inner option is its argument.
+  pub fn get(&self, name: &str) -> Option<Option<&str>> {
+    self.directives
+      .iter()
+      .find(|(key, _)| key.eq_ignore_ascii_case(name))
+      .map(|(_, value)| value.as_deref())
+  }
+
+  pub fn contains(&self, name: &str) -> bool {
+    self.get(name).is_some()
+  }
+
+  pub fn directives(&self) -> impl Iterator<Item = (&str, Option<&str>)> {
+    self.directives
+      .iter()
+      .map(|(key, value)| (key.as_str(), value.as_deref()))
+  }
+
+  // delta-seconds, see RFC 9111, section 1.2.2
+  fn seconds(&self, name: &str) -> Option<u64> {
+    let value = self.get(name)??;
+    if value.is_empty() || !value.bytes().all(|b| b.is_ascii_digit()) {
+      return None
+    }
+
+    // too large values are capped
+    Some(value.parse().unwrap_or(u64::MAX))
+  }
+
+  pub fn max_age(&self) -> Option<u64> {
+    self.seconds("max-age")
+  }
+
+  pub fn s_maxage(&self) -> Option<u64> {
+    self.seconds("s-maxage")
+  }
+
+  pub fn is_no_cache(&self) -> bool {
+    self.contains("no-cache")
+  }
+
+  pub fn is_no_store(&self) -> bool {
+    self.contains("no-store")
+  }
+
+  pub fn is_public(&self) -> bool {
+    self.contains("public")
+  }
+
+  pub fn is_private(&self) -> bool {
+    self.contains("private")
+  }
+
+  pub fn is_immutable(&self) -> bool {
+    self.contains("immutable")
+  }
+
+  pub fn must_revalidate(&self) -> bool {
+    self.contains("must-revalidate")
+  }
+}
+
+impl TypedHeader for CacheControl {
+  const NAME: &'static str = "Cache-Control";
+
+  fn decode(values: &[String]) -> Result<Self, Error> {
+    let mut cache_control = CacheControl::new();
+    for element in list(values)? {
+      if element.params().next().is_some() {
+        return Err(Error::InvalidValue(Self::NAME))
+      }
+
+      let (name, value) = match element.value().split_once('=') {
+        Some((name, value)) => (name, Some(parser::unquote(value)?)),
+        None => (element.value(), None)
+      };
+
+      if !protocol::is_token(name.as_bytes()) {
+        return Err(Error::InvalidValue(Self::NAME))
+      }
+
+      cache_control = match value {
+        Some(value) => cache_control.with_value(name, value),
+        None => cache_control.with(name)
+      };
+    }
+    Ok(cache_control)
+  }
+
+  fn encode(&self) -> String {
+    self.directives
+      .iter()
+      .map(|(name, value)| match value {
+        Some(value) => format!("{name}={}", parser::quote(value)),
+        None => name.clone()
+      })
+      .collect::<Vec<_>>()
+      .join(", ")
+  }
+}
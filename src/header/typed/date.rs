@@ -0,0 +1,205 @@
+use std::{fmt, time::{Duration, SystemTime, UNIX_EPOCH}};
+
+use super::{single, Error, TypedHeader};
+
+const DAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+const LONG_DAYS: [&str; 7] = ["Monday", "Tuesday", "Wednesday", "Thursday", "Friday", "Saturday", "Sunday"];
+const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+
+const SECONDS_PER_DAY: u64 = 86400;
+
+/// A timestamp with a one second precision, see RFC 9110, section 5.6.7.
+///
+/// Dates are sent in the IMF-fixdate format, the obsolete RFC 850
+/// and asctime formats are accepted as well.
+///
+/// ```
+/// use webserver::header::typed::HttpDate;
+///
+/// let date = HttpDate::parse("Sunday, 06-Nov-94 08:49:37 GMT").unwrap();
+/// assert_eq!(date.to_string(), "Sun, 06 Nov 1994 08:49:37 GMT");
+/// assert_eq!(HttpDate::parse("Sun Nov  6 08:49:37 1994"), Some(date));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct HttpDate(u64);
+
+// days since the epoch of a proleptic gregorian date,
+// see http://howardhinnant.github.io/date_algorithms.html
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+  let year = if month <= 2 { year - 1 } else { year };
+  let era = year.div_euclid(400);
+  let year_of_era = year - era * 400;
+  let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+  let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+  era * 146097 + day_of_era - 719468
+}
+
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+  let days = days + 719468;
+  let era = days.div_euclid(146097);
+  let day_of_era = days - era * 146097;
+  let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+  let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+  let month = (5 * day_of_year + 2) / 153;
+  let day = day_of_year - (153 * month + 2) / 5 + 1;
+  let month = if month < 10 { month + 3 } else { month - 9 };
+  (year_of_era + era * 400 + i64::from(month <= 2), month, day)
+}
+
+fn number(value: &str, digits: std::ops::RangeInclusive<usize>) -> Option<i64> {
+  if !digits.contains(&value.len()) || !value.bytes().all(|b| b.is_ascii_digit()) {
+    return None
+  }
+  value.parse().ok()
+}
+
+fn month(value: &str) -> Option<i64> {
+  MONTHS.iter().position(|m| *m == value).map(|m| m as i64 + 1)
+}
+
+fn time(value: &str) -> Option<(i64, i64, i64)> {
+  let mut parts = value.split(':');
+  let hour = number(parts.next()?, 2..=2)?;
+  let minute = number(parts.next()?, 2..=2)?;
+  let second = number(parts.next()?, 2..=2)?;
+  if parts.next().is_some() || hour > 23 || minute > 59 || second > 60 {
+    return None
+  }
+  Some((hour, minute, second))
+}
+
+impl HttpDate {
+  pub fn now() -> HttpDate {
+    HttpDate::from(SystemTime::now())
+  }
+
+  /// Parses any of the three HTTP-date formats.
+  pub fn parse(value: &str) -> Option<HttpDate> {
+    let parts: Vec<&str> = value.split(' ').filter(|p| !p.is_empty()).collect();
+
+    let (year, month, day, time) = match parts[..] {
+      // IMF-fixdate: `Sun, 06 Nov 1994 08:49:37 GMT`
+      [weekday, day, month, year, time, "GMT"] if DAYS.contains(&weekday.strip_suffix(',')?) => {
+        (number(year, 4..=4)?, self::month(month)?, number(day, 2..=2)?, time)
+      },
+      // RFC 850: `Sunday, 06-Nov-94 08:49:37 GMT`
+      [weekday, date, time, "GMT"] if LONG_DAYS.contains(&weekday.strip_suffix(',')?) => {
+        let mut date = date.split('-');
+        let (day, month, year) = (date.next()?, date.next()?, date.next()?);
+        if date.next().is_some() {
+          return None
+        }
+
+        // a year more than 50 years in the future is in the past century
+        let current_year = HttpDate::now().civil().0;
+        let mut year = 2000 + number(year, 2..=2)?;
+        if year > current_year + 50 {
+          year -= 100;
+        }
+        (year, self::month(month)?, number(day, 2..=2)?, time)
+      },
+      // asctime: `Sun Nov  6 08:49:37 1994`
+      [weekday, month, day, time, year] if DAYS.contains(&weekday) => {
+        (number(year, 4..=4)?, self::month(month)?, number(day, 1..=2)?, time)
+      },
+      _ => return None
+    };
+
+    let (hour, minute, second) = self::time(time)?;
+    let days = days_from_civil(year, month, day);
+    if days < 0 || civil_from_days(days) != (year, month, day) {
+      return None
+    }
+
+    let seconds = days as u64 * SECONDS_PER_DAY + (hour * 3600 + minute * 60 + second) as u64;
+    Some(HttpDate(seconds))
+  }
+
+  fn civil(&self) -> (i64, i64, i64) {
+    civil_from_days((self.0 / SECONDS_PER_DAY) as i64)
+  }
+
+  fn clock(&self) -> (u64, u64, u64) {
+    let seconds = self.0 % SECONDS_PER_DAY;
+    (seconds / 3600, seconds % 3600 / 60, seconds % 60)
+  }
+
+  /// The date in the Common Log Format, `10/Oct/2000:13:55:36 +0000`.
+  pub(crate) fn to_common_log(self) -> String {
+    let (year, month, day) = self.civil();
+    let (hour, minute, second) = self.clock();
+    format!("{day:02}/{}/{year:04}:{hour:02}:{minute:02}:{second:02} +0000", MONTHS[month as usize - 1])
+  }
+
+  /// The date as an RFC 3339 timestamp, `2000-10-10T13:55:36Z`.
+  pub(crate) fn to_rfc3339(self) -> String {
+    let (year, month, day) = self.civil();
+    let (hour, minute, second) = self.clock();
+    format!("{year:04}-{month:02}-{day:02}T{hour:02}:{minute:02}:{second:02}Z")
+  }
+}
+
+impl From<SystemTime> for HttpDate {
+  /// Truncates to the second, times before the epoch are clamped to it.
+  fn from(time: SystemTime) -> Self {
+    HttpDate(time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs()))
+  }
+}
+
+impl From<HttpDate> for SystemTime {
+  fn from(date: HttpDate) -> Self {
+    UNIX_EPOCH + Duration::from_secs(date.0)
+  }
+}
+
+impl fmt::Display for HttpDate {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let (year, month, day) = self.civil();
+    let (hour, minute, second) = self.clock();
+    let days = self.0 / SECONDS_PER_DAY;
+
+    // the epoch was a thursday
+    let weekday = DAYS[((days + 3) % 7) as usize];
+    write!(
+      f,
+      "{weekday}, {day:02} {} {year:04} {hour:02}:{minute:02}:{second:02} GMT",
+      MONTHS[month as usize - 1]
+    )
+  }
+}
+
+// header fields whose value is a single HTTP-date
+macro_rules! date_header {
+  ($($(#[$doc:meta])* $header:ident => $name:literal),*) => {
+    $(
+      $(#[$doc])*
+      #[derive(Debug, Clone, Copy, PartialEq)]
+      pub struct $header(pub HttpDate);
+
+      impl TypedHeader for $header {
+        const NAME: &'static str = $name;
+
+        fn decode(values: &[String]) -> Result<Self, Error> {
+          HttpDate::parse(single::<Self>(values)?)
+            .map($header)
+            .ok_or(Error::InvalidValue(Self::NAME))
+        }
+
+        fn encode(&self) -> String {
+          self.0.to_string()
+        }
+      }
+    )*
+  };
+}
+
+date_header! {
+  /// `Date`, when the message was originated.
+  Date => "Date",
+  /// `Last-Modified`, when the representation was last changed.
+  LastModified => "Last-Modified",
+  /// `If-Modified-Since`, when the cached representation was last changed.
+  IfModifiedSince => "If-Modified-Since",
+  /// `If-Unmodified-Since`, when the representation the client has was last changed.
+  IfUnmodifiedSince => "If-Unmodified-Since"
+}
@@ -0,0 +1,199 @@
+use super::{single, Error, TypedHeader};
+
+/// An entity tag, see RFC 9110, section 8.8.3.
+///
+/// ```
+/// use webserver::header::typed::ETag;
+///
+/// let strong = ETag::strong("xyzzy").unwrap();
+/// let weak = ETag::weak("xyzzy").unwrap();
+/// assert!(strong.weak_eq(&weak));
+/// assert!(!strong.strong_eq(&weak));
+/// assert_eq!(weak.to_string(), r#"W/"xyzzy""#);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ETag {
+  weak: bool,
+  tag: String
+}
+
+// etagc, see RFC 9110, section 8.8.3
+fn is_tag(tag: &str) -> bool {
+  tag.bytes().all(|b| b == 0x21 || (0x23..=0x7e).contains(&b) || b >= 0x80)
+}
+
+impl ETag {
+  /// Creates a strong entity tag, `tag` being the opaque part without quotes.
+  pub fn strong(tag: &str) -> Result<ETag, Error> {
+    ETag::new(false, tag)
+  }
+
+  /// Creates a weak entity tag, `tag` being the opaque part without quotes.
+  pub fn weak(tag: &str) -> Result<ETag, Error> {
+    ETag::new(true, tag)
+  }
+
+  fn new(weak: bool, tag: &str) -> Result<ETag, Error> {
+    if !is_tag(tag) {
+      return Err(Error::InvalidValue(Self::NAME))
+    }
+    Ok(ETag { weak, tag: tag.to_string() })
+  }
+
+  pub fn is_weak(&self) -> bool {
+    self.weak
+  }
+
+  pub fn tag(&self) -> &str {
+    &self.tag
+  }
+
+  /// Both tags are strong and identical.
+  pub fn strong_eq(&self, other: &ETag) -> bool {
+    !self.weak && !other.weak && self.tag == other.tag
+  }
+
+  /// Both tags are identical, whether they are weak or not.
+  pub fn weak_eq(&self, other: &ETag) -> bool {
+    self.tag == other.tag
+  }
+
+  // parses a comma separated list of entity tags
+  pub(crate) fn parse_list(field: &str) -> Option<Vec<ETag>> {
+    let mut tags = Vec::new();
+    let mut rest = field;
+    loop {
+      rest = rest.trim_start_matches([' ', '\t', ',']);
+      if rest.is_empty() {
+        return Some(tags)
+      }
+
+      let (weak, tail) = match rest.strip_prefix("W/") {
+        Some(tail) => (true, tail),
+        None => (false, rest)
+      };
+
+      let tail = tail.strip_prefix('"')?;
+      let end = tail.find('"')?;
+      tags.push(ETag::new(weak, &tail[..end]).ok()?);
+
+      rest = tail[end + 1..].trim_start_matches([' ', '\t']);
+      if !rest.is_empty() && !rest.starts_with(',') {
+        return None
+      }
+    }
+  }
+}
+
+impl TypedHeader for ETag {
+  const NAME: &'static str = "ETag";
+
+  fn decode(values: &[String]) -> Result<Self, Error> {
+    match ETag::parse_list(single::<Self>(values)?).as_deref() {
+      Some([tag]) => Ok(tag.clone()),
+      _ => Err(Error::InvalidValue(Self::NAME))
+    }
+  }
+
+  fn encode(&self) -> String {
+    match self.weak {
+      true => format!("W/\"{}\"", self.tag),
+      false => format!("\"{}\"", self.tag)
+    }
+  }
+}
+
+// decodes `*` or a list of entity tags, which may be split over several lines
+fn tags<H: TypedHeader>(values: &[String]) -> Result<Option<Vec<ETag>>, Error> {
+  if let [value] = values {
+    if value.trim_matches([' ', '\t']) == "*" {
+      return Ok(None)
+    }
+  }
+
+  let mut tags = Vec::new();
+  for value in values {
+    tags.extend(ETag::parse_list(value).ok_or(Error::InvalidValue(H::NAME))?);
+  }
+  Ok(Some(tags))
+}
+
+fn encode_tags(tags: Option<&[ETag]>) -> String {
+  match tags {
+    None => "*".to_string(),
+    Some(tags) => tags
+      .iter()
+      .map(ETag::encode)
+      .collect::<Vec<_>>()
+      .join(", ")
+  }
+}
+
+/// `If-Match`, the entity tags the representation must have for
+/// the request to be performed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum IfMatch {
+  /// `*`, any current representation.
+  Any,
+  Tags(Vec<ETag>)
+}
+
+impl IfMatch {
+  /// Whether a representation tagged `etag` is matched,
+  /// using the strong comparison as required by RFC 9110, section 13.1.1.
+  pub fn matches(&self, etag: &ETag) -> bool {
+    match self {
+      IfMatch::Any => true,
+      IfMatch::Tags(tags) => tags.iter().any(|tag| tag.strong_eq(etag))
+    }
+  }
+}
+
+impl TypedHeader for IfMatch {
+  const NAME: &'static str = "If-Match";
+
+  fn decode(values: &[String]) -> Result<Self, Error> {
+    Ok(tags::<Self>(values)?.map_or(IfMatch::Any, IfMatch::Tags))
+  }
+
+  fn encode(&self) -> String {
+    match self {
+      IfMatch::Any => encode_tags(None),
+      IfMatch::Tags(tags) => encode_tags(Some(tags))
+    }
+  }
+}
+
+/// `If-None-Match`, the entity tags a cached representation has.
+#[derive(Debug, Clone, PartialEq)]
+pub enum IfNoneMatch {
+  /// `*`, any current representation.
+  Any,
+  Tags(Vec<ETag>)
+}
+
+impl IfNoneMatch {
+  /// Whether a representation tagged `etag` is matched,
+  /// using the weak comparison as required by RFC 9110, section 13.1.2.
+  pub fn matches(&self, etag: &ETag) -> bool {
+    match self {
+      IfNoneMatch::Any => true,
+      IfNoneMatch::Tags(tags) => tags.iter().any(|tag| tag.weak_eq(etag))
+    }
+  }
+}
+
+impl TypedHeader for IfNoneMatch {
+  const NAME: &'static str = "If-None-Match";
+
+  fn decode(values: &[String]) -> Result<Self, Error> {
+    Ok(tags::<Self>(values)?.map_or(IfNoneMatch::Any, IfNoneMatch::Tags))
+  }
+
+  fn encode(&self) -> String {
+    match self {
+      IfNoneMatch::Any => encode_tags(None),
+      IfNoneMatch::Tags(tags) => encode_tags(Some(tags))
+    }
+  }
+}
@@ -0,0 +1,160 @@
+use std::ops;
+
+use super::{single, Error, ETag, HttpDate, TypedHeader};
+
+/// A range of bytes, see RFC 9110, section 14.1.2.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ByteRange {
+  /// `first-last`, both inclusive.
+  Bounded(u64, u64),
+  /// `first-`, up to the end.
+  From(u64),
+  /// `-length`, the last `length` bytes.
+  Suffix(u64)
+}
+
+impl ByteRange {
+  /// The bytes selected in a representation of `length` bytes,
+  /// `None` if the range is not satisfiable.
+  pub fn resolve(&self, length: u64) -> Option<ops::Range<u64>> {
+    match *self {
+      ByteRange::Bounded(first, last) if first < length => Some(first..length.min(last.saturating_add(1))),
+      ByteRange::From(first) if first < length => Some(first..length),
+      ByteRange::Suffix(suffix) if suffix > 0 && length > 0 => Some(length.saturating_sub(suffix)..length),
+      _ => None
+    }
+  }
+}
+
+/// `Range`, the byte ranges requested. Other range units are not supported.
+///
+/// ```
+/// use webserver::header::typed::{ByteRange, Range};
+///
+/// let range = Range::new(vec![ByteRange::Bounded(0, 499), ByteRange::Suffix(100)]);
+/// assert_eq!(range.resolve(1000), vec![0..500, 900..1000]);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Range {
+  ranges: Vec<ByteRange>
+}
+
+fn position(value: &str) -> Option<u64> {
+  if value.is_empty() || !value.bytes().all(|b| b.is_ascii_digit()) {
+    return None
+  }
+  value.parse().ok()
+}
+
+fn byte_range(value: &str) -> Option<ByteRange> {
+  match value.split_once('-')? {
+    ("", suffix) => position(suffix).map(ByteRange::Suffix),
+    (first, "") => position(first).map(ByteRange::From),
+    (first, last) => {
+      let (first, last) = (position(first)?, position(last)?);
+      (first <= last).then_some(ByteRange::Bounded(first, last))
+    }
+  }
+}
+
+impl Range {
+  pub fn new(ranges: Vec<ByteRange>) -> Range {
+    Range { ranges }
+  }
+
+  pub fn ranges(&self) -> &[ByteRange] {
+    &self.ranges
+  }
+
+  /// The satisfiable ranges in a representation of `length` bytes, in
+  /// the order they were requested. Empty if none is satisfiable.
+  pub fn resolve(&self, length: u64) -> Vec<ops::Range<u64>> {
+    self.ranges
+      .iter()
+      .filter_map(|range| range.resolve(length))
+      .collect()
+  }
+}
+
+impl TypedHeader for Range {
+  const NAME: &'static str = "Range";
+
+  fn decode(values: &[String]) -> Result<Self, Error> {
+    let value = single::<Self>(values)?;
+    let invalid = || Error::InvalidValue(Self::NAME);
+
+    let (unit, ranges) = value.split_once('=').ok_or_else(invalid)?;
+    if !unit.eq_ignore_ascii_case("bytes") {
+      return Err(invalid())
+    }
+
+    let ranges = ranges
+      .split(',')
+      .map(|range| range.trim_matches([' ', '\t']))
+      .filter(|range| !range.is_empty())
+      .map(|range| byte_range(range).ok_or_else(invalid))
+      .collect::<Result<Vec<_>, _>>()?;
+
+    if ranges.is_empty() {
+      return Err(invalid())
+    }
+    Ok(Range::new(ranges))
+  }
+
+  fn encode(&self) -> String {
+    let ranges: Vec<String> = self.ranges
+      .iter()
+      .map(|range| match range {
+        ByteRange::Bounded(first, last) => format!("{first}-{last}"),
+        ByteRange::From(first) => format!("{first}-"),
+        ByteRange::Suffix(suffix) => format!("-{suffix}")
+      })
+      .collect();
+
+    format!("bytes={}", ranges.join(", "))
+  }
+}
+
+/// `If-Range`, the representation a `Range` request is conditional on.
+#[derive(Debug, Clone, PartialEq)]
+pub enum IfRange {
+  Tag(ETag),
+  Date(HttpDate)
+}
+
+impl IfRange {
+  /// Whether the representation is still the one the client has, using
+  /// the strong comparison for entity tags and an exact match for dates,
+  /// see RFC 9110, section 13.1.5.
+  pub fn matches(&self, etag: Option<&ETag>, last_modified: Option<HttpDate>) -> bool {
+    match self {
+      IfRange::Tag(tag) => etag.is_some_and(|etag| etag.strong_eq(tag)),
+      IfRange::Date(date) => last_modified == Some(*date)
+    }
+  }
+}
+
+impl TypedHeader for IfRange {
+  const NAME: &'static str = "If-Range";
+
+  fn decode(values: &[String]) -> Result<Self, Error> {
+    let value = single::<Self>(values)?;
+    if value.starts_with('"') || value.starts_with("W/") {
+      return match ETag::parse_list(value).as_deref() {
+        Some([tag]) => Ok(IfRange::Tag(tag.clone())),
+        _ => Err(Error::InvalidValue(Self::NAME))
+      }
+    }
+
+    HttpDate::parse(value)
+      .map(IfRange::Date)
+      .ok_or(Error::InvalidValue(Self::NAME))
+  }
+
+  fn encode(&self) -> String {
+    match self {
+      IfRange::Tag(tag) => tag.encode(),
+      IfRange::Date(date) => date.to_string()
+    }
+  }
+}
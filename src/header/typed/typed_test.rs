@@ -0,0 +1,326 @@
+use super::*;
+
+fn values(values: &[&str]) -> Vec<String> {
+  values.iter().map(|v| v.to_string()).collect()
+}
+
+fn decode<H: TypedHeader>(field: &[&str]) -> Result<H, Error> {
+  H::decode(&values(field))
+}
+
+fn round_trip<H: TypedHeader + PartialEq + fmt::Debug>(header: H) {
+  assert_eq!(H::decode(&[header.encode()]).unwrap(), header);
+}
+
+mod content_test {
+  use super::*;
+
+  #[test]
+  fn content_type_test() {
+    let content_type: ContentType = decode(&["Text/HTML; Charset=\"UTF-8\"; q"]).unwrap();
+    assert_eq!(content_type.media_type(), "text/html");
+    assert_eq!(content_type.main_type(), "text");
+    assert_eq!(content_type.subtype(), "html");
+    assert_eq!(content_type.charset(), Some("UTF-8"));
+    assert_eq!(content_type.encode(), "text/html; charset=UTF-8; q");
+
+    assert!(decode::<ContentType>(&["text"]).is_err());
+    assert!(decode::<ContentType>(&["text/"]).is_err());
+    assert!(decode::<ContentType>(&["text/html", "text/css"]).is_err());
+    round_trip(ContentType::new("multipart/form-data").with_param("boundary", "a b"));
+  }
+
+  #[test]
+  fn content_length_test() {
+    assert_eq!(decode(&["42"]), Ok(ContentLength(42)));
+    assert_eq!(decode(&["42, 42", "42"]), Ok(ContentLength(42)));
+    assert!(decode::<ContentLength>(&["42, 43"]).is_err());
+    assert!(decode::<ContentLength>(&["+42"]).is_err());
+    assert!(decode::<ContentLength>(&[""]).is_err());
+    assert!(decode::<ContentLength>(&["99999999999999999999999"]).is_err());
+  }
+
+  #[test]
+  fn location_test() {
+    assert_eq!(decode(&["/a?b=c"]), Ok(Location("/a?b=c".to_string())));
+    assert!(decode::<Location>(&["/a b"]).is_err());
+    assert!(decode::<Location>(&[""]).is_err());
+  }
+}
+
+mod host_test {
+  use super::*;
+
+  #[test]
+  fn host_test() {
+    let host = |hostname: &str, port| Host { hostname: hostname.to_string(), port };
+    assert_eq!(decode(&["example.com"]), Ok(host("example.com", None)));
+    assert_eq!(decode(&["example.com:8080"]), Ok(host("example.com", Some(8080))));
+    assert_eq!(decode(&["[::1]:80"]), Ok(host("[::1]", Some(80))));
+    assert_eq!(decode(&["[::1]"]), Ok(host("[::1]", None)));
+    round_trip(host("localhost", Some(3000)));
+  }
+
+  #[test]
+  fn invalid_host_test() {
+    for value in ["", ":80", "a:", "a:b", "a:99999", "a b", "a/b", "[::1", "[::1]x", "a:1:2"] {
+      assert!(decode::<Host>(&[value]).is_err(), "Host should be rejected: {value:?}");
+    }
+    assert!(decode::<Host>(&["a", "b"]).is_err());
+  }
+}
+
+mod date_test {
+  use std::time::{Duration, SystemTime, UNIX_EPOCH};
+  use super::*;
+
+  const IMF_FIXDATE: &str = "Sun, 06 Nov 1994 08:49:37 GMT";
+
+  #[test]
+  fn formats_test() {
+    let date = HttpDate::parse(IMF_FIXDATE).unwrap();
+    assert_eq!(SystemTime::from(date), UNIX_EPOCH + Duration::from_secs(784111777));
+    assert_eq!(HttpDate::parse("Sunday, 06-Nov-94 08:49:37 GMT"), Some(date));
+    assert_eq!(HttpDate::parse("Sun Nov  6 08:49:37 1994"), Some(date));
+    assert_eq!(date.to_string(), IMF_FIXDATE);
+  }
+
+  #[test]
+  fn epoch_test() {
+    let date = HttpDate::from(UNIX_EPOCH);
+    assert_eq!(date.to_string(), "Thu, 01 Jan 1970 00:00:00 GMT");
+    assert_eq!(HttpDate::parse("Thu, 01 Jan 1970 00:00:00 GMT"), Some(date));
+    assert_eq!(HttpDate::parse("Wed, 31 Dec 1969 23:59:59 GMT"), None);
+  }
+
+  #[test]
+  fn leap_year_test() {
+    assert!(HttpDate::parse("Thu, 29 Feb 2024 12:00:00 GMT").is_some());
+    assert!(HttpDate::parse("Sat, 29 Feb 2025 12:00:00 GMT").is_none());
+    assert!(HttpDate::parse("Fri, 31 Apr 2025 12:00:00 GMT").is_none());
+  }
+
+  #[test]
+  fn invalid_date_test() {
+    for value in [
+      "Sun, 06 Nov 1994 08:49:37 UTC",
+      "Sun, 6 Nov 1994 08:49:37 GMT",
+      "Sun 06 Nov 1994 08:49:37 GMT",
+      "Sun, 06 nov 1994 08:49:37 GMT",
+      "Sun, 06 Nov 1994 24:00:00 GMT",
+      "Sun, 06 Nov 1994 08:49 GMT",
+      "Sunday, 06-Nov-1994 08:49:37 GMT",
+      "Sun, 06 Nov 1994 08:49:37 GMT trailing",
+      ""
+    ] {
+      assert!(HttpDate::parse(value).is_none(), "Date should be rejected: {value:?}");
+    }
+  }
+
+  #[test]
+  fn round_trip_test() {
+    for seconds in [0, 951782400, 1709208000, 4102444799] {
+      let date = HttpDate::from(UNIX_EPOCH + Duration::from_secs(seconds));
+      assert_eq!(HttpDate::parse(&date.to_string()), Some(date));
+    }
+    round_trip(Date(HttpDate::now()));
+    round_trip(LastModified(HttpDate::now()));
+    round_trip(IfModifiedSince(HttpDate::now()));
+    round_trip(IfUnmodifiedSince(HttpDate::now()));
+  }
+
+  #[test]
+  fn log_formats_test() {
+    let date = HttpDate::parse("Tue, 10 Oct 2000 13:55:36 GMT").unwrap();
+    assert_eq!(date.to_common_log(), "10/Oct/2000:13:55:36 +0000");
+    assert_eq!(date.to_rfc3339(), "2000-10-10T13:55:36Z");
+  }
+}
+
+mod cache_control_test {
+  use super::*;
+
+  #[test]
+  fn directives_test() {
+    let cache_control: CacheControl = decode(&["Public, max-age=60", "no-cache=\"Set-Cookie, X-Id\", s-maxage=\"30\""]).unwrap();
+    assert!(cache_control.is_public());
+    assert!(cache_control.is_no_cache());
+    assert!(!cache_control.is_no_store());
+    assert_eq!(cache_control.max_age(), Some(60));
+    assert_eq!(cache_control.s_maxage(), Some(30));
+    assert_eq!(cache_control.get("no-cache"), Some(Some("Set-Cookie, X-Id")));
+    assert_eq!(cache_control.get("public"), Some(None));
+    round_trip(cache_control);
+  }
+
+  #[test]
+  fn invalid_test() {
+    assert!(decode::<CacheControl>(&["max-age=\"60"]).is_err());
+    assert!(decode::<CacheControl>(&["no cache"]).is_err());
+    assert_eq!(decode::<CacheControl>(&["max-age=soon"]).unwrap().max_age(), None);
+  }
+}
+
+mod entity_tag_test {
+  use super::*;
+
+  #[test]
+  fn etag_test() {
+    let etag: ETag = decode(&["W/\"a,b\""]).unwrap();
+    assert!(etag.is_weak());
+    assert_eq!(etag.tag(), "a,b");
+    assert_eq!(decode(&["\"\""]), ETag::strong(""));
+
+    for value in ["a", "\"a", "\"a\" \"b\"", "\"a\", \"b\"", "w/\"a\"", "\"a b\""] {
+      assert!(decode::<ETag>(&[value]).is_err(), "ETag should be rejected: {value:?}");
+    }
+    assert!(ETag::strong("a\"b").is_err());
+    round_trip(ETag::weak("xyzzy").unwrap());
+  }
+
+  #[test]
+  fn comparison_test() {
+    let strong = ETag::strong("1").unwrap();
+    let weak = ETag::weak("1").unwrap();
+    assert!(strong.strong_eq(&strong));
+    assert!(!weak.strong_eq(&weak));
+    assert!(weak.weak_eq(&strong));
+    assert!(!strong.weak_eq(&ETag::strong("2").unwrap()));
+  }
+
+  #[test]
+  fn if_none_match_test() {
+    let if_none_match: IfNoneMatch = decode(&["\"a\", W/\"b\"", "\"c\""]).unwrap();
+    assert!(if_none_match.matches(&ETag::weak("a").unwrap()));
+    assert!(if_none_match.matches(&ETag::strong("b").unwrap()));
+    assert!(!if_none_match.matches(&ETag::strong("d").unwrap()));
+    round_trip(if_none_match);
+
+    assert_eq!(decode(&[" * "]), Ok(IfNoneMatch::Any));
+    assert!(decode::<IfNoneMatch>(&["*, \"a\""]).is_err());
+  }
+
+  #[test]
+  fn if_match_test() {
+    let if_match: IfMatch = decode(&["\"a\", W/\"b\""]).unwrap();
+    assert!(if_match.matches(&ETag::strong("a").unwrap()));
+    assert!(!if_match.matches(&ETag::weak("a").unwrap()));
+    assert!(!if_match.matches(&ETag::strong("b").unwrap()));
+    round_trip(if_match);
+
+    assert_eq!(decode(&["*"]), Ok(IfMatch::Any));
+  }
+}
+
+mod range_test {
+  use super::*;
+
+  #[test]
+  fn range_test() {
+    let range: Range = decode(&["bytes=0-499, 500-, -100,"]).unwrap();
+    assert_eq!(range.ranges(), &[ByteRange::Bounded(0, 499), ByteRange::From(500), ByteRange::Suffix(100)]);
+    assert_eq!(range.resolve(1000), vec![0..500, 500..1000, 900..1000]);
+    assert_eq!(range.resolve(200), vec![0..200, 100..200]);
+    round_trip(range);
+  }
+
+  #[test]
+  fn unsatisfiable_test() {
+    assert_eq!(ByteRange::From(10).resolve(10), None);
+    assert_eq!(ByteRange::Suffix(0).resolve(10), None);
+    assert_eq!(ByteRange::Suffix(10).resolve(0), None);
+    assert_eq!(ByteRange::Suffix(20).resolve(10), Some(0..10));
+    assert_eq!(ByteRange::Bounded(5, u64::MAX).resolve(10), Some(5..10));
+  }
+
+  #[test]
+  fn invalid_range_test() {
+    for value in ["bytes=", "bytes=5-1", "bytes=a-b", "bytes=1", "items=0-1", "0-1", "bytes=--1"] {
+      assert!(decode::<Range>(&[value]).is_err(), "Range should be rejected: {value:?}");
+    }
+  }
+
+  #[test]
+  fn if_range_test() {
+    let etag = ETag::strong("v1").unwrap();
+    let date = HttpDate::parse("Sun, 06 Nov 1994 08:49:37 GMT").unwrap();
+
+    let if_range: IfRange = decode(&["\"v1\""]).unwrap();
+    assert!(if_range.matches(Some(&etag), None));
+    assert!(!if_range.matches(Some(&ETag::weak("v1").unwrap()), None));
+    assert!(!decode::<IfRange>(&["W/\"v1\""]).unwrap().matches(Some(&etag), None));
+    round_trip(if_range);
+
+    let if_range: IfRange = decode(&["Sun, 06 Nov 1994 08:49:37 GMT"]).unwrap();
+    assert!(if_range.matches(None, Some(date)));
+    assert!(!if_range.matches(Some(&etag), None));
+    round_trip(if_range);
+
+    assert!(decode::<IfRange>(&["\"a\", \"b\""]).is_err());
+    assert!(decode::<IfRange>(&["yesterday"]).is_err());
+  }
+}
+
+mod authorization_test {
+  use super::*;
+
+  #[test]
+  fn basic_test() {
+    let authorization: Authorization = decode(&["basic dXNlcjpwYTpzcw=="]).unwrap();
+    assert_eq!(authorization.as_basic(), Some(("user".to_string(), "pa:ss".to_string())));
+    assert_eq!(authorization.as_bearer(), None);
+
+    let authorization: Authorization = decode(&["Basic !!!!"]).unwrap();
+    assert_eq!(authorization.as_basic(), None);
+    round_trip(Authorization::basic("", ""));
+  }
+
+  #[test]
+  fn bearer_test() {
+    let authorization: Authorization = decode(&["Bearer mF_9.B5f-4.1JqM"]).unwrap();
+    assert_eq!(authorization.as_bearer(), Some("mF_9.B5f-4.1JqM"));
+    assert!(decode::<Authorization>(&["Bear/er token"]).is_err());
+  }
+
+  #[test]
+  fn base64_test() {
+    use crate::base64;
+
+    for input in ["", "f", "fo", "foo", "foob", "fooba", "foobar"] {
+      assert_eq!(base64::decode(&base64::encode(input.as_bytes())).unwrap(), input.as_bytes());
+    }
+    assert_eq!(base64::encode(b"foobar"), "Zm9vYmFy");
+    assert_eq!(base64::decode("Zm9vYg"), None);
+    assert_eq!(base64::decode("Zg==Zg=="), None);
+    assert_eq!(base64::decode("Zm9=v"), None);
+  }
+}
+
+mod list_test {
+  use super::*;
+
+  #[test]
+  fn vary_test() {
+    let vary: Vary = decode(&["Accept-Encoding, origin", "Accept"]).unwrap();
+    assert!(vary.contains("accept-encoding"));
+    assert!(vary.contains("Origin"));
+    assert!(!vary.contains("cookie"));
+    round_trip(vary.clone());
+
+    let mut inserted = vary.clone();
+    inserted.insert("ORIGIN");
+    assert_eq!(inserted, vary);
+    inserted.insert("Cookie");
+    assert_eq!(inserted.encode(), "Accept-Encoding, origin, Accept, Cookie");
+
+    assert_eq!(decode(&["accept, *"]), Ok(Vary::Any));
+    assert!(decode::<Vary>(&["a b"]).is_err());
+  }
+
+  #[test]
+  fn allow_test() {
+    assert_eq!(decode(&["GET, HEAD", "POST"]), Ok(Allow(values(&["GET", "HEAD", "POST"]))));
+    assert_eq!(decode(&[""]), Ok(Allow(Vec::new())));
+    assert!(decode::<Allow>(&["GET;q=1"]).is_err());
+  }
+}
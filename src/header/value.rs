@@ -0,0 +1,106 @@
+use std::{borrow::Cow, fmt};
+
+use super::{parser, Error};
+
+/// A single header field value with its parameters,
+/// e.g. `text/html; charset=utf-8`.
+///
+/// ```
+/// use webserver::header::Value;
+///
+/// let value = Value::parse(r#"form-data; Name="file"; filename="a \"b\".txt""#).unwrap();
+/// assert_eq!(value.value(), "form-data");
+/// assert_eq!(value.param("name"), Some("file"));
+/// assert_eq!(value.param("filename"), Some(r#"a "b".txt"#));
+/// assert_eq!(value.to_string(), r#"form-data; Name=file; filename="a \"b\".txt""#);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Value<'a> {
+  value: Cow<'a, str>,
+  params: Vec<(Cow<'a, str>, Cow<'a, str>)>
+}
+
+impl<'a> Value<'a> {
+  pub fn new(value: impl Into<Cow<'a, str>>) -> Value<'a> {
+    Value {
+      value: value.into(),
+      params: Vec::new()
+    }
+  }
+
+  /// Parses the first value of a header field.
+  pub fn parse(field: &'a str) -> Result<Value<'a>, Error> {
+    parser::single_value_field(field).map(|v| v.unwrap_or_else(|| Value::new("")))
+  }
+
+  /// Parses every value of a comma separated header field.
+  pub fn parse_list(field: &'a str) -> Result<Vec<Value<'a>>, Error> {
+    parser::multi_values_field(field)
+  }
+
+  /// Appends a parameter.
+  pub fn with_param(mut self, name: impl Into<Cow<'a, str>>, value: impl Into<Cow<'a, str>>) -> Value<'a> {
+    self.params.push((name.into(), value.into()));
+    self
+  }
+
+  /// The value without its parameters. A value sent as a quoted string
+  /// as a whole is unquoted.
+  pub fn value(&self) -> &str {
+    &self.value
+  }
+
+  /// Looks up a parameter by name, ignoring case. Parameters without
+  /// a value (e.g. `; secure`) have an empty value.
+  pub fn param(&self, name: &str) -> Option<&str> {
+    self.params
+      .iter()
+      .find(|(key, _)| key.eq_ignore_ascii_case(name))
+      .map(|(_, value)| value.as_ref())
+  }
+
+  /// Iterates over the parameters, in the order they were sent.
+  pub fn params(&self) -> impl Iterator<Item = (&str, &str)> {
+    self.params
+      .iter()
+      .map(|(key, value)| (key.as_ref(), value.as_ref()))
+  }
+
+  /// Detaches the value from the header field it was parsed from.
+  pub fn into_owned(self) -> Value<'static> {
+    Value {
+      value: Cow::Owned(self.value.into_owned()),
+      params: self.params
+        .into_iter()
+        .map(|(key, value)| (Cow::Owned(key.into_owned()), Cow::Owned(value.into_owned())))
+        .collect()
+    }
+  }
+}
+
+/// Serializes back into a header field value. The value is quoted when
+/// it would not be parsed back as is, e.g. once unquoted from `"a, b"`,
+/// and parameter values when they are not tokens. Parameters with an
+/// empty value are written without `=`.
+impl fmt::Display for Value<'_> {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    // `text/html` or `W/"tag"` are not tokens, but are sent as they are
+    let is_bare = parser::single_value_field(&self.value).is_ok_and(|value| match value {
+      Some(value) => value.value == self.value && value.params.is_empty(),
+      // an empty value is skipped unless quoted, as are empty list elements
+      None => self.value.is_empty() && self.params.is_empty()
+    });
+    match is_bare {
+      true => write!(f, "{}", self.value)?,
+      false => write!(f, "{}", parser::quote(&self.value))?
+    }
+    for (key, value) in self.params.iter() {
+      if value.is_empty() {
+        write!(f, "; {key}")?;
+      } else {
+        write!(f, "; {key}={}", parser::quote(value))?;
+      }
+    }
+    Ok(())
+  }
+}
@@ -1,13 +1,42 @@
-mod debug;
+mod base64;
+mod random;
 mod stream;
 mod router;
 
+pub mod access_log;
 pub mod app;
+#[cfg(feature = "compression")]
+pub mod compression;
+pub mod conditional;
+pub mod cors;
+pub mod config;
+pub mod cookie;
+pub mod error;
+pub mod files;
+pub mod form;
+pub mod metrics;
 pub mod request;
+pub mod request_id;
 pub mod response;
 pub mod header;
+pub mod multipart;
+pub mod parser;
 pub mod protocol;
+pub mod rate_limit;
+pub mod negotiation;
+pub mod session;
+
+#[cfg(feature = "fuzzing")]
+#[doc(hidden)]
+pub mod fuzzing {
+  pub use crate::stream::parse_stream;
+  pub use crate::header::parser::{value, multi_values_field, single_value_field};
+}
 
 pub use app::App;
+pub use config::ServerConfig;
+pub use error::HttpError;
+pub use router::Context;
 pub use router::Handle;
+pub use router::HookType;
 pub use router::Return;
\ No newline at end of file
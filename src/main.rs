@@ -1,7 +1,7 @@
-use webserver::{App, Handle, Return};
+use webserver::App;
 
 fn main() {
-    let mut app = App::new();
+    let app = App::new();
 
     app.listen("0.0.0.0:8080").unwrap();
 }
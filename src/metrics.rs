@@ -0,0 +1,239 @@
+//! Request metrics, exposed in the Prometheus text format.
+//!
+//! The metrics are recorded by the app rather than by a handler, so that
+//! failed requests and requests that could not be parsed are counted too.
+//!
+//! ```no_run
+//! use webserver::{App, metrics::Metrics};
+//!
+//! let mut app = App::new();
+//! app.metrics("/metrics", Metrics::new());
+//! app.listen("127.0.0.1:8080").unwrap();
+//! ```
+//!
+//! | Metric | Type | Labels |
+//! |---|---|---|
+//! | `http_requests_total` | counter | `method`, `route`, `status` |
+//! | `http_request_duration_seconds` | histogram | `method`, `route` |
+//! | `http_requests_in_flight` | gauge | |
+//! | `http_connections_total` | counter | |
+//! | `http_connections_open` | gauge | |
+//! | `http_parse_errors_total` | counter | `error` |
+//! | `http_received_bytes_total` | counter | |
+//! | `http_sent_bytes_total` | counter | |
+//!
+//! `route` is the pattern of the handler that ended the request, e.g.
+//! `/users/:id`, or empty when none did, so that the number of series
+//! does not grow with the paths requested.
+use std::{cell::{Cell, RefCell}, collections::BTreeMap, fmt::Write as _, io::{self, Read, Write}, rc::Rc, time::Duration};
+
+use crate::{router::Context, Handle};
+
+#[cfg(test)]
+mod metrics_test;
+
+/// The default latency buckets, in seconds, the ones of the Prometheus clients.
+pub const DEFAULT_BUCKETS: [f64; 11] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+/// The content type of the text exposition format.
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+#[derive(Debug, Clone)]
+struct Histogram {
+  // not cumulative, summed when rendered
+  counts: Vec<u64>,
+  sum: f64,
+  count: u64
+}
+
+#[derive(Debug, Default)]
+struct Registry {
+  requests: BTreeMap<(String, String, u16), u64>,
+  durations: BTreeMap<(String, String), Histogram>,
+  in_flight: u64,
+  connections: u64,
+  open_connections: u64,
+  parse_errors: BTreeMap<&'static str, u64>,
+  received_bytes: u64,
+  sent_bytes: u64
+}
+
+/// The metrics of an app, see the [module documentation](self).
+///
+/// Clones share the same metrics.
+#[derive(Debug, Clone)]
+pub struct Metrics {
+  buckets: Rc<[f64]>,
+  registry: Rc<RefCell<Registry>>
+}
+
+impl Default for Metrics {
+  fn default() -> Self {
+    Metrics::new()
+  }
+}
+
+// escapes a label value, see the text exposition format
+fn label(value: &str) -> String {
+  value
+    .replace('\\', "\\\\")
+    .replace('"', "\\\"")
+    .replace('\n', "\\n")
+}
+
+fn header(text: &mut String, name: &str, kind: &str, help: &str) {
+  let _ = writeln!(text, "# HELP {name} {help}");
+  let _ = writeln!(text, "# TYPE {name} {kind}");
+}
+
+impl Metrics {
+  /// Metrics with the [`DEFAULT_BUCKETS`].
+  pub fn new() -> Metrics {
+    Metrics {
+      buckets: Rc::from(&DEFAULT_BUCKETS[..]),
+      registry: Rc::default()
+    }
+  }
+
+  /// The upper bounds of the latency buckets, in seconds.
+  pub fn with_buckets(mut self, buckets: &[f64]) -> Metrics {
+    let mut buckets = buckets.to_vec();
+    buckets.retain(|bound| bound.is_finite());
+    buckets.sort_by(f64::total_cmp);
+    buckets.dedup();
+    self.buckets = Rc::from(buckets);
+    self
+  }
+
+  /// Answers with the metrics, for the route they are exposed on.
+  pub fn handle(&self) -> Handle {
+    let metrics = self.clone();
+    Handle::main(move |ctx: &mut Context| {
+      ctx.res.content_type(CONTENT_TYPE).send_body(metrics.render().into_bytes())
+    })
+  }
+
+  /// The metrics in the Prometheus text format.
+  pub fn render(&self) -> String {
+    let registry = self.registry.borrow();
+    let mut text = String::new();
+
+    header(&mut text, "http_requests_total", "counter", "Requests answered, by method, route and status.");
+    for ((method, route, status), count) in &registry.requests {
+      let _ = writeln!(text, "http_requests_total{{method=\"{method}\",route=\"{}\",status=\"{status}\"}} {count}", label(route));
+    }
+
+    header(&mut text, "http_request_duration_seconds", "histogram", "Time to answer requests, by method and route.");
+    for ((method, route), histogram) in &registry.durations {
+      let labels = format!("method=\"{method}\",route=\"{}\"", label(route));
+      let mut cumulative = 0;
+      for (bound, count) in self.buckets.iter().zip(&histogram.counts) {
+        cumulative += count;
+        let _ = writeln!(text, "http_request_duration_seconds_bucket{{{labels},le=\"{bound}\"}} {cumulative}");
+      }
+      let _ = writeln!(text, "http_request_duration_seconds_bucket{{{labels},le=\"+Inf\"}} {}", histogram.count);
+      let _ = writeln!(text, "http_request_duration_seconds_sum{{{labels}}} {}", histogram.sum);
+      let _ = writeln!(text, "http_request_duration_seconds_count{{{labels}}} {}", histogram.count);
+    }
+
+    header(&mut text, "http_requests_in_flight", "gauge", "Requests being answered.");
+    let _ = writeln!(text, "http_requests_in_flight {}", registry.in_flight);
+
+    header(&mut text, "http_connections_total", "counter", "Connections accepted.");
+    let _ = writeln!(text, "http_connections_total {}", registry.connections);
+
+    header(&mut text, "http_connections_open", "gauge", "Connections open.");
+    let _ = writeln!(text, "http_connections_open {}", registry.open_connections);
+
+    header(&mut text, "http_parse_errors_total", "counter", "Requests that could not be parsed, by error.");
+    for (error, count) in &registry.parse_errors {
+      let _ = writeln!(text, "http_parse_errors_total{{error=\"{error}\"}} {count}");
+    }
+
+    header(&mut text, "http_received_bytes_total", "counter", "Bytes read from connections.");
+    let _ = writeln!(text, "http_received_bytes_total {}", registry.received_bytes);
+
+    header(&mut text, "http_sent_bytes_total", "counter", "Bytes written to connections.");
+    let _ = writeln!(text, "http_sent_bytes_total {}", registry.sent_bytes);
+
+    text
+  }
+
+  pub(crate) fn connection_opened(&self) {
+    let mut registry = self.registry.borrow_mut();
+    registry.connections += 1;
+    registry.open_connections += 1;
+  }
+
+  pub(crate) fn connection_closed(&self, received: u64, sent: u64) {
+    let mut registry = self.registry.borrow_mut();
+    registry.open_connections = registry.open_connections.saturating_sub(1);
+    registry.received_bytes += received;
+    registry.sent_bytes += sent;
+  }
+
+  pub(crate) fn parse_failed(&self, error: &'static str) {
+    *self.registry.borrow_mut().parse_errors.entry(error).or_default() += 1;
+  }
+
+  pub(crate) fn request_started(&self) {
+    self.registry.borrow_mut().in_flight += 1;
+  }
+
+  /// Records a request answered with `status`, `route` being empty
+  /// when no handler ended it. Requests left unanswered are not counted.
+  pub(crate) fn request_finished(&self, method: &str, route: &str, status: Option<u16>, latency: Duration) {
+    let mut registry = self.registry.borrow_mut();
+    registry.in_flight = registry.in_flight.saturating_sub(1);
+    let Some(status) = status else {
+      return
+    };
+    *registry.requests.entry((method.to_string(), route.to_string(), status)).or_default() += 1;
+
+    let seconds = latency.as_secs_f64();
+    let histogram = registry
+      .durations
+      .entry((method.to_string(), route.to_string()))
+      .or_insert_with(|| Histogram { counts: vec![0; self.buckets.len()], sum: 0.0, count: 0 });
+    if let Some(bucket) = self.buckets.iter().position(|bound| seconds <= *bound) {
+      histogram.counts[bucket] += 1;
+    }
+    histogram.sum += seconds;
+    histogram.count += 1;
+  }
+}
+
+/// A reader or writer counting the bytes that went through it.
+pub(crate) struct Counted<T> {
+  inner: T,
+  count: Rc<Cell<u64>>
+}
+
+impl<T> Counted<T> {
+  /// Wraps `inner`, the count being readable through the returned cell
+  /// once `inner` has been moved away.
+  pub(crate) fn new(inner: T) -> (Counted<T>, Rc<Cell<u64>>) {
+    let count = Rc::new(Cell::new(0));
+    (Counted { inner, count: count.clone() }, count)
+  }
+}
+
+impl<T: Read> Read for Counted<T> {
+  fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+    let read = self.inner.read(buf)?;
+    self.count.set(self.count.get() + read as u64);
+    Ok(read)
+  }
+}
+
+impl<T: Write> Write for Counted<T> {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    let written = self.inner.write(buf)?;
+    self.count.set(self.count.get() + written as u64);
+    Ok(written)
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    self.inner.flush()
+  }
+}
@@ -0,0 +1,113 @@
+use crate::{app::app_test::{get, send}, error::HttpError, protocol::StatusCode, App, Handle, Return};
+use super::*;
+
+fn app(metrics: Metrics) -> App {
+  let mut app = App::new();
+  app.metrics("/metrics", metrics);
+  app.get("/users/:id", Handle::main(|ctx| ctx.res.send_body(b"user".to_vec())));
+  app.get("/fail", Handle::main(|_| Err(Box::new(HttpError::new(StatusCode::Conflict, "Already exists")))));
+  app.get("/silent", Handle::main(|_| Ok(Return::Next)));
+  app
+}
+
+// the value of the sample with exactly `series` as its name and labels
+fn sample(text: &str, series: &str) -> Option<String> {
+  text
+    .lines()
+    .find_map(|line| line.strip_prefix(series)?.strip_prefix(' '))
+    .map(String::from)
+}
+
+mod record_test {
+  use super::*;
+
+  #[test]
+  fn requests_test() {
+    let metrics = Metrics::new();
+    let app = app(metrics.clone());
+    get(&app, "/users/1", "");
+    get(&app, "/users/2", "");
+    get(&app, "/fail", "");
+    get(&app, "/missing", "");
+    get(&app, "/silent", "");
+
+    let text = metrics.render();
+    assert_eq!(sample(&text, r#"http_requests_total{method="GET",route="/users/:id",status="200"}"#).as_deref(), Some("2"));
+    assert_eq!(sample(&text, r#"http_requests_total{method="GET",route="/fail",status="409"}"#).as_deref(), Some("1"));
+    assert_eq!(sample(&text, r#"http_requests_total{method="GET",route="",status="404"}"#), None);
+    assert!(!text.contains("route=\"/silent\",status"));
+    assert_eq!(sample(&text, "http_requests_in_flight").as_deref(), Some("0"));
+    assert_eq!(sample(&text, "http_connections_total").as_deref(), Some("5"));
+    assert_eq!(sample(&text, "http_connections_open").as_deref(), Some("0"));
+  }
+
+  #[test]
+  fn latency_test() {
+    let metrics = Metrics::new().with_buckets(&[1.0, 0.5, f64::INFINITY]);
+    let app = app(metrics.clone());
+    get(&app, "/users/1", "");
+
+    let text = metrics.render();
+    let labels = r#"method="GET",route="/users/:id""#;
+    assert_eq!(sample(&text, &format!("http_request_duration_seconds_bucket{{{labels},le=\"0.5\"}}")).as_deref(), Some("1"));
+    assert_eq!(sample(&text, &format!("http_request_duration_seconds_bucket{{{labels},le=\"1\"}}")).as_deref(), Some("1"));
+    assert_eq!(sample(&text, &format!("http_request_duration_seconds_bucket{{{labels},le=\"+Inf\"}}")).as_deref(), Some("1"));
+    assert_eq!(sample(&text, &format!("http_request_duration_seconds_count{{{labels}}}")).as_deref(), Some("1"));
+    assert!(text.contains("# TYPE http_request_duration_seconds histogram\n"));
+  }
+
+  #[test]
+  fn parse_errors_test() {
+    let metrics = Metrics::new();
+    let app = app(metrics.clone());
+    send(&app, "GET / HTTP/1.1\nHost: localhost\r\n\r\n");
+    send(&app, "GET / HTTP/1.1\nHost: localhost\r\n\r\n");
+    send(&app, "");
+
+    let text = metrics.render();
+    assert_eq!(sample(&text, r#"http_parse_errors_total{error="BareLineFeed"}"#).as_deref(), Some("2"));
+    assert_eq!(sample(&text, r#"http_parse_errors_total{error="EmptyRequest"}"#).as_deref(), Some("1"));
+    assert_eq!(sample(&text, "http_requests_in_flight").as_deref(), Some("0"));
+  }
+
+  #[test]
+  fn bytes_test() {
+    let metrics = Metrics::new();
+    let app = app(metrics.clone());
+    let raw = "GET /users/1 HTTP/1.1\r\nHost: localhost\r\n\r\n";
+    let response = send(&app, raw);
+
+    let text = metrics.render();
+    assert_eq!(sample(&text, "http_received_bytes_total"), Some(raw.len().to_string()));
+    assert_eq!(sample(&text, "http_sent_bytes_total"), Some(response.len().to_string()));
+  }
+}
+
+mod render_test {
+  use super::*;
+
+  #[test]
+  fn endpoint_test() {
+    let app = app(Metrics::new());
+    get(&app, "/users/1", "");
+    let response = get(&app, "/metrics", "");
+
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(response.contains("Content-Type: text/plain; version=0.0.4; charset=utf-8\r\n"));
+    let body = response.split_once("\r\n\r\n").unwrap().1;
+    assert!(body.starts_with("# HELP http_requests_total "));
+    // the request for the metrics is still in flight
+    assert_eq!(sample(body, "http_requests_in_flight").as_deref(), Some("1"));
+    assert!(body.lines().all(|line| line.starts_with('#') || line.rsplit_once(' ').is_some_and(|(_, value)| value.parse::<f64>().is_ok())));
+  }
+
+  #[test]
+  fn label_test() {
+    assert_eq!(label("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+
+    let metrics = Metrics::new();
+    metrics.request_started();
+    metrics.request_finished("GET", "/say/\"hi\"", Some(200), Duration::ZERO);
+    assert!(metrics.render().contains(r#"route="/say/\"hi\"",status="200"} 1"#));
+  }
+}
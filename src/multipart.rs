@@ -0,0 +1,428 @@
+//! Streaming `multipart/form-data` parsing, as described in RFC 7578.
+//!
+//! Parts are read one after the other straight from the request body,
+//! so uploaded files are never held in memory as a whole.
+//!
+//! ```
+//! use std::io::{Cursor, Read};
+//! use webserver::multipart::Multipart;
+//!
+//! let body = "--XyZ\r\n\
+//!   Content-Disposition: form-data; name=\"title\"\r\n\r\n\
+//!   Holidays\r\n\
+//!   --XyZ\r\n\
+//!   Content-Disposition: form-data; name=\"photo\"; filename=\"beach.jpg\"\r\n\
+//!   Content-Type: image/jpeg\r\n\r\n\
+//!   <jpeg>\r\n\
+//!   --XyZ--\r\n";
+//!
+//! let mut multipart = Multipart::new(Cursor::new(body), "XyZ").unwrap();
+//!
+//! let mut title = multipart.next_part().unwrap().unwrap();
+//! assert_eq!(title.name(), "title");
+//! assert_eq!(title.text().unwrap(), "Holidays");
+//!
+//! let mut photo = multipart.next_part().unwrap().unwrap();
+//! assert_eq!(photo.filename(), Some("beach.jpg"));
+//! assert_eq!(photo.content_type().media_type(), "image/jpeg");
+//! let mut bytes = Vec::new();
+//! photo.read_to_end(&mut bytes).unwrap();
+//! assert_eq!(bytes, b"<jpeg>");
+//!
+//! assert!(multipart.next_part().unwrap().is_none());
+//! ```
+use std::{fmt, io::{self, Read}};
+
+use crate::{error::HttpError, header::{typed::ContentType, Headers, Value}, protocol::StatusCode, request};
+
+#[cfg(test)]
+mod multipart_test;
+
+const BUFFER_SIZE: usize = 8 * 1024;
+
+#[derive(Debug)]
+pub enum Error {
+  InvalidBoundary,
+  /// The body does not follow the multipart syntax.
+  Malformed(&'static str),
+  InvalidHeader(String),
+  /// A part without `Content-Disposition: form-data` and a field name.
+  MissingName,
+  InvalidText(String),
+  PartTooLarge(u64),
+  TooLarge(u64),
+  TooManyParts(usize),
+  HeadersTooLarge(usize),
+  Io(io::Error)
+}
+
+impl Error {
+  /// Status code to answer with when the body cannot be read.
+  pub fn status(&self) -> StatusCode {
+    match self {
+      Error::PartTooLarge(_) |
+      Error::TooLarge(_) |
+      Error::TooManyParts(_) |
+      Error::HeadersTooLarge(_) => StatusCode::ContentTooLarge,
+      Error::Io(err) => request::body_error(err).status().clone(),
+      _ => StatusCode::BadRequest
+    }
+  }
+}
+
+impl fmt::Display for Error {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Error::InvalidBoundary => write!(f, "Invalid multipart boundary"),
+      Error::Malformed(reason) => write!(f, "Malformed multipart body: {reason}"),
+      Error::InvalidHeader(name) => write!(f, "Invalid part header: {name}"),
+      Error::MissingName => write!(f, "Part is missing a form-data name"),
+      Error::InvalidText(name) => write!(f, "Part is not valid UTF-8: {name}"),
+      Error::PartTooLarge(size) => write!(f, "Part is too large, exceeded size limit: {size}"),
+      Error::TooLarge(size) => write!(f, "Multipart body is too large, exceeded size limit: {size}"),
+      Error::TooManyParts(count) => write!(f, "Too many parts, exceeded count limit: {count}"),
+      Error::HeadersTooLarge(size) => write!(f, "Part headers are too large, exceeded size limit: {size}"),
+      Error::Io(err) => write!(f, "Invalid body: {err}")
+    }
+  }
+}
+
+impl std::error::Error for Error {}
+
+impl From<Error> for HttpError {
+  fn from(err: Error) -> Self {
+    HttpError::new(err.status(), err.to_string())
+  }
+}
+
+impl From<Error> for io::Error {
+  fn from(err: Error) -> Self {
+    match err {
+      Error::Io(err) => err,
+      err => io::Error::new(io::ErrorKind::InvalidData, err)
+    }
+  }
+}
+
+// bchars, see RFC 2046, section 5.1.1
+fn is_boundary(boundary: &str) -> bool {
+  let is_bchar = |b: u8| b.is_ascii_alphanumeric() || b"'()+_,-./:=? ".contains(&b);
+  (1..=70).contains(&boundary.len()) && !boundary.ends_with(' ') && boundary.bytes().all(is_bchar)
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+  haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum State {
+  Preamble,
+  Body,
+  // the buffer starts with the delimiter
+  Delimiter,
+  Done
+}
+
+/// Reads the parts of a `multipart/form-data` body, see the
+/// [module documentation](self).
+///
+/// By default a body can hold at most 100 parts, with up to 8 KiB of
+/// headers each. Read through `Request::multipart`, part and total sizes
+/// are bounded by `ServerConfig::max_parsed_body_size`, otherwise only by
+/// `ServerConfig::max_body_size`, unless set here.
+pub struct Multipart {
+  reader: Box<dyn Read>,
+  delimiter: Vec<u8>,
+  buffer: Vec<u8>,
+  state: State,
+  total: u64,
+  part_size: u64,
+  parts: usize,
+  max_part_size: u64,
+  max_total_size: u64,
+  max_parts: usize,
+  max_header_size: usize
+}
+
+impl Multipart {
+  /// Reads `reader` as a body delimited by `boundary`, usually the
+  /// `boundary` parameter of the `Content-Type` header field.
+  pub fn new(reader: impl Read + 'static, boundary: &str) -> Result<Multipart, Error> {
+    if !is_boundary(boundary) {
+      return Err(Error::InvalidBoundary)
+    }
+
+    Ok(Multipart {
+      reader: Box::new(reader),
+      delimiter: format!("\r\n--{boundary}").into_bytes(),
+      // the first delimiter is not preceded by a line break
+      buffer: b"\r\n".to_vec(),
+      state: State::Preamble,
+      total: 0,
+      part_size: 0,
+      parts: 0,
+      max_part_size: u64::MAX,
+      max_total_size: u64::MAX,
+      max_parts: 100,
+      max_header_size: 8 * 1024
+    })
+  }
+
+  /// Maximum size of the body of a single part.
+  pub fn with_max_part_size(mut self, size: u64) -> Multipart {
+    self.max_part_size = size;
+    self
+  }
+
+  /// Maximum size of the whole body, boundaries and headers included.
+  pub fn with_max_total_size(mut self, size: u64) -> Multipart {
+    self.max_total_size = size;
+    self
+  }
+
+  pub fn with_max_parts(mut self, count: usize) -> Multipart {
+    self.max_parts = count;
+    self
+  }
+
+  /// Maximum size of the header section of a single part.
+  pub fn with_max_header_size(mut self, size: usize) -> Multipart {
+    self.max_header_size = size;
+    self
+  }
+
+  /// The next part, `None` after the last one. Whatever was not read
+  /// of the previous part is skipped.
+  pub fn next_part(&mut self) -> Result<Option<Part<'_>>, Error> {
+    loop {
+      match self.state {
+        State::Done => return Ok(None),
+        State::Preamble | State::Body => {
+          loop {
+            let available = self.available()?;
+            if available == 0 {
+              break
+            }
+            self.buffer.drain(..available);
+          }
+          self.state = State::Delimiter;
+        },
+        State::Delimiter => {
+          if !self.read_delimiter()? {
+            self.state = State::Done;
+            return Ok(None)
+          }
+
+          self.parts += 1;
+          if self.parts > self.max_parts {
+            return Err(Error::TooManyParts(self.max_parts))
+          }
+
+          let headers = self.read_headers()?;
+          let (name, filename) = {
+            let disposition = headers.get("content-disposition").ok_or(Error::MissingName)?;
+            let value = Value::parse(disposition)
+              .map_err(|_| Error::InvalidHeader("Content-Disposition".to_string()))?;
+
+            if !value.value().eq_ignore_ascii_case("form-data") {
+              return Err(Error::MissingName)
+            }
+
+            let name = value.param("name").ok_or(Error::MissingName)?.to_string();
+            (name, value.param("filename").map(String::from))
+          };
+
+          self.state = State::Body;
+          self.part_size = 0;
+          return Ok(Some(Part { multipart: self, headers, name, filename }))
+        }
+      }
+    }
+  }
+
+  // reads more of the body into the buffer, false at the end of the body
+  fn fill(&mut self) -> Result<bool, Error> {
+    let start = self.buffer.len();
+    self.buffer.resize(start + BUFFER_SIZE, 0);
+    let read = loop {
+      match self.reader.read(&mut self.buffer[start..]) {
+        Ok(read) => break read,
+        Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+        Err(err) => {
+          self.buffer.truncate(start);
+          return Err(Error::Io(err))
+        }
+      }
+    };
+    self.buffer.truncate(start + read);
+
+    self.total += read as u64;
+    if self.total > self.max_total_size {
+      return Err(Error::TooLarge(self.max_total_size))
+    }
+    Ok(read > 0)
+  }
+
+  // the number of bytes at the start of the buffer that come before the
+  // next delimiter, 0 once the buffer starts with it
+  fn available(&mut self) -> Result<usize, Error> {
+    loop {
+      if let Some(index) = find(&self.buffer, &self.delimiter) {
+        return Ok(index)
+      }
+
+      // the end of the buffer could be the start of the delimiter
+      let available = self.buffer.len().saturating_sub(self.delimiter.len() - 1);
+      if available > 0 {
+        return Ok(available)
+      }
+
+      if !self.fill()? {
+        return Err(Error::Malformed("Missing closing boundary"))
+      }
+    }
+  }
+
+  fn read_line(&mut self) -> Result<Vec<u8>, Error> {
+    loop {
+      if let Some(index) = find(&self.buffer, b"\r\n") {
+        let line = self.buffer[..index].to_vec();
+        self.buffer.drain(..index + 2);
+        return Ok(line)
+      }
+
+      if self.buffer.len() > self.max_header_size {
+        return Err(Error::HeadersTooLarge(self.max_header_size))
+      }
+
+      if !self.fill()? {
+        return Err(Error::Malformed("Unterminated line"))
+      }
+    }
+  }
+
+  // consumes the delimiter line, false for the closing delimiter
+  fn read_delimiter(&mut self) -> Result<bool, Error> {
+    self.buffer.drain(..self.delimiter.len());
+    while self.buffer.len() < 2 {
+      if !self.fill()? {
+        return Err(Error::Malformed("Unterminated boundary"))
+      }
+    }
+
+    // the epilogue is ignored
+    if self.buffer.starts_with(b"--") {
+      return Ok(false)
+    }
+
+    // only transport padding may follow the boundary
+    let line = self.read_line()?;
+    if !line.iter().all(|b| *b == b' ' || *b == b'\t') {
+      return Err(Error::Malformed("Invalid boundary line"))
+    }
+    Ok(true)
+  }
+
+  fn read_headers(&mut self) -> Result<Headers, Error> {
+    let mut headers = Headers::new();
+    let mut size = 0;
+    loop {
+      let line = self.read_line()?;
+      if line.is_empty() {
+        return Ok(headers)
+      }
+
+      size += line.len() + 2;
+      if size > self.max_header_size {
+        return Err(Error::HeadersTooLarge(self.max_header_size))
+      }
+
+      let line = String::from_utf8_lossy(&line);
+      let (name, value) = line
+        .split_once(':')
+        .ok_or(Error::Malformed("Invalid part header line"))?;
+
+      headers
+        .try_append(name, value.trim_matches([' ', '\t']))
+        .map_err(|_| Error::InvalidHeader(name.to_string()))?;
+    }
+  }
+
+  // reads the body of the current part, 0 once it is over
+  fn read_body(&mut self, out: &mut [u8]) -> Result<usize, Error> {
+    if self.state != State::Body || out.is_empty() {
+      return Ok(0)
+    }
+
+    let available = self.available()?;
+    if available == 0 {
+      self.state = State::Delimiter;
+      return Ok(0)
+    }
+
+    let length = available.min(out.len());
+    self.part_size += length as u64;
+    if self.part_size > self.max_part_size {
+      return Err(Error::PartTooLarge(self.max_part_size))
+    }
+
+    out[..length].copy_from_slice(&self.buffer[..length]);
+    self.buffer.drain(..length);
+    Ok(length)
+  }
+}
+
+/// A part of a multipart body, its body is read with [`Read`].
+pub struct Part<'a> {
+  multipart: &'a mut Multipart,
+  headers: Headers,
+  name: String,
+  filename: Option<String>
+}
+
+impl Part<'_> {
+  pub fn headers(&self) -> &Headers {
+    &self.headers
+  }
+
+  /// The name of the form field.
+  pub fn name(&self) -> &str {
+    &self.name
+  }
+
+  /// The file name chosen by the client, it must not be used
+  /// as a path without being sanitized.
+  pub fn filename(&self) -> Option<&str> {
+    self.filename.as_deref()
+  }
+
+  /// The declared content type, `text/plain` when none is sent.
+  pub fn content_type(&self) -> ContentType {
+    self.headers
+      .typed()
+      .unwrap_or_else(|| ContentType::new("text/plain"))
+  }
+
+  /// Reads the rest of the body into memory.
+  pub fn bytes(&mut self) -> Result<Vec<u8>, Error> {
+    let mut bytes = Vec::new();
+    let mut chunk = [0; BUFFER_SIZE];
+    loop {
+      match self.multipart.read_body(&mut chunk)? {
+        0 => return Ok(bytes),
+        read => bytes.extend_from_slice(&chunk[..read])
+      }
+    }
+  }
+
+  /// Reads the rest of the body into memory as UTF-8 text.
+  pub fn text(&mut self) -> Result<String, Error> {
+    String::from_utf8(self.bytes()?).map_err(|_| Error::InvalidText(self.name.clone()))
+  }
+}
+
+impl Read for Part<'_> {
+  fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+    Ok(self.multipart.read_body(out)?)
+  }
+}
@@ -0,0 +1,155 @@
+use std::io::Cursor;
+
+use super::*;
+
+const BODY: &str = "preamble\r\n\
+  --boundary\r\n\
+  Content-Disposition: form-data; name=\"field\"\r\n\
+  \r\n\
+  value\r\n\
+  --boundary  \r\n\
+  Content-Disposition: form-data; name=\"file\"; filename=\"a b.txt\"\r\n\
+  Content-Type: text/csv; charset=utf-8\r\n\
+  \r\n\
+  a,b\r\n--boundar,c\r\n\
+  \r\n\
+  --boundary\r\n\
+  Content-Disposition: form-data; name=\"empty\"\r\n\
+  \r\n\
+  \r\n\
+  --boundary--\r\n\
+  epilogue";
+
+// hands out the body one byte at a time
+struct Trickle(Cursor<Vec<u8>>);
+
+impl Read for Trickle {
+  fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+    let length = out.len().min(1);
+    self.0.read(&mut out[..length])
+  }
+}
+
+fn multipart(body: &str) -> Multipart {
+  Multipart::new(Cursor::new(body.as_bytes().to_vec()), "boundary").unwrap()
+}
+
+fn parts(mut multipart: Multipart) -> Result<Vec<(String, Option<String>, String)>, Error> {
+  let mut parts = Vec::new();
+  while let Some(mut part) = multipart.next_part()? {
+    let text = part.text()?;
+    parts.push((part.name().to_string(), part.filename().map(String::from), text));
+  }
+  Ok(parts)
+}
+
+mod parse_test {
+  use super::*;
+
+  #[test]
+  fn parts_test() {
+    let expected = vec![
+      ("field".to_string(), None, "value".to_string()),
+      ("file".to_string(), Some("a b.txt".to_string()), "a,b\r\n--boundar,c\r\n".to_string()),
+      ("empty".to_string(), None, String::new())
+    ];
+    assert_eq!(parts(multipart(BODY)).unwrap(), expected);
+
+    let trickle = Trickle(Cursor::new(BODY.as_bytes().to_vec()));
+    assert_eq!(parts(Multipart::new(trickle, "boundary").unwrap()).unwrap(), expected);
+  }
+
+  #[test]
+  fn part_headers_test() {
+    let mut multipart = multipart(BODY);
+    let part = multipart.next_part().unwrap().unwrap();
+    assert_eq!(part.content_type().media_type(), "text/plain");
+    drop(part);
+
+    let part = multipart.next_part().unwrap().unwrap();
+    assert_eq!(part.content_type().media_type(), "text/csv");
+    assert_eq!(part.content_type().charset(), Some("utf-8"));
+    assert!(part.headers().contains("content-disposition"));
+  }
+
+  #[test]
+  fn skip_unread_parts_test() {
+    let mut multipart = multipart(BODY);
+    assert_eq!(multipart.next_part().unwrap().unwrap().name(), "field");
+
+    let mut part = multipart.next_part().unwrap().unwrap();
+    let mut start = [0; 2];
+    part.read_exact(&mut start).unwrap();
+    assert_eq!(&start, b"a,");
+
+    assert_eq!(multipart.next_part().unwrap().unwrap().name(), "empty");
+    assert!(multipart.next_part().unwrap().is_none());
+    assert!(multipart.next_part().unwrap().is_none());
+  }
+
+  #[test]
+  fn no_parts_test() {
+    assert!(parts(multipart("--boundary--")).unwrap().is_empty());
+  }
+
+  #[test]
+  fn boundary_test() {
+    assert!(Multipart::new(io::empty(), "'()+_,-./:=? x").is_ok());
+    assert!(matches!(Multipart::new(io::empty(), ""), Err(Error::InvalidBoundary)));
+    assert!(matches!(Multipart::new(io::empty(), "trailing "), Err(Error::InvalidBoundary)));
+    assert!(matches!(Multipart::new(io::empty(), "a\"b"), Err(Error::InvalidBoundary)));
+    assert!(matches!(Multipart::new(io::empty(), &"a".repeat(71)), Err(Error::InvalidBoundary)));
+  }
+}
+
+mod errors_test {
+  use super::*;
+
+  #[test]
+  fn malformed_test() {
+    let malformed = |body| parts(multipart(body)).unwrap_err();
+    assert!(matches!(malformed(""), Error::Malformed(_)));
+    assert!(matches!(malformed("--boundary\r\nContent-Disposition: form-data; name=a\r\n\r\nvalue"), Error::Malformed(_)));
+    assert!(matches!(malformed("--boundary\r\nContent-Disposition: form-data; name=a"), Error::Malformed(_)));
+    assert!(matches!(malformed("--boundaryx\r\n"), Error::Malformed(_)));
+    assert!(matches!(malformed("--boundary\r\nno colon\r\n\r\n\r\n--boundary--"), Error::Malformed(_)));
+    assert!(matches!(malformed("--boundary\r\nBad Name: x\r\n\r\n\r\n--boundary--"), Error::InvalidHeader(_)));
+  }
+
+  #[test]
+  fn missing_name_test() {
+    let missing = |headers: &str| {
+      let body = format!("--boundary\r\n{headers}\r\n\r\n\r\n--boundary--");
+      matches!(parts(multipart(&body)).unwrap_err(), Error::MissingName)
+    };
+    assert!(missing("Content-Type: text/plain"));
+    assert!(missing("Content-Disposition: form-data"));
+    assert!(missing("Content-Disposition: attachment; name=a"));
+  }
+
+  #[test]
+  fn limits_test() {
+    let err = parts(multipart(BODY).with_max_part_size(8)).unwrap_err();
+    assert!(matches!(err, Error::PartTooLarge(8)));
+    assert!(parts(multipart(BODY).with_max_part_size(18)).is_ok());
+
+    let err = parts(multipart(BODY).with_max_total_size(64)).unwrap_err();
+    assert!(matches!(err, Error::TooLarge(64)));
+    assert!(parts(multipart(BODY).with_max_total_size(BODY.len() as u64)).is_ok());
+
+    let err = parts(multipart(BODY).with_max_parts(2)).unwrap_err();
+    assert!(matches!(err, Error::TooManyParts(2)));
+
+    let err = parts(multipart(BODY).with_max_header_size(32)).unwrap_err();
+    assert!(matches!(err, Error::HeadersTooLarge(32)));
+    assert_eq!(err.status(), StatusCode::ContentTooLarge);
+  }
+
+  #[test]
+  fn invalid_text_test() {
+    let body = b"--boundary\r\nContent-Disposition: form-data; name=a\r\n\r\n\xff\r\n--boundary--".to_vec();
+    let mut multipart = Multipart::new(Cursor::new(body), "boundary").unwrap();
+    let mut part = multipart.next_part().unwrap().unwrap();
+    assert!(matches!(part.text(), Err(Error::InvalidText(_))));
+  }
+}
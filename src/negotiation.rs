@@ -0,0 +1,176 @@
+//! Proactive content negotiation, as described in RFC 9110, section 12.5.
+//!
+//! Each function picks, among the representations the server has
+//! `available`, the one the client prefers according to the matching
+//! `Accept-*` header. Ties are broken by the order of `available`.
+//! When nothing is acceptable, a `406 Not Acceptable` error is returned
+//! that can be propagated from a handler as is.
+//!
+//! ```
+//! use webserver::{header::Headers, negotiation};
+//!
+//! let mut headers = Headers::new();
+//! headers.set("accept", "text/*;q=0.5, application/json".to_string());
+//!
+//! let available = ["text/html", "application/json"];
+//! assert_eq!(negotiation::media_type(&headers, &available).unwrap(), "application/json");
+//! ```
+use crate::{error::HttpError, header::{Headers, Value}, protocol::StatusCode};
+
+#[cfg(test)]
+mod negotiation_test;
+
+// quality values are handled in thousandths to compare them exactly
+type Quality = u16;
+
+const MAX_QUALITY: Quality = 1000;
+
+fn not_acceptable() -> HttpError {
+  HttpError::from_status(StatusCode::NotAcceptable)
+}
+
+// parses a `qvalue`, see RFC 9110, section 12.4.2
+fn parse_quality(value: &str) -> Option<Quality> {
+  let (int, fraction) = value.split_once('.').unwrap_or((value, ""));
+  if fraction.len() > 3 || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+    return None
+  }
+
+  let fraction = format!("{fraction:0<3}").parse::<Quality>().ok()?;
+  match int {
+    "0" => Some(fraction),
+    "1" if fraction == 0 => Some(MAX_QUALITY),
+    _ => None
+  }
+}
+
+fn quality(range: &Value) -> Option<Quality> {
+  range.param("q").map_or(Some(MAX_QUALITY), parse_quality)
+}
+
+// the field lines of `key` combined into one list, see RFC 9110, section 5.3
+fn field(headers: &Headers, key: &str) -> Option<String> {
+  headers.get_all(key).map(|values| values.join(", "))
+}
+
+// weighs every available representation against the ranges of `field`,
+// a range only applies through its most specific match
+fn negotiate<'a>(
+  field: Option<String>,
+  available: &[&'a str],
+  specificity: impl Fn(&Value, &str) -> Option<usize>,
+  unmatched: impl Fn(&str) -> Quality
+) -> Option<&'a str> {
+  // a missing or malformed field accepts anything
+  let Some(ranges) = field.as_deref().and_then(|field| Value::parse_list(field).ok()) else {
+    return available.first().copied()
+  };
+
+  let ranges: Vec<(Value, Quality)> = ranges
+    .into_iter()
+    .filter_map(|range| quality(&range).map(|q| (range, q)))
+    .collect();
+
+  let mut best: Option<(&'a str, Quality)> = None;
+  for candidate in available {
+    let mut matched: Option<(usize, Quality)> = None;
+    for (range, q) in ranges.iter() {
+      if let Some(specificity) = specificity(range, candidate) {
+        if matched.is_none_or(|(s, _)| specificity > s) {
+          matched = Some((specificity, *q));
+        }
+      }
+    }
+
+    let q = matched.map_or_else(|| unmatched(candidate), |(_, q)| q);
+    if q > 0 && best.is_none_or(|(_, b)| q > b) {
+      best = Some((candidate, q));
+    }
+  }
+
+  best.map(|(candidate, _)| candidate)
+}
+
+fn media_type_specificity(range: &Value, candidate: &str) -> Option<usize> {
+  let candidate = Value::parse(candidate).ok()?;
+  let (range_type, range_subtype) = range.value().split_once('/')?;
+  let (candidate_type, candidate_subtype) = candidate.value().split_once('/')?;
+
+  let specificity = match (range_type, range_subtype) {
+    ("*", "*") => 0,
+    (t, "*") if t.eq_ignore_ascii_case(candidate_type) => 1,
+    (t, s) if t.eq_ignore_ascii_case(candidate_type) && s.eq_ignore_ascii_case(candidate_subtype) => 2,
+    _ => return None
+  };
+
+  // parameters after `q` are accept extensions, the ones before
+  // must all be present in the candidate
+  let params: Vec<_> = range.params()
+    .take_while(|(key, _)| !key.eq_ignore_ascii_case("q"))
+    .collect();
+
+  let is_matched = params
+    .iter()
+    .all(|(key, value)| candidate.param(key).is_some_and(|v| v.eq_ignore_ascii_case(value)));
+
+  match (is_matched, params.is_empty()) {
+    (false, _) => None,
+    (true, true) => Some(specificity),
+    (true, false) => Some(specificity + 1)
+  }
+}
+
+fn token_specificity(range: &Value, candidate: &str) -> Option<usize> {
+  match range.value() {
+    "*" => Some(0),
+    value if value.eq_ignore_ascii_case(candidate) => Some(1),
+    _ => None
+  }
+}
+
+// basic filtering, see RFC 4647, section 3.3.1
+fn language_specificity(range: &Value, candidate: &str) -> Option<usize> {
+  let range = range.value();
+  if range == "*" {
+    return Some(0)
+  }
+
+  let is_prefix = candidate.len() > range.len()
+    && candidate.as_bytes()[range.len()] == b'-'
+    && candidate[..range.len()].eq_ignore_ascii_case(range);
+
+  if is_prefix || candidate.eq_ignore_ascii_case(range) {
+    Some(range.len())
+  } else {
+    None
+  }
+}
+
+/// Picks a media type (e.g. `application/json`) using `Accept`.
+pub fn media_type<'a>(headers: &Headers, available: &[&'a str]) -> Result<&'a str, HttpError> {
+  negotiate(field(headers, "accept"), available, media_type_specificity, |_| 0)
+    .ok_or_else(not_acceptable)
+}
+
+/// Picks a content coding (e.g. `gzip`) using `Accept-Encoding`.
+/// `identity` stays acceptable unless it is explicitly excluded.
+pub fn encoding<'a>(headers: &Headers, available: &[&'a str]) -> Result<&'a str, HttpError> {
+  let unmatched = |candidate: &str| {
+    if candidate.eq_ignore_ascii_case("identity") { 1 } else { 0 }
+  };
+
+  negotiate(field(headers, "accept-encoding"), available, token_specificity, unmatched)
+    .ok_or_else(not_acceptable)
+}
+
+/// Picks a charset (e.g. `utf-8`) using `Accept-Charset`.
+pub fn charset<'a>(headers: &Headers, available: &[&'a str]) -> Result<&'a str, HttpError> {
+  negotiate(field(headers, "accept-charset"), available, token_specificity, |_| 0)
+    .ok_or_else(not_acceptable)
+}
+
+/// Picks a language tag (e.g. `en-US`) using `Accept-Language`.
+pub fn language<'a>(headers: &Headers, available: &[&'a str]) -> Result<&'a str, HttpError> {
+  negotiate(field(headers, "accept-language"), available, language_specificity, |_| 0)
+    .ok_or_else(not_acceptable)
+}
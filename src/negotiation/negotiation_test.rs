@@ -0,0 +1,154 @@
+use crate::{header::Headers, protocol::StatusCode};
+use super::*;
+
+fn headers(key: &str, value: &str) -> Headers {
+  let mut headers = Headers::new();
+  headers.set(key, value.to_string());
+  headers
+}
+
+mod quality_test {
+  use super::*;
+
+  #[test]
+  fn parse_quality_test() {
+    assert_eq!(parse_quality("1"), Some(1000));
+    assert_eq!(parse_quality("1.000"), Some(1000));
+    assert_eq!(parse_quality("0"), Some(0));
+    assert_eq!(parse_quality("0.5"), Some(500));
+    assert_eq!(parse_quality("0.123"), Some(123));
+    assert_eq!(parse_quality("0.1234"), None);
+    assert_eq!(parse_quality("1.5"), None);
+    assert_eq!(parse_quality("2"), None);
+    assert_eq!(parse_quality(".5"), None);
+    assert_eq!(parse_quality("0.-1"), None);
+  }
+}
+
+mod media_type_test {
+  use super::*;
+
+  #[test]
+  fn missing_header_test() {
+    let available = ["text/html", "application/json"];
+    assert_eq!(media_type(&Headers::new(), &available).unwrap(), "text/html");
+  }
+
+  #[test]
+  fn quality_test() {
+    let headers = headers("accept", "text/html;q=0.8, application/json");
+    let available = ["text/html", "application/json"];
+    assert_eq!(media_type(&headers, &available).unwrap(), "application/json");
+  }
+
+  #[test]
+  fn specificity_test() {
+    // the most specific range decides the quality of a representation
+    let headers = headers("accept", "text/*;q=0.3, text/html;q=0.7, text/html;level=1, */*;q=0.5");
+    assert_eq!(media_type(&headers, &["text/plain", "image/png"]).unwrap(), "image/png");
+    assert_eq!(media_type(&headers, &["text/plain", "text/html"]).unwrap(), "text/html");
+    assert_eq!(media_type(&headers, &["text/html", "text/html;level=1"]).unwrap(), "text/html;level=1");
+  }
+
+  #[test]
+  fn excluded_test() {
+    let headers = headers("accept", "*/*, application/xml;q=0");
+    assert_eq!(media_type(&headers, &["application/xml", "text/plain"]).unwrap(), "text/plain");
+
+    let err = media_type(&headers, &["application/xml"]).unwrap_err();
+    assert_eq!(err.status(), &StatusCode::NotAcceptable);
+  }
+
+  #[test]
+  fn not_acceptable_test() {
+    let headers = headers("accept", "application/json");
+    let err = media_type(&headers, &["text/html", "image/png"]).unwrap_err();
+    assert_eq!(err.status(), &StatusCode::NotAcceptable);
+  }
+
+  #[test]
+  fn server_order_breaks_ties_test() {
+    let headers = headers("accept", "text/html, application/json");
+    assert_eq!(media_type(&headers, &["application/json", "text/html"]).unwrap(), "application/json");
+  }
+
+  #[test]
+  fn field_lines_test() {
+    // every line of the field counts, not just the first
+    let mut headers = super::headers("accept", "text/html;q=0.5");
+    headers.append("Accept".to_string(), "application/json".to_string());
+    assert_eq!(media_type(&headers, &["text/html", "application/json"]).unwrap(), "application/json");
+
+    let mut headers = super::headers("accept-encoding", "gzip");
+    headers.append("Accept-Encoding".to_string(), "identity;q=0".to_string());
+    assert!(encoding(&headers, &["identity"]).is_err());
+  }
+}
+
+mod encoding_test {
+  use super::*;
+
+  #[test]
+  fn quality_test() {
+    let headers = headers("accept-encoding", "gzip;q=0.5, br");
+    assert_eq!(encoding(&headers, &["gzip", "br", "identity"]).unwrap(), "br");
+  }
+
+  #[test]
+  fn identity_is_acceptable_by_default_test() {
+    let headers = headers("accept-encoding", "br");
+    assert_eq!(encoding(&headers, &["gzip", "identity"]).unwrap(), "identity");
+
+    let headers = self::headers("accept-encoding", "");
+    assert_eq!(encoding(&headers, &["gzip", "identity"]).unwrap(), "identity");
+  }
+
+  #[test]
+  fn identity_excluded_test() {
+    let headers = headers("accept-encoding", "gzip, identity;q=0");
+    assert!(encoding(&headers, &["identity"]).is_err());
+
+    let headers = self::headers("accept-encoding", "*;q=0");
+    assert!(encoding(&headers, &["identity", "gzip"]).is_err());
+
+    let headers = self::headers("accept-encoding", "*;q=0, identity");
+    assert_eq!(encoding(&headers, &["gzip", "identity"]).unwrap(), "identity");
+  }
+
+  #[test]
+  fn wildcard_test() {
+    let headers = headers("accept-encoding", "gzip;q=0.2, *;q=0.5");
+    assert_eq!(encoding(&headers, &["gzip", "br"]).unwrap(), "br");
+  }
+}
+
+mod charset_test {
+  use super::*;
+
+  #[test]
+  fn charset_test() {
+    let headers = headers("accept-charset", "iso-8859-5, UTF-8;q=0.8");
+    assert_eq!(charset(&headers, &["utf-8", "iso-8859-5"]).unwrap(), "iso-8859-5");
+    assert_eq!(charset(&headers, &["utf-8", "us-ascii"]).unwrap(), "utf-8");
+    assert!(charset(&headers, &["us-ascii"]).is_err());
+  }
+}
+
+mod language_test {
+  use super::*;
+
+  #[test]
+  fn prefix_match_test() {
+    let headers = headers("accept-language", "fr-CH, fr;q=0.9, en;q=0.8, de;q=0.7, *;q=0.5");
+    assert_eq!(language(&headers, &["en-US", "fr-FR"]).unwrap(), "fr-FR");
+    assert_eq!(language(&headers, &["de", "en-GB"]).unwrap(), "en-GB");
+    assert_eq!(language(&headers, &["ja"]).unwrap(), "ja");
+  }
+
+  #[test]
+  fn range_more_specific_than_tag_test() {
+    let headers = headers("accept-language", "en-US");
+    assert!(language(&headers, &["en"]).is_err());
+    assert!(language(&headers, &["english"]).is_err());
+  }
+}
@@ -0,0 +1,403 @@
+//! Incremental parser for HTTP/1.1 message heads.
+//!
+//! The parser is fed the whole buffer read so far each time new bytes
+//! arrive and resumes where the previous call stopped. Parsed elements
+//! are stored as byte ranges into that buffer, which the server then
+//! copies into the fields of the [`Request`](crate::request::Request).
+//!
+//! ```
+//! use webserver::{parser::{Parser, Status}, ServerConfig};
+//!
+//! let mut parser = Parser::request(&ServerConfig::default());
+//! let mut buf = Vec::new();
+//!
+//! buf.extend_from_slice(b"GET / HTTP/1.1\r\nHo");
+//! assert_eq!(parser.parse(&buf).unwrap(), Status::Partial);
+//!
+//! buf.extend_from_slice(b"st: localhost\r\n\r\nbody");
+//! let Status::Complete(length) = parser.parse(&buf).unwrap() else { panic!() };
+//!
+//! // buf[length..] is the start of the body
+//! assert_eq!(&buf[length..], b"body");
+//! assert_eq!(parser.headers()[0].value(&buf), b"localhost");
+//! ```
+use std::ops::Range;
+
+use crate::{config::ServerConfig, protocol};
+pub use crate::stream::error::Error;
+
+#[cfg(test)]
+mod parser_test;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Kind {
+  Request,
+  Response
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StartLine {
+  Request {
+    method: Range<usize>,
+    target: Range<usize>,
+    version: Range<usize>
+  },
+  Response {
+    version: Range<usize>,
+    status: u16,
+    reason: Range<usize>
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeaderRange {
+  pub name: Range<usize>,
+  pub value: Range<usize>,
+  /// The value spans multiple lines (obsolete line folding, lenient mode only),
+  /// its raw line breaks should be read as a single space.
+  pub folded: bool
+}
+
+impl HeaderRange {
+  pub fn name<'b>(&self, buf: &'b [u8]) -> &'b [u8] {
+    &buf[self.name.clone()]
+  }
+
+  pub fn value<'b>(&self, buf: &'b [u8]) -> &'b [u8] {
+    &buf[self.value.clone()]
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Status {
+  /// More bytes are needed.
+  Partial,
+  /// The head is complete, holds its length in bytes.
+  Complete(usize)
+}
+
+#[derive(Debug, Clone)]
+struct Limits {
+  max_start_line: usize,
+  max_uri_length: usize,
+  max_header_bytes: usize,
+  max_header_count: usize,
+  max_header_size: usize,
+  lenient: bool
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum State {
+  Start,
+  StartLine,
+  Headers,
+  Done
+}
+
+#[derive(Debug, Clone)]
+pub struct Parser {
+  kind: Kind,
+  limits: Limits,
+  state: State,
+  // start of the line currently being parsed
+  line_start: usize,
+  // how far the current line has been scanned for a line feed
+  scanned: usize,
+  header_bytes: usize,
+  start_line: Option<StartLine>,
+  headers: Vec<HeaderRange>
+}
+
+fn is_whitespace(byte: &u8) -> bool {
+  *byte == b' ' || *byte == b'\t'
+}
+
+fn trim_range(buf: &[u8], range: Range<usize>) -> Range<usize> {
+  let slice = &buf[range.clone()];
+  let Some(start) = slice.iter().position(|b| !is_whitespace(b)) else {
+    return range.start..range.start
+  };
+  let end = slice.iter().rposition(|b| !is_whitespace(b)).unwrap();
+  range.start + start..range.start + end + 1
+}
+
+fn is_version(version: &[u8], lenient: bool) -> bool {
+  let [name @ .., major, b'.', minor] = version else {
+    return false
+  };
+
+  let is_name = if lenient {
+    name.eq_ignore_ascii_case(b"HTTP/")
+  } else {
+    name == b"HTTP/"
+  };
+  is_name && major.is_ascii_digit() && minor.is_ascii_digit()
+}
+
+impl Parser {
+  pub fn new(kind: Kind, config: &ServerConfig) -> Parser {
+    Parser {
+      kind,
+      limits: Limits {
+        max_start_line: config.max_request_line,
+        max_uri_length: config.max_uri_length,
+        max_header_bytes: config.max_header_bytes,
+        max_header_count: config.max_header_count,
+        max_header_size: config.max_header_size,
+        lenient: config.lenient_parsing
+      },
+      state: State::Start,
+      line_start: 0,
+      scanned: 0,
+      header_bytes: 0,
+      start_line: None,
+      headers: Vec::new()
+    }
+  }
+
+  pub fn request(config: &ServerConfig) -> Parser {
+    Parser::new(Kind::Request, config)
+  }
+
+  pub fn response(config: &ServerConfig) -> Parser {
+    Parser::new(Kind::Response, config)
+  }
+
+  pub fn kind(&self) -> Kind {
+    self.kind
+  }
+
+  pub fn is_complete(&self) -> bool {
+    self.state == State::Done
+  }
+
+  pub fn start_line(&self) -> Option<&StartLine> {
+    self.start_line.as_ref()
+  }
+
+  pub fn headers(&self) -> &[HeaderRange] {
+    &self.headers
+  }
+
+  /// Prepares the parser for the next message. Ranges of the next message
+  /// are relative to the buffer passed afterwards, so bytes consumed by
+  /// the previous message should be drained from it first.
+  pub fn reset(&mut self) {
+    self.state = State::Start;
+    self.line_start = 0;
+    self.scanned = 0;
+    self.header_bytes = 0;
+    self.start_line = None;
+    self.headers.clear();
+  }
+
+  /// Continues parsing `buf`, which must start with the bytes given to
+  /// the previous calls.
+  pub fn parse(&mut self, buf: &[u8]) -> Result<Status, Error> {
+    loop {
+      if self.state == State::Done {
+        return Ok(Status::Complete(self.line_start))
+      }
+
+      let from = self.scanned.max(self.line_start);
+      let Some(offset) = buf[from..].iter().position(|b| *b == b'\n') else {
+        self.scanned = buf.len();
+        self.check_line_size(buf.len() - self.line_start)?;
+        return Ok(Status::Partial)
+      };
+
+      let end = from + offset + 1;
+      self.check_line_size(end - self.line_start)?;
+
+      let line = self.trim_line(buf, self.line_start..end)?;
+      match self.state {
+        State::Start if line.is_empty() => {
+          // a server should ignore at least one empty line before the start line
+          self.state = State::StartLine;
+        },
+        State::Start | State::StartLine => {
+          self.start_line = Some(match self.kind {
+            Kind::Request => self.parse_request_line(buf, line)?,
+            Kind::Response => self.parse_status_line(buf, line)?
+          });
+          self.state = State::Headers;
+        },
+        State::Headers => {
+          self.header_bytes += end - self.line_start;
+          if line.is_empty() {
+            self.state = State::Done;
+          } else {
+            self.parse_header(buf, line)?;
+          }
+        },
+        State::Done => unreachable!()
+      }
+
+      self.line_start = end;
+      self.scanned = end;
+    }
+  }
+
+  /// Signals that no more bytes will come, returns the head length
+  /// if the message can still be considered complete.
+  pub fn eof(&mut self, buf: &[u8]) -> Result<usize, Error> {
+    match self.state {
+      State::Done => Ok(self.line_start),
+      State::Start | State::StartLine if buf[self.line_start..].is_empty() => {
+        Err(Error::EmptyRequest)
+      },
+      // header section must be terminated by an empty line
+      State::Headers if self.limits.lenient && self.line_start == buf.len() => {
+        self.state = State::Done;
+        Ok(self.line_start)
+      },
+      _ => Err(Error::IncompleteRequest)
+    }
+  }
+
+  fn check_line_size(&self, size: usize) -> Result<(), Error> {
+    match self.state {
+      State::Start | State::StartLine if size > self.limits.max_start_line => {
+        Err(Error::RequestLineTooLong(self.limits.max_start_line))
+      },
+      State::Headers if size > self.limits.max_header_size => {
+        Err(Error::HeaderTooLong(self.limits.max_header_size))
+      },
+      State::Headers if self.header_bytes + size > self.limits.max_header_bytes => {
+        Err(Error::HeadersTooLarge(self.limits.max_header_bytes))
+      },
+      _ => Ok(())
+    }
+  }
+
+  // strips the line ending, only CRLF is allowed in strict mode
+  fn trim_line(&self, buf: &[u8], line: Range<usize>) -> Result<Range<usize>, Error> {
+    let mut end = line.end - 1;
+    if end > line.start && buf[end - 1] == b'\r' {
+      end -= 1;
+    } else if !self.limits.lenient {
+      return Err(Error::BareLineFeed)
+    }
+
+    if buf[line.start..end].contains(&b'\r') {
+      return Err(Error::BareCarriageReturn)
+    }
+
+    Ok(line.start..end)
+  }
+
+  fn parse_request_line(&self, buf: &[u8], line: Range<usize>) -> Result<StartLine, Error> {
+    let slice = &buf[line.clone()];
+    let parts: Vec<usize> = slice
+      .iter()
+      .enumerate()
+      .filter(|(_, b)| **b == b' ')
+      .map(|(i, _)| line.start + i)
+      .collect();
+
+    let [first, second] = parts[..] else {
+      return Err(Error::InvalidLocationFormat)
+    };
+
+    let method = line.start..first;
+    let target = first + 1..second;
+    let version = second + 1..line.end;
+
+    if !protocol::is_token(&buf[method.clone()]) {
+      let method = String::from_utf8_lossy(&buf[method]).to_string();
+      return Err(Error::InvalidMethod(method))
+    }
+
+    if target.is_empty() || !buf[target.clone()].iter().all(|b| b.is_ascii_graphic()) {
+      return Err(Error::InvalidLocationFormat)
+    }
+
+    if target.len() > self.limits.max_uri_length {
+      return Err(Error::URITooLong(self.limits.max_uri_length))
+    }
+
+    if !is_version(&buf[version.clone()], self.limits.lenient) {
+      let version = String::from_utf8_lossy(&buf[version]).to_string();
+      return Err(Error::UnsupportedProtocol(version))
+    }
+
+    Ok(StartLine::Request { method, target, version })
+  }
+
+  fn parse_status_line(&self, buf: &[u8], line: Range<usize>) -> Result<StartLine, Error> {
+    let slice = &buf[line.clone()];
+    let Some(space) = slice.iter().position(|b| *b == b' ') else {
+      return Err(Error::InvalidLocationFormat)
+    };
+
+    let version = line.start..line.start + space;
+    if !is_version(&buf[version.clone()], self.limits.lenient) {
+      let version = String::from_utf8_lossy(&buf[version]).to_string();
+      return Err(Error::UnsupportedProtocol(version))
+    }
+
+    let rest = &slice[space + 1..];
+    let (status, reason) = match rest {
+      [a, b, c] if self.limits.lenient => ([*a, *b, *c], line.end..line.end),
+      [a, b, c, b' ', ..] => ([*a, *b, *c], version.end + 5..line.end),
+      _ => return Err(Error::InvalidLocationFormat)
+    };
+
+    if !status.iter().all(|b| b.is_ascii_digit()) || !protocol::is_field_value(&buf[reason.clone()]) {
+      return Err(Error::InvalidLocationFormat)
+    }
+
+    let status = status.iter().fold(0, |acc, b| acc * 10 + (b - b'0') as u16);
+    Ok(StartLine::Response { version, status, reason })
+  }
+
+  fn parse_header(&mut self, buf: &[u8], line: Range<usize>) -> Result<(), Error> {
+    let slice = &buf[line.clone()];
+
+    if is_whitespace(&slice[0]) {
+      // obsolete line folding, see RFC 9112, section 5.2
+      let Some(header) = self.headers.last_mut().filter(|_| self.limits.lenient) else {
+        return Err(Error::ObsoleteLineFolding)
+      };
+
+      if !protocol::is_field_value(slice) {
+        let name = String::from_utf8_lossy(header.name(buf)).to_string();
+        return Err(Error::InvalidHeaderValue(name))
+      }
+
+      let folded = trim_range(buf, line);
+      if !folded.is_empty() {
+        header.value.end = folded.end;
+        header.folded = true;
+      }
+      return Ok(())
+    }
+
+    let Some(colon) = slice.iter().position(|b| *b == b':') else {
+      return Err(Error::InvalidHeaderEntryFormat)
+    };
+
+    let name = line.start..line.start + colon;
+    if buf[name.clone()].last().is_some_and(is_whitespace) {
+      return Err(Error::WhitespaceBeforeColon)
+    }
+
+    if !protocol::is_token(&buf[name.clone()]) {
+      let name = String::from_utf8_lossy(&buf[name]).to_string();
+      return Err(Error::InvalidHeaderName(name))
+    }
+
+    let value = trim_range(buf, name.end + 1..line.end);
+    if !protocol::is_field_value(&buf[value.clone()]) {
+      let name = String::from_utf8_lossy(&buf[name]).to_string();
+      return Err(Error::InvalidHeaderValue(name))
+    }
+
+    if self.headers.len() >= self.limits.max_header_count {
+      return Err(Error::TooManyHeaders(self.limits.max_header_count))
+    }
+
+    self.headers.push(HeaderRange { name, value, folded: false });
+    Ok(())
+  }
+}
@@ -0,0 +1,140 @@
+use crate::config::ServerConfig;
+use super::*;
+
+const REQUEST: &[u8] = b"POST /a/b?c=d HTTP/1.1\r\nHost: localhost\r\nContent-Length: 5\r\nX-Empty:\r\n\r\nhello";
+const RESPONSE: &[u8] = b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n";
+
+fn headers<'b>(parser: &Parser, buf: &'b [u8]) -> Vec<(&'b [u8], &'b [u8])> {
+  parser
+    .headers()
+    .iter()
+    .map(|h| (h.name(buf), h.value(buf)))
+    .collect()
+}
+
+mod request_test {
+  use super::*;
+
+  #[test]
+  fn complete_request_test() {
+    let mut parser = Parser::request(&ServerConfig::default());
+    let length = REQUEST.len() - "hello".len();
+
+    assert_eq!(parser.parse(REQUEST).unwrap(), Status::Complete(length));
+    assert!(parser.is_complete());
+
+    let Some(StartLine::Request { method, target, version }) = parser.start_line() else {
+      panic!("Start line should be a request line");
+    };
+    assert_eq!(&REQUEST[method.clone()], b"POST");
+    assert_eq!(&REQUEST[target.clone()], b"/a/b?c=d");
+    assert_eq!(&REQUEST[version.clone()], b"HTTP/1.1");
+
+    assert_eq!(headers(&parser, REQUEST), vec![
+      (&b"Host"[..], &b"localhost"[..]),
+      (&b"Content-Length"[..], &b"5"[..]),
+      (&b"X-Empty"[..], &b""[..]),
+    ]);
+  }
+
+  #[test]
+  fn byte_by_byte_test() {
+    let mut parser = Parser::request(&ServerConfig::default());
+    let length = REQUEST.len() - "hello".len();
+
+    for i in 1..length {
+      assert_eq!(parser.parse(&REQUEST[..i]).unwrap(), Status::Partial);
+    }
+    assert_eq!(parser.parse(&REQUEST[..length]).unwrap(), Status::Complete(length));
+
+    let mut whole = Parser::request(&ServerConfig::default());
+    whole.parse(REQUEST).unwrap();
+    assert_eq!(parser.start_line(), whole.start_line());
+    assert_eq!(parser.headers(), whole.headers());
+  }
+
+  #[test]
+  fn reset_test() {
+    let mut parser = Parser::request(&ServerConfig::default());
+    parser.parse(REQUEST).unwrap();
+    parser.reset();
+
+    let buf = b"GET / HTTP/1.1\r\nHost: a\r\n\r\n";
+    assert_eq!(parser.parse(buf).unwrap(), Status::Complete(buf.len()));
+    assert_eq!(headers(&parser, buf), vec![(&b"Host"[..], &b"a"[..])]);
+  }
+
+  #[test]
+  fn partial_line_limit_test() {
+    let config = ServerConfig { max_header_size: 16, ..Default::default() };
+    let mut parser = Parser::request(&config);
+
+    // the limit is enforced before the line feed arrives
+    let buf = b"GET / HTTP/1.1\r\nCookie: a-rather-long-cookie";
+    assert!(matches!(parser.parse(buf), Err(Error::HeaderTooLong(16))));
+  }
+
+  #[test]
+  fn eof_test() {
+    let mut parser = Parser::request(&ServerConfig::default());
+    assert!(matches!(parser.eof(b""), Err(Error::EmptyRequest)));
+
+    let buf = b"GET / HTTP/1.1\r\nHost: a\r\n";
+    assert_eq!(parser.parse(buf).unwrap(), Status::Partial);
+    assert!(matches!(parser.eof(buf), Err(Error::IncompleteRequest)));
+
+    let mut parser = Parser::request(&ServerConfig { lenient_parsing: true, ..Default::default() });
+    assert_eq!(parser.parse(buf).unwrap(), Status::Partial);
+    assert_eq!(parser.eof(buf).unwrap(), buf.len());
+  }
+
+  #[test]
+  fn folded_header_test() {
+    let mut parser = Parser::request(&ServerConfig { lenient_parsing: true, ..Default::default() });
+    let buf = b"GET / HTTP/1.1\r\nX-Folded: a,\r\n  b \r\n\r\n";
+    parser.parse(buf).unwrap();
+
+    let header = &parser.headers()[0];
+    assert!(header.folded);
+    assert_eq!(header.value(buf), b"a,\r\n  b");
+  }
+}
+
+mod response_test {
+  use super::*;
+
+  #[test]
+  fn complete_response_test() {
+    let mut parser = Parser::response(&ServerConfig::default());
+    assert_eq!(parser.parse(RESPONSE).unwrap(), Status::Complete(RESPONSE.len()));
+
+    let Some(StartLine::Response { version, status, reason }) = parser.start_line() else {
+      panic!("Start line should be a status line");
+    };
+    assert_eq!(&RESPONSE[version.clone()], b"HTTP/1.1");
+    assert_eq!(*status, 404);
+    assert_eq!(&RESPONSE[reason.clone()], b"Not Found");
+    assert_eq!(headers(&parser, RESPONSE), vec![(&b"Content-Length"[..], &b"0"[..])]);
+  }
+
+  #[test]
+  fn empty_reason_test() {
+    let mut parser = Parser::response(&ServerConfig::default());
+    let buf = b"HTTP/1.1 204 \r\n\r\n";
+    parser.parse(buf).unwrap();
+
+    let Some(StartLine::Response { status, reason, .. }) = parser.start_line() else {
+      panic!("Start line should be a status line");
+    };
+    assert_eq!(*status, 204);
+    assert!(reason.is_empty());
+  }
+
+  #[test]
+  fn invalid_status_line_test() {
+    for buf in [&b"HTTP/1.1 20 OK\r\n\r\n"[..], b"HTTP/1.1 2000 OK\r\n\r\n", b"HTTP/1.1 abc OK\r\n\r\n", b"HTTP/1.1 200\r\n\r\n"] {
+      let mut parser = Parser::response(&ServerConfig::default());
+      assert!(parser.parse(buf).is_err(), "Status line should be rejected: {buf:?}");
+    }
+  }
+}
@@ -1,56 +1,123 @@
+use std::fmt;
+
 pub const HTTP_PROTOCOL: &str = "HTTP/1.1";
 
-#[repr(u16)]
-#[derive(Debug, Clone, PartialEq)]
-pub enum StatusCode {
-  Continue = 100,
-  SwitchingProtocols = 101,
-  Processing = 102,
-  EarlyHints = 103,
-
-  OK = 200,
-  Created = 201,
-  Accepted = 202,
-  NonAuthoritativeInformation = 203,
-  NoContent = 204,
-  PartialContent = 206,
-  MultiStatus = 207,
-  AlreadyReported = 208,
-  IMUsed = 226,
-
-  MultipleChoices = 300,
-  MovedPermanently = 301,
-  Found = 302,
-  SeeOther = 303,
-  NotModified = 304,
-  UseProxy = 305,
-  TemporaryRedirect = 307,
-  PermanentRedirect = 308,
-
-  BadRequest = 400,
-  Unauthorized = 401,
-  PaymentRequired = 402,
-  Forbidden = 403,
-  NotFound = 404,
-  MethodNotAllowed = 405,
-  NotAcceptable = 406,
-  ProxyAuthenticationRequired = 407,
-  RequestTimeout = 408,
-  Conflict = 409,
-
-  InternalServerError = 500,
-  NotImplemented = 501,
-  BadGateway = 502,
-  ServiceUnavailable = 503,
-  GatewayTimeout = 504,
-  HTTPVersionNotSupported = 505,
-  VariantAlsoNegotiates = 506,
-  InsufficientStorage = 507,
-  LoopDetected = 508,
-  NotExtended = 510,
-  NetworkAuthenticationRequired = 511,
-
-  Other(u16, String)
+#[cfg(test)]
+mod protocol_test;
+
+// the registered status codes, with their variant and reason phrase
+macro_rules! status_codes {
+  ($($variant:ident = $code:literal => $phrase:literal),* $(,)?) => {
+    /// A response status code, see RFC 9110, section 15. The variants are
+    /// the codes of the IANA registry, any other code is [`StatusCode::Other`].
+    #[derive(Debug, Clone, PartialEq)]
+    pub enum StatusCode {
+      $($variant,)*
+      /// An unregistered code with its reason phrase, the phrase being
+      /// the registered one when empty.
+      ///
+      /// Built directly, a registered code does not equal its variant,
+      /// `Other(404, ..) != NotFound`, and [`StatusCode::try_from`] should
+      /// be preferred. A code out of `100..=599`, or a reason phrase that
+      /// fails [`StatusCode::validate`], is never sent: the response is
+      /// answered with a 500 instead.
+      Other(u16, String)
+    }
+
+    impl StatusCode {
+      pub fn to_u16(&self) -> u16 {
+        match self {
+          $(StatusCode::$variant => $code,)*
+          StatusCode::Other(code, _) => *code
+        }
+      }
+
+      // the variant of a registered code
+      fn registered(code: u16) -> Option<StatusCode> {
+        match code {
+          $($code => Some(StatusCode::$variant),)*
+          _ => None
+        }
+      }
+
+      fn registered_phrase(code: u16) -> Option<&'static str> {
+        match code {
+          $($code => Some($phrase),)*
+          _ => None
+        }
+      }
+    }
+  };
+}
+
+status_codes! {
+  Continue = 100 => "Continue",
+  SwitchingProtocols = 101 => "Switching Protocols",
+  Processing = 102 => "Processing",
+  EarlyHints = 103 => "Early Hints",
+
+  OK = 200 => "OK",
+  Created = 201 => "Created",
+  Accepted = 202 => "Accepted",
+  NonAuthoritativeInformation = 203 => "Non-Authoritative Information",
+  NoContent = 204 => "No Content",
+  ResetContent = 205 => "Reset Content",
+  PartialContent = 206 => "Partial Content",
+  MultiStatus = 207 => "Multi-Status",
+  AlreadyReported = 208 => "Already Reported",
+  IMUsed = 226 => "IM Used",
+
+  MultipleChoices = 300 => "Multiple Choices",
+  MovedPermanently = 301 => "Moved Permanently",
+  Found = 302 => "Found",
+  SeeOther = 303 => "See Other",
+  NotModified = 304 => "Not Modified",
+  UseProxy = 305 => "Use Proxy",
+  TemporaryRedirect = 307 => "Temporary Redirect",
+  PermanentRedirect = 308 => "Permanent Redirect",
+
+  BadRequest = 400 => "Bad Request",
+  Unauthorized = 401 => "Unauthorized",
+  PaymentRequired = 402 => "Payment Required",
+  Forbidden = 403 => "Forbidden",
+  NotFound = 404 => "Not Found",
+  MethodNotAllowed = 405 => "Method Not Allowed",
+  NotAcceptable = 406 => "Not Acceptable",
+  ProxyAuthenticationRequired = 407 => "Proxy Authentication Required",
+  RequestTimeout = 408 => "Request Timeout",
+  Conflict = 409 => "Conflict",
+  Gone = 410 => "Gone",
+  LengthRequired = 411 => "Length Required",
+  PreconditionFailed = 412 => "Precondition Failed",
+  ContentTooLarge = 413 => "Content Too Large",
+  URITooLong = 414 => "URI Too Long",
+  UnsupportedMediaType = 415 => "Unsupported Media Type",
+  RangeNotSatisfiable = 416 => "Range Not Satisfiable",
+  ExpectationFailed = 417 => "Expectation Failed",
+  // reserved by RFC 9110, section 15.5.19, but still sent
+  ImATeapot = 418 => "I'm a teapot",
+  MisdirectedRequest = 421 => "Misdirected Request",
+  UnprocessableContent = 422 => "Unprocessable Content",
+  Locked = 423 => "Locked",
+  FailedDependency = 424 => "Failed Dependency",
+  TooEarly = 425 => "Too Early",
+  UpgradeRequired = 426 => "Upgrade Required",
+  PreconditionRequired = 428 => "Precondition Required",
+  TooManyRequests = 429 => "Too Many Requests",
+  RequestHeaderFieldsTooLarge = 431 => "Request Header Fields Too Large",
+  UnavailableForLegalReasons = 451 => "Unavailable For Legal Reasons",
+
+  InternalServerError = 500 => "Internal Server Error",
+  NotImplemented = 501 => "Not Implemented",
+  BadGateway = 502 => "Bad Gateway",
+  ServiceUnavailable = 503 => "Service Unavailable",
+  GatewayTimeout = 504 => "Gateway Timeout",
+  HTTPVersionNotSupported = 505 => "HTTP Version Not Supported",
+  VariantAlsoNegotiates = 506 => "Variant Also Negotiates",
+  InsufficientStorage = 507 => "Insufficient Storage",
+  LoopDetected = 508 => "Loop Detected",
+  NotExtended = 510 => "Not Extended",
+  NetworkAuthenticationRequired = 511 => "Network Authentication Required"
 }
 
 impl StatusCode {
@@ -61,67 +128,124 @@ impl StatusCode {
       }
     }
 
-    match self.to_u16() {
-      100 => "Continue",
-      101 => "Switching Protocols",
-      102 => "Processing",
-      103 => "Early Hints",
-    
-      200 => "OK",
-      201 => "Created",
-      202 => "Accepted",
-      203 => "Non Authoritative Information",
-      204 => "No Content",
-      206 => "Partial Content",
-      207 => "Multi Status",
-      208 => "Already Reported",
-      226 => "IM Used",
-    
-      300 => "Multiple Choices",
-      301 => "Moved Permanently",
-      302 => "Found",
-      303 => "See Other",
-      304 => "Not Modified",
-      305 => "Use Proxy",
-      307 => "Temporary Redirect",
-      308 => "Permanent Redirect",
-    
-      400 => "Bad Request",
-      401 => "Unauthorized",
-      402 => "Payment Required",
-      403 => "Forbidden",
-      404 => "Not Found",
-      405 => "Method Not Allowed",
-      406 => "Not Acceptable",
-      407 => "Proxy Authentication Required",
-      408 => "Request Timeout",
-      409 => "Conflict",
-    
-      500 => "Internal Server Error",
-      501 => "Not Implemented",
-      502 => "Bad Gateway",
-      503 => "Service Unavailable",
-      504 => "Gateway Timeout",
-      505 => "HTTP VersionNot Supported",
-      506 => "Variant Also Negotiates",
-      507 => "Insufficient Storage",
-      508 => "Loop Detected",
-      510 => "Not Extended",
-      511 => "Network Authentication Required",
-      
-      _ => "Other"
-    }
+    StatusCode::registered_phrase(self.to_u16()).unwrap_or("Other")
   }
 
-  pub fn to_u16(&self) -> u16 {
-    match self {
-      StatusCode::Other(code, _) => code.clone(),
-      code => unsafe { *(code as *const Self as *const u16) }
+  /// Checks that the status line would parse back: the code is in
+  /// `100..=599` and the reason phrase only has the characters of RFC 9112,
+  /// section 4. Only [`StatusCode::Other`] can fail.
+  pub fn validate(&self) -> Result<(), Box<dyn std::error::Error>> {
+    if let StatusCode::Other(code, reason) = self {
+      if !(100..=599).contains(code) {
+        return Err(Box::new(InvalidStatusCode(*code)))
+      }
+
+      if !is_field_value(reason.as_bytes()) {
+        return Err(Box::new(InvalidReasonPhrase(reason.clone())))
+      }
     }
+
+    Ok(())
+  }
+
+  /// 1xx, the request was received and is being processed.
+  pub fn is_informational(&self) -> bool {
+    (100..200).contains(&self.to_u16())
+  }
+
+  /// 2xx, the request was successfully received, understood and accepted.
+  pub fn is_success(&self) -> bool {
+    (200..300).contains(&self.to_u16())
+  }
+
+  /// 3xx, further action is needed to complete the request.
+  pub fn is_redirection(&self) -> bool {
+    (300..400).contains(&self.to_u16())
+  }
+
+  /// 4xx, the request is at fault.
+  pub fn is_client_error(&self) -> bool {
+    (400..500).contains(&self.to_u16())
   }
 
-  pub fn to_string(&self) -> String {
-    let code = self.to_u16();
-    code.to_string()
+  /// 5xx, the server failed to fulfill a valid request.
+  pub fn is_server_error(&self) -> bool {
+    (500..600).contains(&self.to_u16())
   }
-}
\ No newline at end of file
+}
+
+/// Writes the three digits of the code.
+impl fmt::Display for StatusCode {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.to_u16())
+  }
+}
+
+impl From<StatusCode> for u16 {
+  fn from(status: StatusCode) -> u16 {
+    status.to_u16()
+  }
+}
+
+/// A code out of the `100..=599` range of RFC 9110, section 15.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InvalidStatusCode(pub u16);
+
+impl fmt::Display for InvalidStatusCode {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "Invalid status code: {}", self.0)
+  }
+}
+
+impl std::error::Error for InvalidStatusCode {}
+
+/// A reason phrase with a character out of `HTAB / SP / VCHAR / obs-text`,
+/// such as a CR or LF that would end the status line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InvalidReasonPhrase(pub String);
+
+impl fmt::Display for InvalidReasonPhrase {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "Invalid reason phrase: {:?}", self.0)
+  }
+}
+
+impl std::error::Error for InvalidReasonPhrase {}
+
+/// Registered codes convert to their variant, others to [`StatusCode::Other`]
+/// without a reason phrase.
+///
+/// ```
+/// use webserver::protocol::StatusCode;
+///
+/// assert_eq!(StatusCode::try_from(404), Ok(StatusCode::NotFound));
+/// assert_eq!(StatusCode::try_from(599).map(|status| status.reason_phrase().to_string()), Ok("Other".to_string()));
+/// assert!(StatusCode::try_from(1000).is_err());
+/// ```
+impl TryFrom<u16> for StatusCode {
+  type Error = InvalidStatusCode;
+
+  fn try_from(code: u16) -> Result<StatusCode, InvalidStatusCode> {
+    match code {
+      100..=599 => Ok(StatusCode::registered(code).unwrap_or(StatusCode::Other(code, String::new()))),
+      _ => Err(InvalidStatusCode(code))
+    }
+  }
+}
+
+/// Whether `byte` is a `tchar` as defined by RFC 9110, section 5.6.2.
+pub fn is_tchar(byte: u8) -> bool {
+  byte.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&byte)
+}
+
+/// Whether `value` is a non-empty `token` as defined by RFC 9110, section 5.6.2.
+pub fn is_token(value: &[u8]) -> bool {
+  !value.is_empty() && value.iter().all(|b| is_tchar(*b))
+}
+
+/// Whether `value` only contains `field-content` characters
+/// (visible characters, `obs-text`, spaces and horizontal tabs),
+/// as defined by RFC 9110, section 5.5.
+pub fn is_field_value(value: &[u8]) -> bool {
+  value.iter().all(|b| *b == b'\t' || *b == b' ' || (0x21..=0x7E).contains(b) || *b >= 0x80)
+}
@@ -0,0 +1,78 @@
+use super::*;
+
+mod status_code_test {
+  use super::*;
+
+  #[test]
+  fn round_trip_test() {
+    // every registered code converts back to itself
+    for code in 100..=599 {
+      let status = StatusCode::try_from(code).unwrap();
+      assert_eq!(status.to_u16(), code);
+      assert_eq!(u16::from(status.clone()), code);
+      assert_eq!(matches!(status, StatusCode::Other(..)), StatusCode::registered(code).is_none(), "for {code}");
+    }
+
+    assert_eq!(StatusCode::try_from(429), Ok(StatusCode::TooManyRequests));
+    assert_eq!(StatusCode::try_from(451), Ok(StatusCode::UnavailableForLegalReasons));
+    assert_eq!(StatusCode::try_from(306), Ok(StatusCode::Other(306, String::new())));
+    assert_eq!(StatusCode::try_from(99), Err(InvalidStatusCode(99)));
+    assert_eq!(StatusCode::try_from(600).unwrap_err().to_string(), "Invalid status code: 600");
+
+    // built directly, a registered code is not its variant
+    assert_ne!(StatusCode::Other(404, String::new()), StatusCode::NotFound);
+    assert_eq!(StatusCode::Other(700, String::new()).to_u16(), 700);
+  }
+
+  #[test]
+  fn reason_phrase_test() {
+    assert_eq!(StatusCode::HTTPVersionNotSupported.reason_phrase(), "HTTP Version Not Supported");
+    assert_eq!(StatusCode::NonAuthoritativeInformation.reason_phrase(), "Non-Authoritative Information");
+    assert_eq!(StatusCode::ImATeapot.reason_phrase(), "I'm a teapot");
+    assert_eq!(StatusCode::Other(404, String::new()).reason_phrase(), "Not Found");
+    assert_eq!(StatusCode::Other(404, "Nope".to_string()).reason_phrase(), "Nope");
+    assert_eq!(StatusCode::Other(599, String::new()).reason_phrase(), "Other");
+  }
+
+  #[test]
+  fn validate_test() {
+    assert!(StatusCode::NotFound.validate().is_ok());
+    assert!(StatusCode::Other(599, "Custom\tphrase caf\u{e9}".to_string()).validate().is_ok());
+    assert!(StatusCode::Other(200, String::new()).validate().is_ok());
+
+    let err = StatusCode::Other(700, String::new()).validate().unwrap_err();
+    assert_eq!(err.downcast_ref::<InvalidStatusCode>(), Some(&InvalidStatusCode(700)));
+    let err = StatusCode::Other(99, String::new()).validate().unwrap_err();
+    assert!(err.is::<InvalidStatusCode>());
+
+    for reason in ["OK\r\nSet-Cookie: a=b", "OK\n", "O\0K"] {
+      let err = StatusCode::Other(200, reason.to_string()).validate().unwrap_err();
+      assert_eq!(err.downcast_ref::<InvalidReasonPhrase>(), Some(&InvalidReasonPhrase(reason.to_string())));
+    }
+  }
+
+  #[test]
+  fn class_test() {
+    assert!(StatusCode::EarlyHints.is_informational());
+    assert!(StatusCode::NoContent.is_success());
+    assert!(StatusCode::PermanentRedirect.is_redirection());
+    assert!(StatusCode::UnprocessableContent.is_client_error());
+    assert!(StatusCode::Other(599, String::new()).is_server_error());
+
+    let classes = |status: StatusCode| [
+      status.is_informational(),
+      status.is_success(),
+      status.is_redirection(),
+      status.is_client_error(),
+      status.is_server_error()
+    ];
+    assert_eq!(classes(StatusCode::NotFound), [false, false, false, true, false]);
+    assert_eq!(classes(StatusCode::Other(600, String::new())), [false; 5]);
+  }
+
+  #[test]
+  fn display_test() {
+    assert_eq!(StatusCode::Gone.to_string(), "410");
+    assert_eq!(format!("{} {}", StatusCode::MisdirectedRequest, StatusCode::MisdirectedRequest.reason_phrase()), "421 Misdirected Request");
+  }
+}
@@ -0,0 +1,12 @@
+//! Unpredictable values from the operating system random number generator.
+
+pub fn fill(bytes: &mut [u8]) {
+  getrandom::getrandom(bytes).expect("Operating system random number generator should be available");
+}
+
+/// `length` random bytes, hex encoded.
+pub fn hex(length: usize) -> String {
+  let mut bytes = vec![0; length];
+  fill(&mut bytes);
+  bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
@@ -0,0 +1,308 @@
+//! Rate limiting, to throttle the clients sending too many requests.
+//!
+//! The [`RateLimit`] middleware counts the requests of each client,
+//! identified by a [`Key`], in a [`RateLimitStore`]. Requests over the
+//! limit are answered with `429 Too Many Requests` and `Retry-After`.
+//! Every response tells the client its quota in the `RateLimit-Limit`,
+//! `RateLimit-Remaining` and `RateLimit-Reset` fields.
+//!
+//! ```no_run
+//! use std::time::Duration;
+//! use webserver::{App, rate_limit::{Algorithm, Key, MemoryStore, RateLimit}};
+//!
+//! let mut app = App::new();
+//! // bursts of 20 requests, then one every 3 seconds per client
+//! app.all("*", RateLimit::new(Algorithm::token_bucket(20, Duration::from_secs(60)), MemoryStore::new()).middleware());
+//! // 5 attempts per minute per user name
+//! app.post("/login", RateLimit::new(Algorithm::sliding_window(5, Duration::from_secs(60)), MemoryStore::new())
+//!   .with_key(Key::Header("X-User".to_string()))
+//!   .middleware());
+//! app.listen("127.0.0.1:8080").unwrap();
+//! ```
+use std::{error::Error, fmt, rc::Rc, time::{Duration, SystemTime, UNIX_EPOCH}};
+
+use crate::{protocol::StatusCode, request::Request, router::Context, Handle, Return};
+
+mod memory;
+pub use memory::MemoryStore;
+
+#[cfg(test)]
+mod rate_limit_test;
+
+/// How the requests of a client are counted.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Algorithm {
+  /// Allows bursts of up to `capacity` requests, the bucket being
+  /// refilled with `capacity` tokens every `period`, one at a time.
+  TokenBucket {
+    capacity: u64,
+    period: Duration
+  },
+  /// Allows `limit` requests in any `window`, approximated by weighting
+  /// the count of the previous fixed window by its overlap with the
+  /// sliding one.
+  SlidingWindow {
+    limit: u64,
+    window: Duration
+  }
+}
+
+/// What a store keeps about a client.
+#[derive(Debug, Clone, PartialEq)]
+pub enum State {
+  TokenBucket {
+    tokens: f64,
+    updated: SystemTime
+  },
+  SlidingWindow {
+    /// The start of the current fixed window.
+    start: SystemTime,
+    current: u64,
+    previous: u64
+  }
+}
+
+/// A persisted state.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record {
+  pub state: State,
+  /// When the state is back to the one of a new client,
+  /// the store may forget it afterwards.
+  pub expires: SystemTime
+}
+
+/// Where the states of the clients are kept between requests.
+pub trait RateLimitStore {
+  fn load(&self, key: &str) -> Result<Option<Record>, Box<dyn Error>>;
+
+  fn save(&self, key: &str, record: &Record) -> Result<(), Box<dyn Error>>;
+}
+
+/// Lets the application keep a handle on the store given to [`RateLimit`].
+impl<S: RateLimitStore> RateLimitStore for Rc<S> {
+  fn load(&self, key: &str) -> Result<Option<Record>, Box<dyn Error>> {
+    self.as_ref().load(key)
+  }
+
+  fn save(&self, key: &str, record: &Record) -> Result<(), Box<dyn Error>> {
+    self.as_ref().save(key, record)
+  }
+}
+
+/// The outcome of counting a request.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Decision {
+  pub is_allowed: bool,
+  pub limit: u64,
+  pub remaining: u64,
+  /// When the quota is whole again.
+  pub reset: Duration,
+  /// When a denied request may be retried.
+  pub retry_after: Option<Duration>
+}
+
+fn elapsed(since: SystemTime, now: SystemTime) -> Duration {
+  // a clock going backwards counts as no time passing
+  now.duration_since(since).unwrap_or_default()
+}
+
+// delta-seconds are rounded up, so that a client waiting that long succeeds
+fn seconds(duration: Duration) -> u64 {
+  duration.as_secs() + u64::from(duration.subsec_nanos() > 0)
+}
+
+impl Algorithm {
+  /// Panics if `capacity` or `period` is zero.
+  pub fn token_bucket(capacity: u64, period: Duration) -> Algorithm {
+    assert!(capacity > 0 && !period.is_zero(), "Token bucket should have a capacity and a period");
+    Algorithm::TokenBucket { capacity, period }
+  }
+
+  /// Panics if `limit` or `window` is zero.
+  pub fn sliding_window(limit: u64, window: Duration) -> Algorithm {
+    assert!(limit > 0 && !window.is_zero(), "Sliding window should have a limit and a duration");
+    Algorithm::SlidingWindow { limit, window }
+  }
+
+  /// Counts a request at `now`, given the previous record of the client.
+  pub fn acquire(&self, record: Option<&Record>, now: SystemTime) -> (Record, Decision) {
+    match self {
+      Algorithm::TokenBucket { capacity, period } => {
+        let capacity = *capacity as f64;
+        let per_second = capacity / period.as_secs_f64();
+        let tokens = match record.map(|record| &record.state) {
+          Some(State::TokenBucket { tokens, updated }) => {
+            (tokens + elapsed(*updated, now).as_secs_f64() * per_second).min(capacity)
+          },
+          _ => capacity
+        };
+
+        let is_allowed = tokens >= 1.0;
+        let tokens = if is_allowed { tokens - 1.0 } else { tokens };
+        let reset = Duration::from_secs_f64((capacity - tokens) / per_second);
+        let retry_after = (!is_allowed).then(|| Duration::from_secs_f64((1.0 - tokens) / per_second));
+
+        let record = Record {
+          state: State::TokenBucket { tokens, updated: now },
+          expires: now + reset
+        };
+        let decision = Decision {
+          is_allowed,
+          limit: capacity as u64,
+          remaining: tokens.floor() as u64,
+          reset,
+          retry_after
+        };
+        (record, decision)
+      },
+      Algorithm::SlidingWindow { limit, window } => {
+        // fixed windows are aligned on the epoch, so that all clients share them
+        let since_epoch = elapsed(UNIX_EPOCH, now);
+        let offset = Duration::from_nanos((since_epoch.as_nanos() % window.as_nanos()) as u64);
+        let start = now - offset;
+
+        let (mut current, previous) = match record.map(|record| &record.state) {
+          Some(State::SlidingWindow { start: last, current, previous }) if *last == start => (*current, *previous),
+          Some(State::SlidingWindow { start: last, current, .. }) if *last + *window == start => (0, *current),
+          _ => (0, 0)
+        };
+
+        let weight = 1.0 - offset.as_secs_f64() / window.as_secs_f64();
+        let estimate = previous as f64 * weight + current as f64;
+        let is_allowed = estimate + 1.0 <= *limit as f64;
+        if is_allowed {
+          current += 1;
+        }
+
+        let remaining = (*limit as f64 - previous as f64 * weight - current as f64).max(0.0);
+        let until_next = *window - offset;
+        let retry_after = (!is_allowed).then(|| {
+          if current < *limit {
+            // until the previous window weighs little enough
+            let weight = (*limit - current - 1) as f64 / previous as f64;
+            window.mul_f64(1.0 - weight).saturating_sub(offset)
+          } else {
+            // until the next window, and the current one weighs little enough
+            let weight = (*limit - 1) as f64 / current as f64;
+            until_next + window.mul_f64(1.0 - weight)
+          }
+        });
+
+        let record = Record {
+          state: State::SlidingWindow { start, current, previous },
+          expires: start + *window * 2
+        };
+        let decision = Decision {
+          is_allowed,
+          limit: *limit,
+          remaining: remaining.floor() as u64,
+          // the current window is forgotten once the next one is over
+          reset: match current {
+            0 => until_next,
+            _ => until_next + *window
+          },
+          retry_after
+        };
+        (record, decision)
+      }
+    }
+  }
+}
+
+type KeyFn = dyn Fn(&Request) -> Option<String>;
+
+/// What identifies the client a request is counted for.
+#[derive(Clone)]
+pub enum Key {
+  /// The IP address of the peer, which is the last proxy when behind one.
+  RemoteAddr,
+  /// The value of a header field, e.g. an API key.
+  Header(String),
+  /// The request path, to limit each resource on its own.
+  Path,
+  /// The key the function returns.
+  Function(Rc<KeyFn>)
+}
+
+impl Key {
+  pub fn function(function: impl Fn(&Request) -> Option<String> + 'static) -> Key {
+    Key::Function(Rc::new(function))
+  }
+
+  /// The key of `req`, `None` if it has none.
+  pub fn extract(&self, req: &Request) -> Option<String> {
+    match self {
+      Key::RemoteAddr => req.remote_addr().map(|addr| addr.ip().to_string()),
+      Key::Header(name) => req.headers().get(name).cloned(),
+      Key::Path => Some(req.path().to_string()),
+      Key::Function(function) => function(req)
+    }
+  }
+}
+
+impl fmt::Debug for Key {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Key::RemoteAddr => f.write_str("RemoteAddr"),
+      Key::Header(name) => f.debug_tuple("Header").field(name).finish(),
+      Key::Path => f.write_str("Path"),
+      Key::Function(_) => f.write_str("Function")
+    }
+  }
+}
+
+/// The rate limiting middleware, see the [module documentation](self).
+///
+/// Requests without a key are not limited, e.g. those without the header
+/// of a [`Key::Header`], so that another limiter can take care of them.
+pub struct RateLimit {
+  algorithm: Algorithm,
+  store: Rc<dyn RateLimitStore>,
+  key: Key
+}
+
+impl RateLimit {
+  /// Limits each client by its IP address.
+  pub fn new(algorithm: Algorithm, store: impl RateLimitStore + 'static) -> RateLimit {
+    RateLimit {
+      algorithm,
+      store: Rc::new(store),
+      key: Key::RemoteAddr
+    }
+  }
+
+  pub fn with_key(mut self, key: Key) -> RateLimit {
+    self.key = key;
+    self
+  }
+
+  pub fn middleware(self) -> Handle {
+    Handle::before(move |ctx: &mut Context| self.limit(ctx))
+  }
+
+  fn limit(&self, ctx: &mut Context) -> Result<Return, Box<dyn Error>> {
+    let Some(key) = self.key.extract(ctx.req) else {
+      return Ok(Return::Next)
+    };
+
+    let record = self.store.load(&key)?;
+    let (record, decision) = self.algorithm.acquire(record.as_ref(), SystemTime::now());
+    self.store.save(&key, &record)?;
+
+    let res = &mut *ctx.res;
+    res.headers.set("RateLimit-Limit", decision.limit.to_string());
+    res.headers.set("RateLimit-Remaining", decision.remaining.to_string());
+    res.headers.set("RateLimit-Reset", seconds(decision.reset).to_string());
+
+    match decision.retry_after {
+      Some(retry_after) => {
+        res.headers.set("Retry-After", seconds(retry_after).to_string());
+        res
+          .status(StatusCode::TooManyRequests)
+          .content_type("text/plain")
+          .send_body(b"Too Many Requests".to_vec())
+      },
+      None => Ok(Return::Next)
+    }
+  }
+}
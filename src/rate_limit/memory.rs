@@ -0,0 +1,87 @@
+use std::{cell::{Cell, RefCell}, collections::HashMap, error::Error, time::SystemTime};
+
+use crate::{error::HttpError, protocol::StatusCode};
+
+use super::{RateLimitStore, Record};
+
+// new clients saved between two purges of the expired states
+const PURGE_INTERVAL: usize = 1024;
+
+/// Keeps the states of the clients in memory, they are lost on restart.
+///
+/// Expired states are purged every 1024 new clients, and whenever the
+/// store is full. A new client that still finds it full is answered with
+/// `429 Too Many Requests`, the known clients being counted as before.
+/// The store holds up to 100 000 clients unless set otherwise.
+#[derive(Debug)]
+pub struct MemoryStore {
+  records: RefCell<HashMap<String, Record>>,
+  capacity: usize,
+  // new clients since the last purge
+  new_clients: Cell<usize>,
+  // whether the last purge left the store full
+  is_stalled: Cell<bool>
+}
+
+impl Default for MemoryStore {
+  fn default() -> Self {
+    MemoryStore {
+      records: RefCell::default(),
+      capacity: 100_000,
+      new_clients: Cell::new(0),
+      is_stalled: Cell::new(false)
+    }
+  }
+}
+
+impl MemoryStore {
+  pub fn new() -> MemoryStore {
+    MemoryStore::default()
+  }
+
+  /// Maximum number of clients stored, expired ones included.
+  pub fn with_capacity(mut self, capacity: usize) -> MemoryStore {
+    self.capacity = capacity;
+    self
+  }
+
+  /// The number of clients stored, expired ones included.
+  pub fn len(&self) -> usize {
+    self.records.borrow().len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.records.borrow().is_empty()
+  }
+}
+
+impl RateLimitStore for MemoryStore {
+  fn load(&self, key: &str) -> Result<Option<Record>, Box<dyn Error>> {
+    Ok(self.records.borrow().get(key).cloned())
+  }
+
+  fn save(&self, key: &str, record: &Record) -> Result<(), Box<dyn Error>> {
+    let mut records = self.records.borrow_mut();
+    if !records.contains_key(key) {
+      let new_clients = self.new_clients.get() + 1;
+      let is_full = records.len() >= self.capacity;
+      // a full store is only purged again once new clients came
+      // through the interval, the expired states being gone
+      if new_clients >= PURGE_INTERVAL || (is_full && !self.is_stalled.get()) {
+        let now = SystemTime::now();
+        records.retain(|_, record| record.expires > now);
+        self.new_clients.set(0);
+        self.is_stalled.set(records.len() >= self.capacity);
+      } else {
+        self.new_clients.set(new_clients);
+      }
+
+      if records.len() >= self.capacity {
+        return Err(Box::new(HttpError::new(StatusCode::TooManyRequests, "Too many clients")))
+      }
+    }
+
+    records.insert(key.to_string(), record.clone());
+    Ok(())
+  }
+}
@@ -0,0 +1,212 @@
+use crate::{app::app_test::{header, send_from}, error::HttpError, App};
+use super::*;
+
+fn at(seconds: f64) -> SystemTime {
+  UNIX_EPOCH + Duration::from_secs_f64(seconds)
+}
+
+// counts requests at the given times, starting from a new client
+fn run(algorithm: &Algorithm, times: &[f64]) -> Vec<Decision> {
+  let mut record = None;
+  times
+    .iter()
+    .map(|time| {
+      let (next, decision) = algorithm.acquire(record.as_ref(), at(*time));
+      record = Some(next);
+      decision
+    })
+    .collect()
+}
+
+fn app(limit: RateLimit) -> App {
+  let mut app = App::new();
+  app.all("*", limit.middleware());
+  app.get("/", Handle::main(|ctx| ctx.res.send_body(b"hello".to_vec())));
+  app
+}
+
+fn get(app: &App, from: &str, headers: &str) -> String {
+  send_from(app, from.parse().ok(), &format!("GET / HTTP/1.1\r\nHost: localhost\r\n{headers}\r\n"))
+}
+
+mod token_bucket_test {
+  use super::*;
+
+  #[test]
+  fn burst_test() {
+    let algorithm = Algorithm::token_bucket(2, Duration::from_secs(10));
+    let decisions = run(&algorithm, &[100.0, 100.0, 100.0]);
+
+    assert!(decisions[0].is_allowed && decisions[1].is_allowed);
+    assert_eq!(decisions[1].remaining, 0);
+    assert_eq!(decisions[1].reset, Duration::from_secs(10));
+    assert_eq!(decisions[2], Decision {
+      is_allowed: false,
+      limit: 2,
+      remaining: 0,
+      reset: Duration::from_secs(10),
+      retry_after: Some(Duration::from_secs(5))
+    });
+  }
+
+  #[test]
+  fn refill_test() {
+    let algorithm = Algorithm::token_bucket(2, Duration::from_secs(10));
+    let decisions = run(&algorithm, &[100.0, 100.0, 104.0, 105.0, 105.0, 200.0]);
+
+    assert!(!decisions[2].is_allowed);
+    assert_eq!(decisions[2].retry_after, Some(Duration::from_secs(1)));
+    assert!(decisions[3].is_allowed);
+    assert!(!decisions[4].is_allowed);
+    // never more than the capacity
+    assert_eq!(decisions[5].remaining, 1);
+  }
+
+  #[test]
+  #[should_panic]
+  fn empty_test() {
+    Algorithm::token_bucket(0, Duration::from_secs(1));
+  }
+}
+
+mod sliding_window_test {
+  use super::*;
+
+  #[test]
+  fn limit_test() {
+    let algorithm = Algorithm::sliding_window(2, Duration::from_secs(10));
+    let decisions = run(&algorithm, &[100.0, 101.0, 102.0]);
+
+    assert!(decisions[0].is_allowed && decisions[1].is_allowed);
+    assert_eq!(decisions[0].remaining, 1);
+    assert_eq!(decisions[2], Decision {
+      is_allowed: false,
+      limit: 2,
+      remaining: 0,
+      reset: Duration::from_secs(18),
+      retry_after: Some(Duration::from_secs(13))
+    });
+  }
+
+  #[test]
+  fn slide_test() {
+    let algorithm = Algorithm::sliding_window(2, Duration::from_secs(10));
+    // the previous window weighs half of its count at 115
+    let decisions = run(&algorithm, &[100.0, 101.0, 114.0, 115.0, 115.0]);
+    assert!(!decisions[2].is_allowed);
+    assert_eq!(decisions[2].retry_after, Some(Duration::from_secs(1)));
+    assert!(decisions[3].is_allowed);
+    assert!(!decisions[4].is_allowed);
+    assert_eq!(decisions[4].retry_after, Some(Duration::from_secs(5)));
+
+    // two windows later, the client starts over
+    let decisions = run(&algorithm, &[100.0, 101.0, 120.0, 120.0]);
+    assert!(decisions[2].is_allowed && decisions[3].is_allowed);
+  }
+}
+
+mod middleware_test {
+  use super::*;
+
+  #[test]
+  fn remote_addr_test() {
+    let app = app(RateLimit::new(Algorithm::token_bucket(2, Duration::from_secs(60)), MemoryStore::new()));
+
+    let response = get(&app, "10.0.0.1:5000", "");
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert_eq!(header(&response, "RateLimit-Limit"), Some("2"));
+    assert_eq!(header(&response, "RateLimit-Remaining"), Some("1"));
+    assert_eq!(header(&response, "RateLimit-Reset"), Some("30"));
+    assert_eq!(header(&response, "Retry-After"), None);
+
+    // the port changes with every connection
+    get(&app, "10.0.0.1:5001", "");
+    let response = get(&app, "10.0.0.1:5002", "");
+    assert!(response.starts_with("HTTP/1.1 429 Too Many Requests\r\n"));
+    assert_eq!(header(&response, "RateLimit-Remaining"), Some("0"));
+    assert_eq!(header(&response, "Retry-After"), Some("30"));
+    assert!(response.ends_with("\r\n\r\nToo Many Requests"));
+
+    assert!(get(&app, "10.0.0.2:5000", "").starts_with("HTTP/1.1 200 OK\r\n"));
+  }
+
+  #[test]
+  fn key_test() {
+    let store = Rc::new(MemoryStore::new());
+    let limit = RateLimit::new(Algorithm::sliding_window(1, Duration::from_secs(60)), store.clone())
+      .with_key(Key::Header("X-Api-Key".to_string()));
+    let app = app(limit);
+
+    assert!(get(&app, "10.0.0.1:5000", "X-Api-Key: a\r\n").starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(get(&app, "10.0.0.2:5000", "X-Api-Key: a\r\n").starts_with("HTTP/1.1 429 Too Many Requests\r\n"));
+    assert!(get(&app, "10.0.0.1:5000", "X-Api-Key: b\r\n").starts_with("HTTP/1.1 200 OK\r\n"));
+
+    // requests without a key are not limited
+    let response = get(&app, "10.0.0.1:5000", "");
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert_eq!(header(&response, "RateLimit-Limit"), None);
+    assert_eq!(store.len(), 2);
+  }
+
+  #[test]
+  fn function_test() {
+    let key = Key::function(|req| req.headers().get("authorization").map(|value| value.to_lowercase()));
+    assert_eq!(format!("{key:?}"), "Function");
+
+    let app = app(RateLimit::new(Algorithm::token_bucket(1, Duration::from_secs(60)), MemoryStore::new()).with_key(key));
+    assert!(get(&app, "", "Authorization: Token A\r\n").starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(get(&app, "", "Authorization: token a\r\n").starts_with("HTTP/1.1 429 Too Many Requests\r\n"));
+  }
+}
+
+mod store_test {
+  use super::*;
+
+  #[test]
+  fn purge_test() {
+    // a full store purges the expired states
+    let store = MemoryStore::new().with_capacity(1);
+    let expired = Record {
+      state: State::TokenBucket { tokens: 1.0, updated: UNIX_EPOCH },
+      expires: UNIX_EPOCH + Duration::from_secs(1)
+    };
+    let current = Record { expires: SystemTime::now() + Duration::from_secs(60), ..expired.clone() };
+    store.save("expired", &expired).unwrap();
+    store.save("current", &current).unwrap();
+    assert!(store.load("expired").unwrap().is_none());
+
+    store.save("current", &expired).unwrap();
+    assert_eq!(store.load("current").unwrap(), Some(expired));
+  }
+
+  #[test]
+  fn capacity_test() {
+    let expired = Record {
+      state: State::TokenBucket { tokens: 1.0, updated: UNIX_EPOCH },
+      expires: UNIX_EPOCH + Duration::from_secs(1)
+    };
+    let current = Record { expires: SystemTime::now() + Duration::from_secs(60), ..expired.clone() };
+
+    // expired clients make room for new ones
+    let store = MemoryStore::new().with_capacity(100);
+    for i in 0..10_000 {
+      store.save(&format!("expired-{i}"), &expired).unwrap();
+      assert!(store.len() <= 100);
+    }
+
+    for i in 0..100 {
+      store.save(&format!("current-{i}"), &current).unwrap();
+    }
+    assert_eq!(store.len(), 100);
+    assert!(store.load("expired-9999").unwrap().is_none());
+
+    // new clients are then denied, known ones still counted
+    for i in 0..10_000 {
+      let err = store.save(&format!("new-{i}"), &current).unwrap_err();
+      assert_eq!(err.downcast_ref::<HttpError>().map(HttpError::status), Some(&StatusCode::TooManyRequests));
+    }
+    store.save("current-0", &expired).unwrap();
+    assert_eq!(store.load("current-0").unwrap(), Some(expired));
+    assert_eq!(store.len(), 100);
+  }
+}
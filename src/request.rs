@@ -1,18 +1,44 @@
-use std::{collections::HashMap, io::BufReader, net::TcpStream};
+use std::{any::{Any, TypeId}, collections::HashMap, io::{self, BufRead, Read}, net::SocketAddr};
+#[cfg(feature = "secure-cookies")]
+use std::rc::Rc;
 
-use crate::header::Headers;
+use crate::{config::ServerConfig, cookie::Cookies, error::HttpError, form::Form, header::{typed::ContentType, Headers}, multipart::Multipart, protocol::StatusCode, stream::error::Error as ParseError};
+#[cfg(feature = "secure-cookies")]
+use crate::cookie::{self, Jar};
+
+#[cfg(test)]
+mod request_test;
 
 pub struct Request {
   location: Location,
   headers: Headers,
-  body: Option<BufReader<TcpStream>>
+  body: Option<Box<dyn BufRead>>,
+  body_limit: usize,
+  max_body_size: usize,
+  extensions: Extensions,
+  remote_addr: Option<SocketAddr>,
+  #[cfg(feature = "secure-cookies")]
+  jar: Option<Rc<Jar>>
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum Method {
   All,
   Post,
-  Get
+  Get,
+  Options
+}
+
+impl Method {
+  /// The method token, `*` for handlers registered on every method.
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      Method::All => "*",
+      Method::Post => "POST",
+      Method::Get => "GET",
+      Method::Options => "OPTIONS"
+    }
+  }
 }
 
 #[derive(Debug)]
@@ -21,15 +47,83 @@ pub struct Location(
   pub URI
 );
 
+/// Values attached to a request by middlewares, at most one per type.
+///
+/// ```
+/// use webserver::request::Extensions;
+///
+/// struct UserId(u32);
+///
+/// let mut extensions = Extensions::default();
+/// extensions.insert(UserId(42));
+/// assert_eq!(extensions.get::<UserId>().map(|id| id.0), Some(42));
+/// ```
+#[derive(Default)]
+pub struct Extensions {
+  values: HashMap<TypeId, Box<dyn Any>>
+}
+
+impl Extensions {
+  /// Inserts `value`, returning the value of the same type it replaces.
+  pub fn insert<T: 'static>(&mut self, value: T) -> Option<T> {
+    self.values
+      .insert(TypeId::of::<T>(), Box::new(value))
+      .and_then(|previous| previous.downcast().ok())
+      .map(|previous| *previous)
+  }
+
+  pub fn get<T: 'static>(&self) -> Option<&T> {
+    self.values
+      .get(&TypeId::of::<T>())
+      .and_then(|value| value.downcast_ref())
+  }
+
+  pub fn get_mut<T: 'static>(&mut self) -> Option<&mut T> {
+    self.values
+      .get_mut(&TypeId::of::<T>())
+      .and_then(|value| value.downcast_mut())
+  }
+
+  pub fn remove<T: 'static>(&mut self) -> Option<T> {
+    self.values
+      .remove(&TypeId::of::<T>())
+      .and_then(|value| value.downcast().ok())
+      .map(|value| *value)
+  }
+
+  pub fn contains<T: 'static>(&self) -> bool {
+    self.values.contains_key(&TypeId::of::<T>())
+  }
+}
+
+// a chunked body over `max_body_size` fails while it is read
+pub(crate) fn body_error(err: &io::Error) -> HttpError {
+  let is_too_large = err
+    .get_ref()
+    .and_then(|err| err.downcast_ref::<ParseError>())
+    .is_some_and(|err| matches!(err, ParseError::BodyTooLarge(_)));
+
+  match is_too_large {
+    true => HttpError::from_status(StatusCode::ContentTooLarge),
+    false => HttpError::new(StatusCode::BadRequest, format!("Invalid body: {err}"))
+  }
+}
+
 pub type Header = HashMap<String, Vec<String>>;
 pub type URI = String;
 
 impl Request {
-  pub(crate) fn new(reader: Option<BufReader<TcpStream>>) -> Request {
+  pub(crate) fn new(reader: Option<Box<dyn BufRead>>) -> Request {
     Request {
       headers: Headers::new(),
       location: Location(Method::Get, String::new()),
-      body: reader
+      body: reader,
+      body_limit: ServerConfig::default().max_parsed_body_size,
+      max_body_size: ServerConfig::default().max_body_size,
+      extensions: Extensions::default(),
+      remote_addr: None,
+      #[cfg(feature = "secure-cookies")]
+      jar: None
     }
   }
 
@@ -37,24 +131,199 @@ impl Request {
     &self.location
   }
 
+  /// The request target without its query string.
+  pub fn path(&self) -> &str {
+    let target = &self.location.1;
+    target.split_once('?').map_or(target, |(path, _)| path)
+  }
+
+  /// The query string, without the leading `?`.
+  pub fn query_string(&self) -> Option<&str> {
+    self.location.1.split_once('?').map(|(_, query)| query)
+  }
+
+  /// The decoded query string parameters.
+  pub fn query(&self) -> Form {
+    Form::parse(self.query_string().unwrap_or_default())
+  }
+
+  /// Deserializes the query string parameters, failing with
+  /// `400 Bad Request` when they do not match `T`.
+  #[cfg(feature = "serde")]
+  pub fn query_as<T: serde::de::DeserializeOwned>(&self) -> Result<T, HttpError> {
+    self.query()
+      .deserialize()
+      .map_err(|err| HttpError::new(StatusCode::BadRequest, format!("Invalid query string: {err}")))
+  }
+
   pub fn headers(&self) -> &Headers {
     &self.headers
   }
 
-  pub fn body(&mut self) -> &Option<BufReader<TcpStream>> {
+  /// The cookies sent in `Cookie` header fields.
+  pub fn cookies(&self) -> Cookies {
+    Cookies::parse(self.headers.get_all("cookie").into_iter().flatten())
+  }
+
+  /// The signed cookies that are authentic, see [`Jar::sign`].
+  #[cfg(feature = "secure-cookies")]
+  pub fn signed_cookies(&self) -> Result<Cookies, cookie::Error> {
+    let jar = self.jar.as_ref().ok_or(cookie::Error::MissingKey)?;
+    Ok(jar.verify_all(&self.cookies()))
+  }
+
+  /// The private cookies that are authentic, see [`Jar::encrypt`].
+  #[cfg(feature = "secure-cookies")]
+  pub fn private_cookies(&self) -> Result<Cookies, cookie::Error> {
+    let jar = self.jar.as_ref().ok_or(cookie::Error::MissingKey)?;
+    Ok(jar.decrypt_all(&self.cookies()))
+  }
+
+  /// The address of the peer the request was received from, which is
+  /// the last proxy rather than the client when behind one.
+  pub fn remote_addr(&self) -> Option<SocketAddr> {
+    self.remote_addr
+  }
+
+  pub fn extensions(&self) -> &Extensions {
+    &self.extensions
+  }
+
+  pub fn extensions_mut(&mut self) -> &mut Extensions {
+    &mut self.extensions
+  }
+
+  pub fn body(&mut self) -> &mut Option<Box<dyn BufRead>> {
     &mut self.body
   }
 
-  pub(crate) fn set_body(&mut self, body: Option<BufReader<TcpStream>>) {
+  /// Reads the whole body into memory, up to `ServerConfig::max_parsed_body_size`.
+  /// The body is consumed, reading it again returns nothing.
+  pub fn read_body(&mut self) -> Result<Vec<u8>, HttpError> {
+    let Some(body) = self.body.take() else {
+      return Ok(Vec::new())
+    };
+
+    let mut bytes = Vec::new();
+    let limit = self.body_limit as u64;
+    body
+      .take(limit + 1)
+      .read_to_end(&mut bytes)
+      .map_err(|err| body_error(&err))?;
+
+    if bytes.len() as u64 > limit {
+      return Err(HttpError::from_status(StatusCode::ContentTooLarge))
+    }
+    Ok(bytes)
+  }
+
+  /// Streams a `multipart/form-data` body, see [`Multipart`]. Each part,
+  /// and the whole body, are limited to `ServerConfig::max_parsed_body_size`
+  /// unless the returned reader sets other limits.
+  ///
+  /// Fails with `415 Unsupported Media Type` for other media types and
+  /// `400 Bad Request` without a valid boundary.
+  pub fn multipart(&mut self) -> Result<Multipart, HttpError> {
+    if !self.is_content_type("multipart/form-data") {
+      return Err(HttpError::new(StatusCode::UnsupportedMediaType, "Expected a multipart/form-data body"))
+    }
+
+    let boundary = self.headers
+      .content_type()
+      .and_then(|t| t.param("boundary").map(String::from))
+      .ok_or_else(|| HttpError::new(StatusCode::BadRequest, "Missing multipart boundary"))?;
+
+    let body = self.body.take().unwrap_or_else(|| Box::new(io::empty()));
+    let limit = self.body_limit as u64;
+    Ok(Multipart::new(body, &boundary)?
+      .with_max_part_size(limit)
+      .with_max_total_size(limit))
+  }
+
+  /// Whether the body is declared as `media_type`, ignoring parameters.
+  /// A media type ending with `/*` (e.g. `text/*`) matches any subtype.
+  pub fn is_content_type(&self, media_type: &str) -> bool {
+    let Some(content_type) = self.headers.typed::<ContentType>() else {
+      return false
+    };
+
+    match media_type.strip_suffix("/*") {
+      Some(main_type) => content_type.main_type().eq_ignore_ascii_case(main_type),
+      None => content_type.media_type().eq_ignore_ascii_case(media_type)
+    }
+  }
+
+  /// Deserializes a JSON body, sent as `application/json` or
+  /// another `+json` media type.
+  ///
+  /// Fails with `415 Unsupported Media Type` for other media types,
+  /// `413 Content Too Large` over `ServerConfig::max_parsed_body_size`
+  /// and `400 Bad Request` for invalid JSON.
+  #[cfg(feature = "json")]
+  pub fn json<T: serde::de::DeserializeOwned>(&mut self) -> Result<T, HttpError> {
+    let is_json = self.headers
+      .typed::<ContentType>()
+      .is_some_and(|t| t.media_type() == "application/json" || t.subtype().ends_with("+json"));
+
+    if !is_json {
+      return Err(HttpError::new(StatusCode::UnsupportedMediaType, "Expected a JSON body"))
+    }
+
+    let body = self.read_body()?;
+    serde_json::from_slice(&body)
+      .map_err(|err| HttpError::new(StatusCode::BadRequest, format!("Invalid JSON body: {err}")))
+  }
+
+  /// Parses an `application/x-www-form-urlencoded` body.
+  ///
+  /// Fails with `415 Unsupported Media Type` for other media types and
+  /// `413 Content Too Large` over `ServerConfig::max_parsed_body_size`.
+  pub fn form(&mut self) -> Result<Form, HttpError> {
+    if !self.is_content_type("application/x-www-form-urlencoded") {
+      return Err(HttpError::new(StatusCode::UnsupportedMediaType, "Expected a form body"))
+    }
+
+    let body = self.read_body()?;
+    Ok(Form::parse_bytes(&body))
+  }
+
+  /// Deserializes an `application/x-www-form-urlencoded` body, see
+  /// [`Request::form`]. Fails with `400 Bad Request` when it does not match `T`.
+  #[cfg(feature = "serde")]
+  pub fn form_as<T: serde::de::DeserializeOwned>(&mut self) -> Result<T, HttpError> {
+    self.form()?
+      .deserialize()
+      .map_err(|err| HttpError::new(StatusCode::BadRequest, format!("Invalid form body: {err}")))
+  }
+
+  pub(crate) fn set_body(&mut self, body: Option<Box<dyn BufRead>>) {
     self.body = body
   }
 
+  pub(crate) fn set_body_limit(&mut self, limit: usize) {
+    self.body_limit = limit
+  }
+
+  pub(crate) fn set_max_body_size(&mut self, size: usize) {
+    self.max_body_size = size
+  }
+
+  #[cfg(feature = "compression")]
+  pub(crate) fn max_body_size(&self) -> usize {
+    self.max_body_size
+  }
+
+  pub(crate) fn set_remote_addr(&mut self, remote_addr: Option<SocketAddr>) {
+    self.remote_addr = remote_addr
+  }
+
   pub(crate) fn set_location(&mut self, loc: Location) {
     self.location = loc
   }
 
-  pub(crate) fn set_headers(&mut self, headers: Headers) {
-    self.headers = headers
+  #[cfg(feature = "secure-cookies")]
+  pub(crate) fn set_jar(&mut self, jar: Option<Rc<Jar>>) {
+    self.jar = jar
   }
 
   pub(crate) fn mut_headers(&mut self) -> &mut Headers {
@@ -0,0 +1,171 @@
+use std::io::{BufReader, Cursor};
+
+use crate::{app::app_test::send, config::ServerConfig, stream::parse_stream, App, Handle};
+use super::*;
+
+fn request(content_type: Option<&str>, body: &str) -> Request {
+  let mut req = Request::new(Some(Box::new(Cursor::new(body.as_bytes().to_vec()))));
+  if let Some(content_type) = content_type {
+    req.mut_headers().set("content-type", content_type.to_string());
+  }
+  req
+}
+
+mod body_test {
+  use super::*;
+
+  #[test]
+  fn read_body_test() {
+    let mut req = request(None, "hello");
+    assert_eq!(req.read_body().unwrap(), b"hello");
+    assert_eq!(req.read_body().unwrap(), b"");
+  }
+
+  #[test]
+  fn body_limit_test() {
+    let mut req = request(None, "hello");
+    req.set_body_limit(5);
+    assert!(req.read_body().is_ok());
+
+    let mut req = request(None, "hello!");
+    req.set_body_limit(5);
+    assert_eq!(req.read_body().unwrap_err().status(), &StatusCode::ContentTooLarge);
+  }
+
+  #[test]
+  fn chunked_body_too_large_test() {
+    let config = ServerConfig { max_body_size: 4, ..Default::default() };
+    let raw = "POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n0\r\n\r\n";
+    let mut req = parse_stream(BufReader::new(raw.as_bytes()), &config).unwrap();
+    assert_eq!(req.read_body().unwrap_err().status(), &StatusCode::ContentTooLarge);
+
+    let raw = "POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: chunked\r\n\r\nzz\r\n";
+    let mut req = parse_stream(BufReader::new(raw.as_bytes()), &config).unwrap();
+    assert_eq!(req.read_body().unwrap_err().status(), &StatusCode::BadRequest);
+  }
+
+  #[test]
+  fn content_type_test() {
+    let req = request(Some("Text/HTML; charset=utf-8"), "");
+    assert!(req.is_content_type("text/html"));
+    assert!(req.is_content_type("text/*"));
+    assert!(!req.is_content_type("text/plain"));
+    assert!(!request(None, "").is_content_type("text/*"));
+  }
+}
+
+mod form_test {
+  use super::*;
+
+  #[test]
+  fn form_test() {
+    let mut req = request(Some("application/x-www-form-urlencoded"), "a=1&b=x+y&a=2");
+    let form = req.form().unwrap();
+    assert_eq!(form.get_all("a").collect::<Vec<_>>(), vec!["1", "2"]);
+    assert_eq!(form.get("b"), Some("x y"));
+  }
+
+  #[test]
+  fn form_errors_test() {
+    let status = |content_type, body| request(content_type, body).form().unwrap_err().status().clone();
+    assert_eq!(status(None, "a=1"), StatusCode::UnsupportedMediaType);
+    assert_eq!(status(Some("multipart/form-data"), "a=1"), StatusCode::UnsupportedMediaType);
+
+    let mut req = request(Some("application/x-www-form-urlencoded"), "a=123456789");
+    req.set_body_limit(8);
+    assert_eq!(req.form().unwrap_err().status(), &StatusCode::ContentTooLarge);
+  }
+
+  #[test]
+  fn query_test() {
+    let mut req = request(None, "");
+    req.set_location(Location(Method::Get, "/search?q=a%20b&page=2".to_string()));
+    assert_eq!(req.path(), "/search");
+    assert_eq!(req.query_string(), Some("q=a%20b&page=2"));
+    assert_eq!(req.query().get("q"), Some("a b"));
+
+    req.set_location(Location(Method::Get, "/search".to_string()));
+    assert_eq!(req.path(), "/search");
+    assert_eq!(req.query_string(), None);
+    assert!(req.query().is_empty());
+  }
+}
+
+mod multipart_test {
+  use super::*;
+
+  #[test]
+  fn multipart_test() {
+    let body = "--b\r\nContent-Disposition: form-data; name=\"a\"\r\n\r\n1\r\n--b--";
+    let mut req = request(Some("multipart/form-data; boundary=\"b\""), body);
+    let mut multipart = req.multipart().unwrap();
+    let mut part = multipart.next_part().unwrap().unwrap();
+    assert_eq!(part.name(), "a");
+    assert_eq!(part.text().unwrap(), "1");
+  }
+
+  #[test]
+  fn multipart_errors_test() {
+    let status = |content_type, body| request(content_type, body).multipart().err().unwrap().status().clone();
+    assert_eq!(status(Some("application/x-www-form-urlencoded"), ""), StatusCode::UnsupportedMediaType);
+    assert_eq!(status(Some("multipart/form-data"), ""), StatusCode::BadRequest);
+    assert_eq!(status(Some("multipart/form-data; boundary=\"a b \""), ""), StatusCode::BadRequest);
+  }
+
+  #[test]
+  fn multipart_limits_test() {
+    let config = ServerConfig { max_parsed_body_size: 64, ..Default::default() };
+    let mut app = App::with_config(config);
+    app.post("/", Handle::main(|ctx| {
+      let mut multipart = ctx.req.multipart()?;
+      while let Some(mut part) = multipart.next_part().map_err(HttpError::from)? {
+        part.text().map_err(HttpError::from)?;
+      }
+      ctx.res.send_headers()
+    }));
+
+    let post = |value: &str| {
+      let body = format!("--b\r\nContent-Disposition: form-data; name=\"a\"\r\n\r\n{value}\r\n--b--");
+      send(&app, &format!(
+        "POST / HTTP/1.1\r\nHost: a\r\nContent-Type: multipart/form-data; boundary=b\r\nContent-Length: {}\r\n\r\n{body}",
+        body.len()
+      ))
+    };
+    assert!(post("small").starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(post(&"a".repeat(64)).starts_with("HTTP/1.1 413 Content Too Large\r\n"));
+  }
+}
+
+#[cfg(feature = "json")]
+mod json_test {
+  use serde::Deserialize;
+  use super::*;
+
+  #[derive(Debug, Deserialize, PartialEq)]
+  struct User {
+    name: String,
+    age: u8
+  }
+
+  #[test]
+  fn json_test() {
+    let mut req = request(Some("application/json; charset=utf-8"), r#"{"name": "a", "age": 3}"#);
+    assert_eq!(req.json::<User>().unwrap(), User { name: "a".to_string(), age: 3 });
+
+    let mut req = request(Some("application/merge-patch+json"), r#"{"name": "a", "age": 3}"#);
+    assert!(req.json::<User>().is_ok());
+  }
+
+  #[test]
+  fn json_errors_test() {
+    let status = |content_type, body| request(content_type, body).json::<User>().unwrap_err().status().clone();
+    assert_eq!(status(None, "{}"), StatusCode::UnsupportedMediaType);
+    assert_eq!(status(Some("text/plain"), "{}"), StatusCode::UnsupportedMediaType);
+    assert_eq!(status(Some("application/json"), r#"{"name": "a"}"#), StatusCode::BadRequest);
+    assert_eq!(status(Some("application/json"), "{"), StatusCode::BadRequest);
+
+    let mut req = request(Some("application/json"), r#"{"name": "a", "age": 3}"#);
+    req.set_body_limit(8);
+    assert_eq!(req.json::<User>().unwrap_err().status(), &StatusCode::ContentTooLarge);
+  }
+}
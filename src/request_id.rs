@@ -0,0 +1,116 @@
+//! Request IDs, to correlate the logs of the services a request went through.
+//!
+//! ```no_run
+//! use webserver::{App, Handle, request_id::{RequestId, RequestIds}};
+//!
+//! let mut app = App::new();
+//! app.all("*", RequestIds::new().middleware());
+//! app.get("/", Handle::main(|ctx| {
+//!   let id = ctx.req.extensions().get::<RequestId>().unwrap().to_string();
+//!   ctx.res.send_body(id.into_bytes())
+//! }));
+//! app.listen("127.0.0.1:8080").unwrap();
+//! ```
+use std::fmt;
+
+use tracing::{field, Span};
+
+use crate::{random, router::Context, Handle, HookType, Return};
+
+#[cfg(test)]
+mod request_id_test;
+
+/// The field the ID is received and sent in.
+pub const HEADER: &str = "X-Request-Id";
+
+// longer IDs are replaced rather than logged
+const MAX_LENGTH: usize = 128;
+
+/// The ID of a request, in the request extensions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequestId(String);
+
+impl RequestId {
+  /// A random ID, 32 hexadecimal digits.
+  pub fn generate() -> RequestId {
+    RequestId(random::hex(16))
+  }
+
+  /// Accepts an ID of up to 128 letters, digits and `-_.:+/=@`,
+  /// so that it cannot forge a log line or a header field.
+  pub fn parse(value: &str) -> Option<RequestId> {
+    let is_valid = !value.is_empty()
+      && value.len() <= MAX_LENGTH
+      && value.bytes().all(|b| b.is_ascii_alphanumeric() || b"-_.:+/=@".contains(&b));
+
+    is_valid.then(|| RequestId(value.to_string()))
+  }
+
+  pub fn as_str(&self) -> &str {
+    &self.0
+  }
+}
+
+impl fmt::Display for RequestId {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(&self.0)
+  }
+}
+
+/// The request ID middleware, see the [module documentation](self).
+///
+/// The ID is sent back in `X-Request-Id`, error responses included, and
+/// recorded as the `request_id` of the `request` span events are logged in.
+#[derive(Debug, Clone)]
+pub struct RequestIds {
+  trust_incoming: bool
+}
+
+impl Default for RequestIds {
+  fn default() -> Self {
+    RequestIds::new()
+  }
+}
+
+impl RequestIds {
+  /// Valid IDs sent by clients are kept, others are replaced by random ones.
+  pub fn new() -> RequestIds {
+    RequestIds { trust_incoming: true }
+  }
+
+  /// Whether to keep the IDs sent by clients, which should only be
+  /// done behind a proxy that sets or filters them.
+  pub fn with_trust_incoming(mut self, trust_incoming: bool) -> RequestIds {
+    self.trust_incoming = trust_incoming;
+    self
+  }
+
+  pub fn middleware(self) -> Handle {
+    Handle::middleware(move |ctx: &mut Context| {
+      match ctx.hook() {
+        HookType::Before => self.assign(ctx),
+        _ => {
+          // a hook registered before may have failed the request first
+          if !ctx.req.extensions().contains::<RequestId>() {
+            self.assign(ctx);
+          }
+          if let Some(id) = ctx.req.extensions().get::<RequestId>() {
+            ctx.res.headers.set(HEADER, id.to_string());
+          }
+        }
+      }
+      Ok(Return::Next)
+    })
+  }
+
+  fn assign(&self, ctx: &mut Context) {
+    let incoming = match self.trust_incoming {
+      true => ctx.req.headers().get(HEADER).and_then(|value| RequestId::parse(value)),
+      false => None
+    };
+    let id = incoming.unwrap_or_else(RequestId::generate);
+
+    Span::current().record("request_id", field::display(&id));
+    ctx.req.extensions_mut().insert(id);
+  }
+}
@@ -0,0 +1,121 @@
+use std::sync::{Arc, Mutex};
+
+use crate::{app::app_test::{body, get, header}, error::HttpError, protocol::StatusCode, App};
+use super::*;
+
+fn app(ids: RequestIds) -> App {
+  let mut app = App::new();
+  app.all("*", ids.middleware());
+  app.get("/id", Handle::main(|ctx| {
+    let id = ctx.req.extensions().get::<RequestId>().unwrap().to_string();
+    ctx.res.send_body(id.into_bytes())
+  }));
+  app.get("/reset", Handle::main(|ctx| {
+    ctx.res.headers = crate::header::Headers::new();
+    ctx.res.send_body(b"reset".to_vec())
+  }));
+  app.get("/fail", Handle::main(|_| Err(Box::new(HttpError::new(StatusCode::Conflict, "Already exists")))));
+  app
+}
+
+mod assign_test {
+  use super::*;
+
+  #[test]
+  fn generated_test() {
+    let app = app(RequestIds::new());
+    let response = get(&app, "/id", "");
+    let id = header(&response, "X-Request-Id").unwrap();
+    assert_eq!(id.len(), 32);
+    assert!(id.bytes().all(|b| b.is_ascii_hexdigit()));
+    assert_eq!(body(&response), id);
+
+    assert_ne!(header(&get(&app, "/id", ""), "X-Request-Id"), Some(id));
+  }
+
+  #[test]
+  fn incoming_test() {
+    let app = app(RequestIds::new());
+    let response = get(&app, "/id", "x-request-id: 7f3a-01:b\r\n");
+    assert_eq!(header(&response, "X-Request-Id"), Some("7f3a-01:b"));
+    assert_eq!(body(&response), "7f3a-01:b");
+
+    let too_long = "a".repeat(129);
+    for invalid in ["a b", "a\"b", "<script>", too_long.as_str()] {
+      let response = get(&app, "/id", &format!("X-Request-Id: {invalid}\r\n"));
+      assert_eq!(header(&response, "X-Request-Id").unwrap().len(), 32, "for {invalid}");
+    }
+
+    let untrusting = super::app(RequestIds::new().with_trust_incoming(false));
+    let response = get(&untrusting, "/id", "X-Request-Id: abc\r\n");
+    assert_ne!(header(&response, "X-Request-Id"), Some("abc"));
+  }
+
+  #[test]
+  fn parse_test() {
+    assert_eq!(RequestId::parse("Zm9v+/=@_.").map(|id| id.to_string()), Some("Zm9v+/=@_.".to_string()));
+    assert!(RequestId::parse("").is_none());
+    assert!(RequestId::parse("é").is_none());
+    assert!(RequestId::parse(&"a".repeat(128)).is_some());
+  }
+}
+
+mod propagation_test {
+  use super::*;
+
+  #[test]
+  fn response_test() {
+    let app = app(RequestIds::new());
+    let response = get(&app, "/reset", "X-Request-Id: abc\r\n");
+    assert_eq!(header(&response, "X-Request-Id"), Some("abc"));
+
+    let response = get(&app, "/fail", "X-Request-Id: abc\r\n");
+    assert!(response.starts_with("HTTP/1.1 409 Conflict\r\n"));
+    assert_eq!(header(&response, "X-Request-Id"), Some("abc"));
+    assert_eq!(body(&response), "Already exists\nRequest ID: abc");
+  }
+
+  #[test]
+  fn early_failure_test() {
+    let mut app = App::new();
+    app.all("*", Handle::before(|ctx| {
+      ctx.res.headers.set(HEADER, "forged".to_string());
+      Err(Box::new(HttpError::new(StatusCode::Forbidden, "Denied")))
+    }));
+    app.all("*", RequestIds::new().middleware());
+
+    // the ID is assigned once the hooks before it failed
+    let response = get(&app, "/", "X-Request-Id: abc\r\n");
+    assert!(response.starts_with("HTTP/1.1 403 Forbidden\r\n"));
+    assert_eq!(header(&response, "X-Request-Id"), Some("abc"));
+  }
+
+  #[test]
+  fn span_test() {
+    #[derive(Clone, Default)]
+    struct Shared(Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for Shared {
+      fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+      }
+
+      fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+      }
+    }
+
+    let app = app(RequestIds::new());
+    let output = Shared::default();
+    let writer = output.clone();
+    let subscriber = tracing_subscriber::fmt()
+      .with_max_level(tracing::Level::DEBUG)
+      .with_writer(move || writer.clone())
+      .finish();
+    tracing::subscriber::with_default(subscriber, || get(&app, "/fail", "X-Request-Id: abc\r\n"));
+
+    let output = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+    let failed = output.lines().find(|line| line.contains("Request failed")).unwrap();
+    assert!(failed.contains("request_id=abc"), "{failed}");
+  }
+}
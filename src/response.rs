@@ -1,26 +1,79 @@
-use std::{error::Error, io::{BufWriter, Write}, net::TcpStream};
+use std::{error::Error, fmt, io::{self, Read, Write}};
+#[cfg(feature = "secure-cookies")]
+use std::rc::Rc;
 
-use crate::{header::Headers, protocol::{self, StatusCode}, Return};
+use crate::{cookie::{self, Cookie}, header::Headers, protocol::{self, StatusCode}, Return};
+#[cfg(feature = "secure-cookies")]
+use crate::cookie::Jar;
 
+/// The body of a response.
+pub enum Body {
+  Bytes(Vec<u8>),
+  /// Read while the response is written. A stream without a known
+  /// length is sent with the chunked transfer coding.
+  Stream {
+    reader: Box<dyn Read>,
+    length: Option<u64>
+  }
+}
+
+impl fmt::Debug for Body {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Body::Bytes(bytes) => write!(f, "Bytes({} bytes)", bytes.len()),
+      Body::Stream { length, .. } => write!(f, "Stream {{ length: {length:?} }}")
+    }
+  }
+}
+
+// writes `reader` with the chunked transfer coding, see RFC 9112, section 7.1,
+// returning the size of the content
+fn write_chunked(reader: &mut dyn Read, writer: &mut impl Write) -> io::Result<u64> {
+  let mut chunk = [0; 8 * 1024];
+  let mut written = 0;
+  loop {
+    let read = match reader.read(&mut chunk) {
+      Ok(read) => read,
+      Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+      Err(err) => return Err(err)
+    };
+
+    if read == 0 {
+      writer.write_all(b"0\r\n\r\n")?;
+      return Ok(written)
+    }
+
+    write!(writer, "{read:X}\r\n")?;
+    writer.write_all(&chunk[..read])?;
+    writer.write_all(b"\r\n")?;
+    written += read as u64;
+  }
+}
+
+/// The response to a request. Nothing is written until every handler,
+/// `after` hooks included, has run, so the status, headers and body can
+/// still be changed once a handler has sent them.
 #[derive(Debug)]
 pub struct Response {
   pub status: StatusCode,
   pub headers: Headers,
-  stream: Option<BufWriter<TcpStream>>,
+  body: Option<Body>,
+  #[cfg(feature = "secure-cookies")]
+  jar: Option<Rc<Jar>>,
 
-  is_header_written: bool,
-  is_body_written: bool
+  is_sent: bool
 }
 
 impl Response {
-  pub(crate) fn new(stream: Option<BufWriter<TcpStream>>) -> Response {
+  pub(crate) fn new() -> Response {
     Response {
       status: StatusCode::OK,
       headers: Headers::new(),
-      stream: stream,
+      body: None,
+      #[cfg(feature = "secure-cookies")]
+      jar: None,
 
-      is_header_written: false,
-      is_body_written: false
+      is_sent: false
     }
   }
 
@@ -34,15 +87,122 @@ impl Response {
     self
   }
 
+  /// Appends a `Set-Cookie` field, unless the cookie could not be sent as is.
+  pub fn set_cookie(&mut self, cookie: &Cookie) -> Result<&mut Self, cookie::Error> {
+    cookie.validate()?;
+    self.headers.append("Set-Cookie".to_string(), cookie.to_string());
+    Ok(self)
+  }
+
+  /// Makes the client delete a cookie, `cookie` must have
+  /// the `Domain` and `Path` the cookie was set with.
+  pub fn remove_cookie(&mut self, cookie: Cookie) -> Result<&mut Self, cookie::Error> {
+    let mut removal = Cookie::removal(cookie.name());
+    if let Some(domain) = cookie.domain() {
+      removal = removal.with_domain(domain);
+    }
+    if let Some(path) = cookie.path() {
+      removal = removal.with_path(path);
+    }
+    self.set_cookie(&removal)
+  }
+
+  /// Sends a cookie the client cannot tamper with, see [`Jar::sign`].
+  #[cfg(feature = "secure-cookies")]
+  pub fn set_signed_cookie(&mut self, cookie: Cookie) -> Result<&mut Self, cookie::Error> {
+    cookie.validate()?;
+    let jar = self.jar.clone().ok_or(cookie::Error::MissingKey)?;
+    self.set_cookie(&jar.sign(cookie))
+  }
+
+  /// Sends a cookie the client can neither read nor tamper with, see [`Jar::encrypt`].
+  #[cfg(feature = "secure-cookies")]
+  pub fn set_private_cookie(&mut self, cookie: Cookie) -> Result<&mut Self, cookie::Error> {
+    cookie.validate()?;
+    let jar = self.jar.clone().ok_or(cookie::Error::MissingKey)?;
+    self.set_cookie(&jar.encrypt(cookie))
+  }
+
+  #[cfg(feature = "secure-cookies")]
+  pub(crate) fn set_jar(&mut self, jar: Option<Rc<Jar>>) {
+    self.jar = jar
+  }
+
+  /// Sends the response without a body.
   pub fn send_headers(&mut self) -> Result<Return, Box<dyn Error>> {
-    if self.is_header_written {
-      return Ok(Return::End);
+    self.is_sent = true;
+    Ok(Return::End)
+  }
+
+  /// Sends the response with `body`, replacing any body sent before.
+  pub fn send_body(&mut self, body: Vec<u8>) -> Result<Return, Box<dyn Error>> {
+    self.headers.remove("Transfer-Encoding");
+    self.headers.set("Content-Length", body.len().to_string());
+    self.body = Some(Body::Bytes(body));
+    self.is_sent = true;
+    Ok(Return::End)
+  }
+
+  /// Sends the response with a body read from `reader` as it is written,
+  /// replacing any body sent before. `length` is the number of bytes to
+  /// send, when it is not known the body is sent chunked.
+  pub fn send_stream(&mut self, reader: impl Read + 'static, length: Option<u64>) -> Result<Return, Box<dyn Error>> {
+    match length {
+      Some(length) => {
+        self.headers.remove("Transfer-Encoding");
+        self.headers.set("Content-Length", length.to_string())
+      },
+      None => {
+        self.headers.remove("Content-Length");
+        self.headers.set("Transfer-Encoding", "chunked".to_string())
+      }
+    };
+    self.body = Some(Body::Stream { reader: Box::new(reader), length });
+    self.is_sent = true;
+    Ok(Return::End)
+  }
+
+  /// Sends `value` serialized as JSON.
+  #[cfg(feature = "json")]
+  pub fn json<T: serde::Serialize + ?Sized>(&mut self, value: &T) -> Result<Return, Box<dyn Error>> {
+    let body = serde_json::to_vec(value)?;
+    self.content_type("application/json").send_body(body)
+  }
+
+  /// Takes the body out of the response, e.g. for a middleware to
+  /// transform it and send it again. The response stays sent.
+  pub fn take_body(&mut self) -> Option<Body> {
+    self.body.take()
+  }
+
+  /// Whether a handler has sent the response.
+  pub fn is_sent(&self) -> bool {
+    self.is_sent
+  }
+
+  /// The body sent with `send_body`, `None` for a streamed body.
+  pub fn body(&self) -> Option<&[u8]> {
+    match &self.body {
+      Some(Body::Bytes(bytes)) => Some(bytes),
+      _ => None
+    }
+  }
+
+  // returns the size of the body written
+  pub(crate) fn write_to(&mut self, writer: &mut impl Write) -> Result<u64, Box<dyn Error>> {
+    // nothing is written if the status line or a field could inject another one
+    self.status.validate()?;
+    self.headers.validate()?;
+
+    // 1xx, 204 and 304 responses never have a body, see RFC 9110, section 6.4.1
+    let is_bodiless = self.status.is_informational() || matches!(self.status.to_u16(), 204 | 304);
+    if self.body.is_none() && !is_bodiless && !self.headers.contains("Content-Length") {
+      self.headers.set("Content-Length", "0".to_string());
     }
 
-    let writer = self.stream.as_mut().unwrap();
     writer.write_fmt(format_args!("{} {} {}\r\n", 
       protocol::HTTP_PROTOCOL, 
-      self.status.to_string(), 
+      self.status, 
       self.status.reason_phrase())
     )?;
 
@@ -52,24 +212,23 @@ impl Response {
       }
     }
 
-    self.is_header_written = true;
-    Ok(Return::End)
-  }
-
-  pub fn send_body(&mut self, body: Vec<u8>) -> Result<Return, Box<dyn Error>> {
-    if self.is_body_written {
-      return Ok(Return::End);
-    }
-
-    self.headers.set("Content-Length", body.len().to_string());
-    self.send_headers()?;
-
-    let writer = self.stream.as_mut().unwrap();
-    writer.write(b"\r\n")?;
-    writer.write_all(&body)?;
+    writer.write_all(b"\r\n")?;
+    let written = match &mut self.body {
+      Some(Body::Bytes(bytes)) => {
+        writer.write_all(bytes)?;
+        bytes.len() as u64
+      },
+      Some(Body::Stream { reader, length: Some(length) }) => {
+        let written = io::copy(&mut reader.take(*length), writer)?;
+        if written < *length {
+          return Err(Box::new(io::Error::new(io::ErrorKind::UnexpectedEof, "Body is shorter than its length")))
+        }
+        written
+      },
+      Some(Body::Stream { reader, length: None }) => write_chunked(reader, writer)?,
+      None => 0
+    };
     writer.flush()?;
-
-    self.is_body_written = true;
-    Ok(Return::End)
+    Ok(written)
   }
-}
\ No newline at end of file
+}
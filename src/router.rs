@@ -1,9 +1,8 @@
 mod tree;
 mod handler;
 mod context;
-mod params;
 
-use std::{error::Error, io::BufWriter, net::TcpStream, rc::Rc};
+use std::{error::Error, rc::Rc};
 
 use tree::Tree;
 pub use context::Context;
@@ -31,72 +30,91 @@ impl Router {
     self
   }
 
-  pub fn dispatch(&self, req: Request, stream: TcpStream) -> (Response, Option<Box<dyn Error>>) {
-    let mut response = Response::new(Some(BufWriter::new(stream)));
-    let location = req.location();
-    let handlers = self.tree.handlers(&location.0, &location.1);
+  /// Runs the handlers of `req`, returning the route of the handler that
+  /// ended the request, if any. An error raised by a handler is turned
+  /// into a response by `fail`, and the after hooks still run on it.
+  ///
+  /// The after hooks of middlewares run last, in the reverse order of
+  /// their registration, so that the first registered wraps the others.
+  pub fn dispatch(&self, req: &mut Request, response: &mut Response, fail: impl Fn(&Request, &mut Response, Box<dyn Error>)) -> Option<String> {
+    let method = req.location().0.clone();
+    let path = req.path().to_string();
+    let handlers = self.tree.handlers(&method, &path);
     let (post_handlers, pre_handlers): (Vec<_>, Vec<_>) = handlers
       .into_iter()
       .partition(|h| h.hook_type == HookType::After);
 
-    let error = 'error: {
-      let mut ctx: Context = Context{
-        req: &req,
-        res: &mut response,
-        // handler: &handler.clone()
-      };
-
-      for handler in pre_handlers {
-        let function = handler.function.as_ref();
-        match handler.hook_type {
-          HookType::Before => {
-            match function(&mut ctx) {
-              Ok(return_type) => match return_type {
-                Return::Next => continue,
-                Return::End => {
-                  break
-                },
-              },
-              Err(err) => break 'error Some(err)
-            }
-          },
-          HookType::Main => {
-            match function(&mut ctx) {
-              Ok(return_type) => match return_type {
-                  Return::Next => break,
-                  Return::End => break,
-              },
-              Err(err) => break 'error Some(err)
-            }
-          },
-          HookType::After => panic!("Should not dispatch any post handlers here!"),
-        }
-      }
-      
-      for handler in post_handlers {
-        let function = handler.function.as_ref();
-        match handler.hook_type {
-          HookType::Before => panic!("Should not dispatch any pre handlers (before) here!"),
-          HookType::Main => panic!("Should not dispatch any pre handlers (main) here!"),
-          HookType::After => {
-            match function(&mut ctx) {
-              Ok(return_type) => match return_type {
-                Return::Next => continue,
-                Return::End => break
+    // the two hooks of a middleware share its function
+    let (mut middlewares, post_handlers): (Vec<_>, Vec<_>) = post_handlers
+      .into_iter()
+      .partition(|h| pre_handlers.iter().any(|pre| Rc::ptr_eq(&pre.function, &h.function)));
+    middlewares.reverse();
+
+    let mut route = None;
+    let mut ctx = Context::new(req, response, HookType::Before);
+
+    for handler in pre_handlers {
+      let function = handler.function.as_ref();
+      ctx.hook = handler.hook_type.clone();
+      ctx.wildcard = wildcard(&handler.path, &path);
+      match handler.hook_type {
+        HookType::Before => {
+          match function(&mut ctx) {
+            Ok(return_type) => match return_type {
+              Return::Next => continue,
+              Return::End => {
+                route = Some(handler.path.clone());
+                break
               },
-              Err(err) => break 'error Some(err)
+            },
+            Err(err) => {
+              route = Some(handler.path.clone());
+              fail(ctx.req, ctx.res, err);
+              break
             }
           }
-        }
+        },
+        HookType::Main => {
+          route = Some(handler.path.clone());
+          if let Err(err) = function(&mut ctx) {
+            fail(ctx.req, ctx.res, err);
+          }
+          break
+        },
+        HookType::After => panic!("Should not dispatch any post handlers here!"),
       }
+    }
 
-      None
-    };
+    for handler in post_handlers.into_iter().chain(middlewares) {
+      let function = handler.function.as_ref();
+      ctx.hook = handler.hook_type.clone();
+      ctx.wildcard = wildcard(&handler.path, &path);
+      match function(&mut ctx) {
+        Ok(return_type) => match return_type {
+          Return::Next => continue,
+          Return::End => break
+        },
+        // the remaining hooks still run on the error response
+        Err(err) => fail(ctx.req, ctx.res, err)
+      }
+    }
 
-    (response, error)
+    route
   }
 }
 
+// the path segments matched by the tail wildcard of `route`
+fn wildcard(route: &str, path: &str) -> Option<String> {
+  let prefix = route.strip_suffix("**")?;
+  let skipped = prefix.split('/').filter(|s| !s.is_empty()).count();
+  let tail: Vec<_> = path
+    .split('/')
+    .filter(|s| !s.is_empty())
+    .skip(skipped)
+    .collect();
+  Some(tail.join("/"))
+}
+
 #[derive(Debug, PartialEq, PartialOrd, Eq, Ord)]
 pub enum HandleType {
   Main,
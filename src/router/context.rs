@@ -1,9 +1,29 @@
 use crate::{request::Request, response::Response};
 
-use super::Handler;
+use super::HookType;
 
 pub struct Context<'a> {
-  pub req: &'a Request,
+  pub req: &'a mut Request,
   pub res: &'a mut Response,
-  // pub handler: &'a Handler
-}
\ No newline at end of file
+  pub(super) hook: HookType,
+  pub(super) wildcard: Option<String>
+}
+
+impl<'a> Context<'a> {
+  pub(crate) fn new(req: &'a mut Request, res: &'a mut Response, hook: HookType) -> Context<'a> {
+    Context { req, res, hook, wildcard: None }
+  }
+
+  /// When the running handler is called. A middleware is
+  /// called twice, as a `Before` and as an `After` hook.
+  pub fn hook(&self) -> &HookType {
+    &self.hook
+  }
+
+  /// The path segments matched by the trailing `**` of the running
+  /// handler route, e.g. `css/site.css` for `/static/css/site.css`
+  /// on `/static/**`, or empty for `/static`. They are not percent-decoded.
+  pub fn wildcard(&self) -> Option<&str> {
+    self.wildcard.as_deref()
+  }
+}
@@ -3,7 +3,6 @@ use std::error::Error;
 use std::rc::Rc;
 
 use crate::request::Method;
-use crate::response::Response;
 
 use super::Context;
 
@@ -32,6 +31,7 @@ impl Handler {
 }
 
 pub type SharedHandler = Rc<Handler>;
+#[allow(dead_code)]
 pub type MutSharedHandler = Rc<RefCell<Handler>>;
 
 #[derive(Debug, PartialEq, PartialOrd, Eq, Ord, Clone)]
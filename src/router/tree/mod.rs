@@ -1,12 +1,12 @@
 use std::{cell::RefCell, cmp::Ordering, collections::HashMap, rc::Rc};
-use crate::{dbgln, request::Method, router::params, should_debug};
-use super::{handler::{Handler, SharedHandler}, params::{params_from, SharedParams}};
+use tracing::trace;
+
+use crate::request::Method;
+use super::handler::{Handler, SharedHandler};
 
 mod node;
 use node::*;
 
-should_debug!(0);
-
 #[cfg(test)]
 mod tree_test;
 
@@ -86,6 +86,10 @@ impl Tree {
       let node = RefCell::borrow(&self.root);
       let mut h = RefCell::borrow_mut(&handlers);
       h.extend_from_slice(&node.handlers);
+      // a tail wildcard matches no fragment too
+      if let Some(wildcard) = node.next.get("**") {
+        h.extend_from_slice(&RefCell::borrow(wildcard).handlers);
+      }
     } else {
       let root = RefCell::borrow(&self.root);
       Tree::traverse(
@@ -93,10 +97,10 @@ impl Tree {
         &root.next,
         1, // we start at 1 to skip root...
         &|node, fragments, i| {
-          dbgln!("on_match hook called with index: {}", i);
+          trace!(index = i, "Fragment matched");
           if fragments.len() == i {
-            let node = RefCell::borrow(&node);
-            dbgln!("Node at last fragment ({}): {:#?}", fragments[i - 1], node);
+            let node = RefCell::borrow(node);
+            trace!(fragment = fragments[i - 1], handlers = node.handlers.len(), "Path matched");
             let mut h = RefCell::borrow_mut(&handlers);
             let h2 = node.handlers
                 .iter()
@@ -109,29 +113,22 @@ impl Tree {
       )
     }
 
-    dbgln!("Handlers: {:#?}", handlers);
+    trace!(path, handlers = handlers.borrow().len(), "Handlers found");
 
     let mut h = handlers.take();
     h.sort_by(|a, b| {
-      Tree::compare_handler(&a, &b)
+      Tree::compare_handler(a, b)
     });
     h
       .iter()
       .map(|h| h.1.clone())
       .filter(|h| {
-        h.method == *method || *method == Method::All
+        // handlers registered for all methods match any request
+        h.method == Method::All || h.method == *method
       })
       .collect()
   }
 
-  fn build_parameters(param: &HashMap<&str, &str>) -> params::Params {
-    params_from(param
-      .iter()
-      .map(|(k, v)| (k.to_string(), v.to_string()))
-      .collect::<HashMap<String, String>>()
-    )
-  }
-
   // will always return "" as the first fragment
   // whatever the input is
   fn split_path(path: &str) -> Vec<&str> {
@@ -149,7 +146,7 @@ impl Tree {
   }
 
   fn compare_handler(a: &PrioritizedHandler, b: &PrioritizedHandler) -> Ordering {
-    if &a.1.hook_type == &b.1.hook_type {
+    if a.1.hook_type == b.1.hook_type {
       a.0.cmp(&b.0)
     }
     else {
@@ -170,7 +167,7 @@ impl Tree {
 
       let parent = parent.unwrap();
       path.insert_str(0, &ref_node.fragment);
-      path.insert_str(0, "/");
+      path.insert(0, '/');
       node = parent.clone();
     }
 
@@ -183,25 +180,33 @@ impl Tree {
     i: usize,
     on_match: &impl Fn(&Rc<RefCell<Node>>, &[&str], usize),
   ) {
-    dbgln!("Start traversing with index: {}", i);
+    trace!(index = i, "Traversing");
     let cursor = {
       if let Some(i) = fragments.get(i) {
         *i
       } else {
+        // a tail wildcard matches no fragment too
+        if let Some(node) = next.get("**") {
+          on_match(node, fragments, i);
+        }
         return
       }
     };
 
-    dbgln!("Traversing <{}>, of {:?}", cursor, fragments.join("/"));
-    for (_, node) in next {
-      let ref_node = RefCell::borrow(&node);
+    trace!(cursor, path = fragments.join("/"), "Matching cursor");
+    for node in next.values() {
+      let ref_node = RefCell::borrow(node);
       // if current node is a normal fragment and cursor is not matched
       // then return early
-      dbgln!("Testing cursor <{}> against fragment <{}> for path <{}>", 
-        cursor, ref_node.fragment, Tree::rebuild_path_to_root(node.clone()));
+      trace!(cursor, fragment = ref_node.fragment, route = Tree::rebuild_path_to_root(node.clone()), "Testing fragment");
+      // a tail wildcard matches every remaining fragment
+      if ref_node.fragment == "**" {
+        on_match(node, fragments, fragments.len());
+        continue
+      }
+
       if ref_node.parameter.is_none() && ref_node.fragment != "*" && cursor != ref_node.fragment {
-        dbgln!("Cursor <{}> does not match fragment <{}> for path <{}>", 
-          cursor, ref_node.fragment, Tree::rebuild_path_to_root(node.clone()));
+        trace!(cursor, fragment = ref_node.fragment, "Fragment does not match");
         continue
       }
 
@@ -209,20 +214,17 @@ impl Tree {
 
       let derived_i = {
         // handler for special fragment (e.g. ":id", ":id{regex}")
-        if let Some(parameter) = &ref_node.parameter {
-          dbgln!("Cursor <{}> match parameterized fragment <{}> for path <{}>", 
-            cursor, ref_node.fragment, Tree::rebuild_path_to_root(node.clone()));
+        if let Some(_parameter) = &ref_node.parameter {
+          trace!(cursor, fragment = ref_node.fragment, "Parameterized fragment matches");
           i + 1
         // handler for normal url fragment
         } else {
-          dbgln!("Cursor <{}> match normal fragment <{}> for path <{}>", 
-            cursor, ref_node.fragment, Tree::rebuild_path_to_root(node.clone()));
+          trace!(cursor, fragment = ref_node.fragment, "Fragment matches");
           i + 1
         }
       };
 
-      dbgln!("Calling on_match hook since cursor matched");
-      on_match(&node, fragments, derived_i);
+      on_match(node, fragments, derived_i);
 
       Tree::traverse(
         fragments, 
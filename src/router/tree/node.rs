@@ -39,9 +39,9 @@ impl Node {
     }
 
     if let Some((key,value)) = fragment.split_once("{") {
-      return Some((key[1..].to_string(), value[0..value.len() - 1].to_string()))
+      Some((key[1..].to_string(), value[0..value.len() - 1].to_string()))
     } else {
-      return Some((fragment[1..].to_string(), String::new()))
+      Some((fragment[1..].to_string(), String::new()))
     }
   }
 }
@@ -1,9 +1,7 @@
 use handler::{Handler, HookType, Return};
 use tree::Node;
-use crate::{protocol::StatusCode, request::{Method, Request}, response::Response, router::*, should_debug};
-use std::{borrow::{Borrow, BorrowMut}, cell::RefCell, collections::HashMap, error::Error, rc::Rc};
-
-should_debug!(no);
+use crate::{protocol::StatusCode, request::{Method, Request}, response::Response, router::*};
+use std::{cell::RefCell, error::Error, rc::Rc};
 
 macro_rules! node {
     ($v:expr) => {
@@ -33,6 +31,7 @@ fn __handler(status: u16, path: &str) -> Handler {
   }
 }
 
+#[allow(clippy::len_zero)]
 fn test_node_exist(tree: &Tree, path: &str) -> Rc<RefCell<Node>> {
   let mut parent = tree.root.clone();
   for (i, fragment) in Tree::split_path(path).into_iter().enumerate() {
@@ -61,13 +60,11 @@ fn test_node_exist(tree: &Tree, path: &str) -> Rc<RefCell<Node>> {
 }
 
 fn test_handler(h: &Rc<Handler>, status: u16) {
-  let mut res = Response::new(None);
-  let mut ctx = Context {
-    req: &Request::new(None), 
-    res: &mut res,
-  };
+  let mut req = Request::new(None);
+  let mut res = Response::new();
+  let mut ctx = Context::new(&mut req, &mut res, h.hook_type.clone());
 
-  h.function.as_ref()(&mut ctx);
+  let _ = h.function.as_ref()(&mut ctx);
   assert_eq!(res.status, StatusCode::Other(status, "Status".to_string()));
 }
 
@@ -94,6 +91,7 @@ mod register_test {
   }
 
   #[test]
+  #[allow(clippy::len_zero, clippy::needless_borrow)]
   fn slashed_prefix_test() {
     let mut tree = Tree::new();
     let path = "/a/b/c/d";
@@ -112,6 +110,7 @@ mod register_test {
   }
 
   #[test]
+  #[allow(clippy::len_zero, clippy::needless_borrow)]
   fn unslashed_prefix_test() {
     let mut tree = Tree::new();
     let path = "a/b/c/d";
@@ -130,6 +129,7 @@ mod register_test {
   }
 
   #[test]
+  #[allow(clippy::len_zero, clippy::needless_borrow)]
   fn catch_all_test() {
     let mut tree = Tree::new();
     let path_star = "*";
@@ -150,6 +150,7 @@ mod register_test {
   }
   
   #[test]
+  #[allow(clippy::needless_borrow)]
   fn normal_parameter_fragment_test() {
     let mut tree = Tree::new();
     let path_regular = "a/b/c/d";
@@ -181,6 +182,7 @@ mod register_test {
   }
   
   #[test]
+  #[allow(clippy::needless_borrow)]
   fn argumentized_parameter_fragment_test() {
     let mut tree = Tree::new();
     let path_regular = "a/b/c/d";
@@ -212,6 +214,7 @@ mod register_test {
   }
 
   #[test]
+  #[allow(clippy::needless_borrow)]
   fn wildcard_fragment_test() {
     let mut tree = Tree::new();
     let path_wildcard = "a/b/*/d";
@@ -245,6 +248,7 @@ mod register_test {
   }
   
   #[test]
+  #[allow(clippy::needless_borrow, clippy::unnecessary_get_then_check)]
   fn misconfigured_parameter_fragment_test() {
     let mut tree = Tree::new();
     let path_boundary_test = "a/b/:xyz{this_{{{sho}}{}{}uld_be_{{{regex!}/d";
@@ -317,6 +321,7 @@ mod register_test {
   }
 
   #[test]
+  #[allow(clippy::len_zero, clippy::needless_borrow)]
   fn deep_tree_integrity_test() {
     let mut tree = Tree::new();
     let path_abcd = "a/b/c/:d";
@@ -376,12 +381,10 @@ mod register_test {
 }
 
 mod tree_routing_test {
-  use std::borrow::Borrow;
-
   use super::*;
-  use crate::{dbgln, router::*};
 
   #[test]
+  #[allow(clippy::needless_borrow)]
   fn one_path_routing_test() {
     let mut tree = Tree::new();
     let path = "a/b/c/d";
@@ -391,7 +394,6 @@ mod tree_routing_test {
     }
 
     let handlers = tree.handlers(&Method::Get, &path);
-    dbgln!("handlers: {:#?}", handlers);
     assert!(handlers.len() == 10);
 
     for i in 0..10 {
@@ -414,7 +416,6 @@ mod tree_routing_test {
     tree.register(__handler(80, "/*/b/c/*"));
     
     let handlers = tree.handlers(&Method::Get, "a/b/c/d");
-    dbgln!("handlers: {:#?}", handlers);
     assert!(handlers.len() == 8);
 
     for i in 0..8 {
@@ -423,6 +424,31 @@ mod tree_routing_test {
     }
   }
 
+  #[test]
+  fn tail_wildcard_routing_test() {
+    let mut tree = Tree::new();
+
+    tree.register(__handler(10, "/static/**"));
+    tree.register(__handler(20, "/static/*/**"));
+    tree.register(__handler(30, "/**"));
+    tree.register(__handler(40, "/other/**"));
+
+    let paths = |path| tree
+      .handlers(&Method::Get, path)
+      .iter()
+      .map(|h| h.path.clone())
+      .collect::<Vec<_>>();
+
+    assert_eq!(paths("/static/a/b/c.css"), vec!["/static/**", "/static/*/**", "/**"]);
+    assert_eq!(paths("/static/a.css"), vec!["/static/**", "/static/*/**", "/**"]);
+    // with nothing after the prefix, the tail wildcard matches no fragment
+    assert_eq!(paths("/static"), vec!["/static/**", "/**"]);
+    assert_eq!(paths("/static/"), vec!["/static/**", "/**"]);
+    assert_eq!(paths("/"), vec!["/**"]);
+    assert_eq!(paths("/other"), vec!["/**", "/other/**"]);
+  }
+
+  #[allow(unused_macros)]
   macro_rules! strmap {
     ($( $key:expr => $value:expr ),* $(,)?) => {{
         let mut map: HashMap<_, _> = HashMap::new();
@@ -447,7 +473,6 @@ mod tree_routing_test {
     tree.register(__handler(80, "/a/b/c/:id{{{{{{{{{{{{{{{{{{{XXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXXX]}"));
     
     let handlers = tree.handlers(&Method::Get, "a/b/c/d");
-    dbgln!("handlers: {:#?}", handlers);
     assert!(handlers.len() == 8);
 
     for i in 0..8 {
@@ -526,6 +551,7 @@ mod tree_routing_test {
   }
 
   #[test]
+  #[allow(unused_mut, unused_variables)]
   fn hook_priority_test() {
     let mut tree = Tree::new();
 
@@ -0,0 +1,303 @@
+//! Server-side sessions, identified by a cookie.
+//!
+//! The [`Sessions`] middleware loads the session before the handlers run
+//! and saves it once they are done. Handlers reach it through
+//! [`Session::from_request`]. A session is only created, and its cookie
+//! only sent, once something is stored in it.
+//!
+//! ```no_run
+//! use webserver::{App, Handle, session::{MemoryStore, Session, Sessions}};
+//!
+//! let mut app = App::new();
+//! app.all("*", Sessions::new(MemoryStore::new()).middleware());
+//! app.get("/visit", Handle::main(|ctx| {
+//!   let session = Session::from_request(ctx.req).unwrap();
+//!   let visits: u32 = session.get("visits").and_then(|v| v.parse().ok()).unwrap_or(0);
+//!   session.insert("visits", (visits + 1).to_string());
+//!   ctx.res.send_body(format!("{} visits", visits + 1).into())
+//! }));
+//! app.listen("127.0.0.1:8080").unwrap();
+//! ```
+use std::{collections::HashMap, error::Error, rc::Rc, time::{Duration, SystemTime, UNIX_EPOCH}};
+
+use crate::{cookie::{Cookie, SameSite}, random, request::Request, router::Context, Handle, HookType, Return};
+
+mod file;
+mod memory;
+pub use file::FileStore;
+pub use memory::MemoryStore;
+
+#[cfg(test)]
+mod session_test;
+
+// 256 bits of entropy, hex encoded
+const ID_LENGTH: usize = 32;
+
+/// A persisted session.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record {
+  pub values: HashMap<String, String>,
+  pub created: SystemTime,
+  pub accessed: SystemTime,
+  /// When the session expires unless it is accessed again,
+  /// the store may forget it afterwards.
+  pub expires: Option<SystemTime>
+}
+
+impl Record {
+  pub fn is_expired(&self, now: SystemTime) -> bool {
+    self.expires.is_some_and(|expires| expires <= now)
+  }
+}
+
+/// Where sessions are persisted between requests.
+pub trait SessionStore {
+  fn load(&self, id: &str) -> Result<Option<Record>, Box<dyn Error>>;
+
+  fn save(&self, id: &str, record: &Record) -> Result<(), Box<dyn Error>>;
+
+  fn remove(&self, id: &str) -> Result<(), Box<dyn Error>>;
+}
+
+/// Lets the application keep a handle on the store given to [`Sessions`].
+impl<S: SessionStore> SessionStore for Rc<S> {
+  fn load(&self, id: &str) -> Result<Option<Record>, Box<dyn Error>> {
+    self.as_ref().load(id)
+  }
+
+  fn save(&self, id: &str, record: &Record) -> Result<(), Box<dyn Error>> {
+    self.as_ref().save(id, record)
+  }
+
+  fn remove(&self, id: &str) -> Result<(), Box<dyn Error>> {
+    self.as_ref().remove(id)
+  }
+}
+
+// timestamps are kept with a one second precision, as stores persist them
+fn now() -> SystemTime {
+  let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+  UNIX_EPOCH + Duration::from_secs(seconds)
+}
+
+fn is_valid_id(id: &str) -> bool {
+  id.len() == ID_LENGTH * 2 && id.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b))
+}
+
+/// The session of the current request.
+#[derive(Debug)]
+pub struct Session {
+  id: Option<String>,
+  values: HashMap<String, String>,
+  created: SystemTime,
+  is_changed: bool,
+  is_rotated: bool,
+  is_destroyed: bool
+}
+
+impl Session {
+  fn new(id: Option<String>, values: HashMap<String, String>, created: SystemTime) -> Session {
+    Session {
+      id,
+      values,
+      created,
+      is_changed: false,
+      is_rotated: false,
+      is_destroyed: false
+    }
+  }
+
+  /// The session loaded by the [`Sessions`] middleware.
+  pub fn from_request(req: &mut Request) -> Option<&mut Session> {
+    req.extensions_mut().get_mut()
+  }
+
+  /// The session ID, `None` until the session is first saved.
+  pub fn id(&self) -> Option<&str> {
+    self.id.as_deref()
+  }
+
+  pub fn is_new(&self) -> bool {
+    self.id.is_none()
+  }
+
+  pub fn get(&self, key: &str) -> Option<&str> {
+    self.values.get(key).map(String::as_str)
+  }
+
+  pub fn insert(&mut self, key: &str, value: impl Into<String>) -> Option<String> {
+    self.is_changed = true;
+    self.values.insert(key.to_string(), value.into())
+  }
+
+  pub fn remove(&mut self, key: &str) -> Option<String> {
+    self.is_changed = true;
+    self.values.remove(key)
+  }
+
+  pub fn clear(&mut self) {
+    self.is_changed = true;
+    self.values.clear();
+  }
+
+  /// Moves the session to a new ID when it is saved. Rotate it whenever
+  /// privileges change, e.g. on login, to prevent session fixation.
+  pub fn rotate(&mut self) {
+    self.is_rotated = true;
+  }
+
+  /// Removes the session from the store and the client.
+  pub fn destroy(&mut self) {
+    self.is_destroyed = true;
+  }
+}
+
+/// The session middleware, see the [module documentation](self).
+pub struct Sessions {
+  store: Rc<dyn SessionStore>,
+  cookie: Cookie,
+  idle_timeout: Option<Duration>,
+  absolute_timeout: Option<Duration>
+}
+
+impl Sessions {
+  /// Sessions expire after 30 minutes of inactivity and after 24 hours
+  /// whatever the activity. The cookie is called `sid`, it is `HttpOnly`
+  /// and `SameSite=Lax` for the whole site.
+  pub fn new(store: impl SessionStore + 'static) -> Sessions {
+    Sessions {
+      store: Rc::new(store),
+      cookie: Cookie::new("sid", "")
+        .with_path("/")
+        .with_http_only(true)
+        .with_same_site(SameSite::Lax),
+      idle_timeout: Some(Duration::from_secs(30 * 60)),
+      absolute_timeout: Some(Duration::from_secs(24 * 60 * 60))
+    }
+  }
+
+  /// The name and attributes of the session cookie, its value is ignored.
+  pub fn with_cookie(mut self, cookie: Cookie) -> Sessions {
+    self.cookie = cookie;
+    self
+  }
+
+  /// How long a session lives without being accessed, `None` to never expire.
+  pub fn with_idle_timeout(mut self, timeout: Option<Duration>) -> Sessions {
+    self.idle_timeout = timeout;
+    self
+  }
+
+  /// How long a session lives after its creation, `None` to never expire.
+  pub fn with_absolute_timeout(mut self, timeout: Option<Duration>) -> Sessions {
+    self.absolute_timeout = timeout;
+    self
+  }
+
+  pub fn middleware(self) -> Handle {
+    Handle::middleware(move |ctx: &mut Context| {
+      match ctx.hook() {
+        HookType::Before => self.load(ctx.req)?,
+        _ => self.save(ctx)?
+      }
+      Ok(Return::Next)
+    })
+  }
+
+  fn expires(&self, created: SystemTime, accessed: SystemTime) -> Option<SystemTime> {
+    let idle = self.idle_timeout.map(|timeout| accessed + timeout);
+    let absolute = self.absolute_timeout.map(|timeout| created + timeout);
+    match (idle, absolute) {
+      (Some(idle), Some(absolute)) => Some(idle.min(absolute)),
+      (idle, absolute) => idle.or(absolute)
+    }
+  }
+
+  // the expiry is checked again in case the timeouts changed
+  fn is_expired(&self, record: &Record, now: SystemTime) -> bool {
+    record.is_expired(now) || self
+      .expires(record.created, record.accessed)
+      .is_some_and(|expires| expires <= now)
+  }
+
+  fn load(&self, req: &mut Request) -> Result<(), Box<dyn Error>> {
+    let now = now();
+    let id = req
+      .cookies()
+      .get(self.cookie.name())
+      .filter(|id| is_valid_id(id))
+      .map(String::from);
+
+    let record = match &id {
+      Some(id) => self.store.load(id)?,
+      None => None
+    };
+
+    let session = match (id, record) {
+      (Some(id), Some(record)) if self.is_expired(&record, now) => {
+        self.store.remove(&id)?;
+        Session::new(None, HashMap::new(), now)
+      },
+      (Some(id), Some(record)) => Session::new(Some(id), record.values, record.created),
+      _ => Session::new(None, HashMap::new(), now)
+    };
+
+    req.extensions_mut().insert(session);
+    Ok(())
+  }
+
+  fn save(&self, ctx: &mut Context) -> Result<(), Box<dyn Error>> {
+    let Some(session) = ctx.req.extensions_mut().remove::<Session>() else {
+      return Ok(())
+    };
+
+    if session.is_destroyed {
+      if let Some(id) = &session.id {
+        self.store.remove(id)?;
+        ctx.res.remove_cookie(self.cookie.clone())?;
+      }
+      return Ok(())
+    }
+
+    // anonymous traffic is not persisted
+    if session.is_new() && session.values.is_empty() {
+      return Ok(())
+    }
+
+    // without idle timeout, unchanged sessions do not need to be touched
+    let is_new_id = session.is_new() || session.is_rotated;
+    if !is_new_id && !session.is_changed && self.idle_timeout.is_none() {
+      return Ok(())
+    }
+
+    let id = match &session.id {
+      Some(id) if !session.is_rotated => id.clone(),
+      previous => {
+        if let Some(previous) = previous {
+          self.store.remove(previous)?;
+        }
+        random::hex(ID_LENGTH)
+      }
+    };
+
+    let now = now();
+    let record = Record {
+      expires: self.expires(session.created, now),
+      values: session.values,
+      created: session.created,
+      accessed: now
+    };
+    self.store.save(&id, &record)?;
+
+    if is_new_id {
+      // the cookie lives as long as the session can
+      let mut cookie = self.cookie.clone().with_value(&id);
+      if let Some(timeout) = self.absolute_timeout {
+        let remaining = (record.created + timeout).duration_since(now).unwrap_or_default();
+        cookie = cookie.with_max_age(remaining.as_secs());
+      }
+      ctx.res.set_cookie(&cookie)?;
+    }
+    Ok(())
+  }
+}
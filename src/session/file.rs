@@ -0,0 +1,146 @@
+use std::{collections::HashMap, error::Error, fs, io, path::PathBuf, time::{Duration, SystemTime, UNIX_EPOCH}};
+
+use super::{is_valid_id, Record, SessionStore};
+
+/// Keeps every session in its own file of a directory.
+///
+/// A record is written as a `created accessed expires` line, in seconds
+/// since the epoch (`-` when it never expires), followed by a `key=value`
+/// line per value, both percent-encoded.
+#[derive(Debug)]
+pub struct FileStore {
+  directory: PathBuf
+}
+
+fn encode(value: &str) -> String {
+  let mut encoded = String::with_capacity(value.len());
+  for char in value.chars() {
+    match char {
+      '%' | '=' | '\n' | '\r' => encoded.push_str(&format!("%{:02X}", char as u8)),
+      char => encoded.push(char)
+    }
+  }
+  encoded
+}
+
+fn decode(value: &str) -> Option<String> {
+  let mut decoded = Vec::with_capacity(value.len());
+  let mut bytes = value.bytes();
+  while let Some(byte) = bytes.next() {
+    if byte == b'%' {
+      let hex = [bytes.next()?, bytes.next()?];
+      decoded.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+    } else {
+      decoded.push(byte);
+    }
+  }
+  String::from_utf8(decoded).ok()
+}
+
+fn seconds(time: SystemTime) -> u64 {
+  time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs())
+}
+
+fn time(seconds: &str) -> Option<SystemTime> {
+  Some(UNIX_EPOCH + Duration::from_secs(seconds.parse().ok()?))
+}
+
+fn serialize(record: &Record) -> String {
+  let expires = record.expires.map_or("-".to_string(), |expires| seconds(expires).to_string());
+  let mut serialized = format!("{} {} {expires}\n", seconds(record.created), seconds(record.accessed));
+  for (key, value) in record.values.iter() {
+    serialized.push_str(&format!("{}={}\n", encode(key), encode(value)));
+  }
+  serialized
+}
+
+fn deserialize(serialized: &str) -> Option<Record> {
+  let mut lines = serialized.lines();
+  let mut times = lines.next()?.split(' ');
+  let created = time(times.next()?)?;
+  let accessed = time(times.next()?)?;
+  let expires = match times.next()? {
+    "-" => None,
+    expires => Some(time(expires)?)
+  };
+
+  let mut values = HashMap::new();
+  for line in lines {
+    let (key, value) = line.split_once('=')?;
+    values.insert(decode(key)?, decode(value)?);
+  }
+
+  Some(Record { values, created, accessed, expires })
+}
+
+impl FileStore {
+  /// Stores sessions in `directory`, which is created if needed.
+  pub fn new(directory: impl Into<PathBuf>) -> io::Result<FileStore> {
+    let directory = directory.into();
+    fs::create_dir_all(&directory)?;
+    Ok(FileStore { directory })
+  }
+
+  // only IDs the middleware generates are turned into paths
+  fn path(&self, id: &str) -> Option<PathBuf> {
+    is_valid_id(id).then(|| self.directory.join(id))
+  }
+
+  /// Removes the expired sessions, which are otherwise only
+  /// removed when a client comes back with them.
+  pub fn purge(&self) -> io::Result<()> {
+    let now = SystemTime::now();
+    for entry in fs::read_dir(&self.directory)? {
+      let path = entry?.path();
+      let is_session = path.file_name().and_then(|n| n.to_str()).is_some_and(is_valid_id);
+      if !is_session {
+        continue
+      }
+
+      let is_expired = fs::read_to_string(&path)
+        .ok()
+        .and_then(|serialized| deserialize(&serialized))
+        .is_none_or(|record| record.is_expired(now));
+
+      if is_expired {
+        fs::remove_file(path)?;
+      }
+    }
+    Ok(())
+  }
+}
+
+impl SessionStore for FileStore {
+  fn load(&self, id: &str) -> Result<Option<Record>, Box<dyn Error>> {
+    let Some(path) = self.path(id) else {
+      return Ok(None)
+    };
+
+    match fs::read_to_string(path) {
+      Ok(serialized) => Ok(deserialize(&serialized)),
+      Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+      Err(err) => Err(Box::new(err))
+    }
+  }
+
+  fn save(&self, id: &str, record: &Record) -> Result<(), Box<dyn Error>> {
+    let path = self.path(id).ok_or("Invalid session ID")?;
+
+    // a reader never sees a partially written record
+    let temporary = path.with_extension("tmp");
+    fs::write(&temporary, serialize(record))?;
+    fs::rename(temporary, path)?;
+    Ok(())
+  }
+
+  fn remove(&self, id: &str) -> Result<(), Box<dyn Error>> {
+    let Some(path) = self.path(id) else {
+      return Ok(())
+    };
+
+    match fs::remove_file(path) {
+      Err(err) if err.kind() != io::ErrorKind::NotFound => Err(Box::new(err)),
+      _ => Ok(())
+    }
+  }
+}
@@ -0,0 +1,47 @@
+use std::{cell::RefCell, collections::HashMap, error::Error, time::SystemTime};
+
+use super::{Record, SessionStore};
+
+/// Keeps sessions in memory, they are lost on restart.
+/// Expired sessions are purged whenever a new session is saved.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+  records: RefCell<HashMap<String, Record>>
+}
+
+impl MemoryStore {
+  pub fn new() -> MemoryStore {
+    MemoryStore::default()
+  }
+
+  /// The number of sessions stored, expired ones included.
+  pub fn len(&self) -> usize {
+    self.records.borrow().len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.records.borrow().is_empty()
+  }
+}
+
+impl SessionStore for MemoryStore {
+  fn load(&self, id: &str) -> Result<Option<Record>, Box<dyn Error>> {
+    Ok(self.records.borrow().get(id).cloned())
+  }
+
+  fn save(&self, id: &str, record: &Record) -> Result<(), Box<dyn Error>> {
+    let mut records = self.records.borrow_mut();
+    if !records.contains_key(id) {
+      let now = SystemTime::now();
+      records.retain(|_, record| !record.is_expired(now));
+    }
+
+    records.insert(id.to_string(), record.clone());
+    Ok(())
+  }
+
+  fn remove(&self, id: &str) -> Result<(), Box<dyn Error>> {
+    self.records.borrow_mut().remove(id);
+    Ok(())
+  }
+}
@@ -0,0 +1,249 @@
+use std::io::Cursor;
+
+use crate::App;
+use super::*;
+
+fn app(sessions: Sessions) -> App {
+  let mut app = App::new();
+  app.all("*", sessions.middleware());
+  app.get("/anonymous", Handle::main(|ctx| ctx.res.send_headers()));
+  app.get("/visit", Handle::main(|ctx| {
+    let session = Session::from_request(ctx.req).unwrap();
+    let visits: u32 = session.get("visits").and_then(|v| v.parse().ok()).unwrap_or(0);
+    session.insert("visits", (visits + 1).to_string());
+    ctx.res.send_body((visits + 1).to_string().into())
+  }));
+  app.get("/login", Handle::main(|ctx| {
+    Session::from_request(ctx.req).unwrap().rotate();
+    ctx.res.send_headers()
+  }));
+  app.get("/logout", Handle::main(|ctx| {
+    Session::from_request(ctx.req).unwrap().destroy();
+    ctx.res.send_headers()
+  }));
+  app
+}
+
+// returns the response body and the session cookie it sets
+fn get(app: &App, path: &str, id: Option<&str>) -> (String, Option<String>) {
+  let cookie = id.map_or(String::new(), |id| format!("Cookie: sid={id}\r\n"));
+  let raw = format!("GET {path} HTTP/1.1\r\nHost: localhost\r\n{cookie}\r\n");
+
+  let mut output = Vec::new();
+  app.respond(Cursor::new(raw.into_bytes()), &mut output);
+  let response = String::from_utf8(output).unwrap();
+
+  let set_cookie = response
+    .lines()
+    .find_map(|line| line.strip_prefix("Set-Cookie: "))
+    .map(String::from);
+  let body = response.split_once("\r\n\r\n").unwrap().1.to_string();
+  (body, set_cookie)
+}
+
+fn cookie_id(set_cookie: &str) -> &str {
+  set_cookie.split(';').next().unwrap().strip_prefix("sid=").unwrap()
+}
+
+fn record(age: Duration, values: &[(&str, &str)]) -> Record {
+  let time = now() - age;
+  Record {
+    values: values.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+    created: time,
+    accessed: time,
+    expires: None
+  }
+}
+
+mod middleware_test {
+  use super::*;
+
+  #[test]
+  fn lazy_creation_test() {
+    let store = Rc::new(MemoryStore::new());
+    let app = app(Sessions::new(store.clone()));
+
+    let (_, set_cookie) = get(&app, "/anonymous", None);
+    assert_eq!(set_cookie, None);
+    assert!(store.is_empty());
+  }
+
+  #[test]
+  fn session_test() {
+    let store = Rc::new(MemoryStore::new());
+    let app = app(Sessions::new(store.clone()));
+
+    let (body, set_cookie) = get(&app, "/visit", None);
+    let set_cookie = set_cookie.unwrap();
+    let id = cookie_id(&set_cookie);
+    assert_eq!(body, "1");
+    assert!(is_valid_id(id));
+    assert_eq!(&set_cookie[id.len() + 4..], "; Max-Age=86400; Path=/; HttpOnly; SameSite=Lax");
+    assert_eq!(store.len(), 1);
+
+    // the cookie is only sent when the ID changes
+    let (body, set_cookie) = get(&app, "/visit", Some(id));
+    assert_eq!(body, "2");
+    assert_eq!(set_cookie, None);
+    assert_eq!(store.load(id).unwrap().unwrap().values["visits"], "2");
+  }
+
+  #[test]
+  fn unknown_id_test() {
+    let store = Rc::new(MemoryStore::new());
+    let app = app(Sessions::new(store.clone()));
+
+    for id in ["0".repeat(64), "unknown".to_string(), "../../etc/passwd".to_string()] {
+      let (body, set_cookie) = get(&app, "/visit", Some(&id));
+      assert_eq!(body, "1");
+      assert_ne!(cookie_id(&set_cookie.unwrap()), id);
+    }
+  }
+
+  #[test]
+  fn rotation_test() {
+    let store = Rc::new(MemoryStore::new());
+    let app = app(Sessions::new(store.clone()));
+
+    let (_, set_cookie) = get(&app, "/visit", None);
+    let set_cookie = set_cookie.unwrap();
+    let id = cookie_id(&set_cookie);
+
+    let (_, rotated) = get(&app, "/login", Some(id));
+    let rotated = rotated.unwrap();
+    let rotated_id = cookie_id(&rotated);
+    assert_ne!(rotated_id, id);
+    assert!(store.load(id).unwrap().is_none());
+
+    // the values move to the new ID
+    let (body, _) = get(&app, "/visit", Some(rotated_id));
+    assert_eq!(body, "2");
+  }
+
+  #[test]
+  fn destroy_test() {
+    let store = Rc::new(MemoryStore::new());
+    let app = app(Sessions::new(store.clone()));
+
+    let (_, set_cookie) = get(&app, "/visit", None);
+    let set_cookie = set_cookie.unwrap();
+
+    let (_, removal) = get(&app, "/logout", Some(cookie_id(&set_cookie)));
+    assert_eq!(removal.unwrap(), "sid=; Expires=Thu, 01 Jan 1970 00:00:00 GMT; Max-Age=0; Path=/");
+    assert!(store.is_empty());
+  }
+
+  #[test]
+  fn idle_expiry_test() {
+    let store = Rc::new(MemoryStore::new());
+    let app = app(Sessions::new(store.clone()).with_idle_timeout(Some(Duration::from_secs(60))));
+
+    let id = random::hex(ID_LENGTH);
+    store.save(&id, &record(Duration::from_secs(120), &[("visits", "5")])).unwrap();
+
+    let (body, set_cookie) = get(&app, "/visit", Some(&id));
+    assert_eq!(body, "1");
+    assert!(set_cookie.is_some());
+    assert!(store.load(&id).unwrap().is_none());
+  }
+
+  #[test]
+  fn absolute_expiry_test() {
+    let store = Rc::new(MemoryStore::new());
+    let sessions = Sessions::new(store.clone())
+      .with_idle_timeout(None)
+      .with_absolute_timeout(Some(Duration::from_secs(3600)));
+    let app = app(sessions);
+
+    let id = random::hex(ID_LENGTH);
+    let mut recent = record(Duration::from_secs(7200), &[("visits", "5")]);
+    recent.accessed = now();
+    store.save(&id, &recent).unwrap();
+
+    let (body, _) = get(&app, "/visit", Some(&id));
+    assert_eq!(body, "1");
+  }
+
+  #[test]
+  fn idle_timeout_touches_session_test() {
+    let store = Rc::new(MemoryStore::new());
+    let app = app(Sessions::new(store.clone()));
+
+    let id = random::hex(ID_LENGTH);
+    store.save(&id, &record(Duration::from_secs(60), &[])).unwrap();
+
+    get(&app, "/anonymous", Some(&id));
+    let record = store.load(&id).unwrap().unwrap();
+    assert!(record.accessed > record.created);
+    assert_eq!(record.expires, Some(record.accessed + Duration::from_secs(30 * 60)));
+  }
+}
+
+mod store_test {
+  use std::{fs, path::PathBuf};
+  use super::*;
+
+  fn directory() -> PathBuf {
+    std::env::temp_dir().join(format!("webserver-sessions-{}", random::hex(8)))
+  }
+
+  #[test]
+  fn memory_purge_test() {
+    let store = MemoryStore::new();
+    let mut expired = record(Duration::from_secs(60), &[]);
+    expired.expires = Some(now() - Duration::from_secs(1));
+
+    store.save(&random::hex(ID_LENGTH), &expired).unwrap();
+    store.save(&random::hex(ID_LENGTH), &record(Duration::ZERO, &[])).unwrap();
+    assert_eq!(store.len(), 1);
+  }
+
+  #[test]
+  fn file_round_trip_test() {
+    let directory = directory();
+    let store = FileStore::new(&directory).unwrap();
+    let id = random::hex(ID_LENGTH);
+
+    let mut record = record(Duration::from_secs(60), &[("user", "a=b%c\r\nd"), ("empty", ""), ("é=", "ü")]);
+    record.expires = Some(now() + Duration::from_secs(60));
+
+    assert!(store.load(&id).unwrap().is_none());
+    store.save(&id, &record).unwrap();
+    assert_eq!(store.load(&id).unwrap(), Some(record));
+
+    store.remove(&id).unwrap();
+    store.remove(&id).unwrap();
+    assert!(store.load(&id).unwrap().is_none());
+    fs::remove_dir_all(directory).unwrap();
+  }
+
+  #[test]
+  fn file_invalid_id_test() {
+    let directory = directory();
+    let store = FileStore::new(&directory).unwrap();
+
+    assert!(store.save("../escape", &record(Duration::ZERO, &[])).is_err());
+    assert!(!directory.join("../escape").exists());
+    assert!(store.load("../escape").unwrap().is_none());
+    fs::remove_dir_all(directory).unwrap();
+  }
+
+  #[test]
+  fn file_purge_test() {
+    let directory = directory();
+    let store = FileStore::new(&directory).unwrap();
+    let (expired_id, valid_id) = (random::hex(ID_LENGTH), random::hex(ID_LENGTH));
+
+    let mut expired = record(Duration::from_secs(60), &[]);
+    expired.expires = Some(now() - Duration::from_secs(1));
+    store.save(&expired_id, &expired).unwrap();
+    store.save(&valid_id, &record(Duration::ZERO, &[])).unwrap();
+    fs::write(directory.join("unrelated"), "").unwrap();
+
+    store.purge().unwrap();
+    assert!(store.load(&expired_id).unwrap().is_none());
+    assert!(store.load(&valid_id).unwrap().is_some());
+    assert!(directory.join("unrelated").exists());
+    fs::remove_dir_all(directory).unwrap();
+  }
+}
@@ -1,99 +1,207 @@
-use std::{error::Error as StdError, io::{BufRead, BufReader}, net::TcpStream};
-use crate::request::{Location, Method, Request};
+use std::{error::Error as StdError, io::{BufReader, Cursor, Read}};
+use tracing::trace;
+
+use crate::{config::ServerConfig, header::Headers, parser::{HeaderRange, Parser, StartLine, Status}, protocol, request::{Location, Method, Request}};
 
 pub mod error;
+mod chunked;
 use error::Error as ParseError;
+use chunked::ChunkedReader;
 type AnyError = Box<dyn StdError>;
 
-const BUFFER_SIZE: usize = 4096;
+const READ_SIZE: usize = 4096;
 
-fn parse_location(buf: &Vec<u8>) -> Result<Location, AnyError> {
-  let mut location = Location(Method::Get, String::new());
+#[cfg(test)]
+mod stream_test;
 
-  let str = String::from_utf8(buf.clone())?;
-  let str: Vec<&str> = str.split(' ').collect();
+enum Framing {
+  Empty,
+  Length(usize),
+  Chunked
+}
 
-  if str.len() != 3 {
-    return Err(Box::new(ParseError::InvalidLocationFormat));
+fn parse_method(method: &[u8], config: &ServerConfig) -> Result<Method, ParseError> {
+  // methods are case-sensitive, see RFC 9110, section 9.1
+  let normalized = if config.lenient_parsing {
+    method.to_ascii_uppercase()
+  } else {
+    method.to_vec()
+  };
+
+  match &normalized[..] {
+    b"GET" => Ok(Method::Get),
+    b"POST" => Ok(Method::Post),
+    b"OPTIONS" => Ok(Method::Options),
+    _ => Err(ParseError::UnsupportedMethod(String::from_utf8_lossy(method).to_string()))
   }
+}
 
-  match str[0].to_lowercase().as_str() {
-    "get" => location.0 = Method::Get,
-    "post" => location.0 = Method::Post,
-    method => {
-      return Err(Box::new(ParseError::UnsupportedMethod(method.to_string())))
-    }
+fn parse_location(buf: &[u8], start_line: &StartLine, config: &ServerConfig) -> Result<Location, AnyError> {
+  let StartLine::Request { method, target, version } = start_line else {
+    return Err(Box::new(ParseError::InvalidLocationFormat))
+  };
+
+  let method = parse_method(&buf[method.clone()], config)?;
+
+  // the version is case-sensitive, see RFC 9112, section 2.3
+  let version = &buf[version.clone()];
+  let is_supported = match config.lenient_parsing {
+    true => version.eq_ignore_ascii_case(protocol::HTTP_PROTOCOL.as_bytes()),
+    false => version == protocol::HTTP_PROTOCOL.as_bytes()
+  };
+  if !is_supported {
+    let version = String::from_utf8_lossy(version).to_string();
+    return Err(Box::new(ParseError::UnsupportedProtocol(version)));
   }
-  
-  let protocol = str[2].trim().to_lowercase();
-  if protocol != "http/1.1" {
-    return Err(Box::new(ParseError::UnsupportedProtocol(protocol)));
+
+  let uri = String::from_utf8(buf[target.clone()].to_vec())?;
+  Ok(Location(method, uri))
+}
+
+fn parse_header(buf: &[u8], header: &HeaderRange) -> Result<(String, String), AnyError> {
+  let key = String::from_utf8(header.name(buf).to_vec())?;
+  let mut value = String::from_utf8(header.value(buf).to_vec())?;
+
+  if header.folded {
+    // folded line breaks are replaced by a single space
+    value = value
+      .split("\n")
+      .map(|line| line.trim_matches([' ', '\t', '\r']))
+      .collect::<Vec<_>>()
+      .join(" ");
   }
 
-  location.1 = str[1].to_string();
-  Ok(location)
+  // values are case-sensitive unless their field says otherwise
+  Ok((key.to_lowercase(), value))
 }
 
-fn parse_header(buf: &Vec<u8>) -> Result<(String, String), Box<dyn StdError>> {
-  let str = String::from_utf8(buf.clone())?;
-  let str = str.split_once(':');
+fn is_digits(value: &str) -> bool {
+  !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit())
+}
 
-  if let Some((key, value)) = str {
-    Ok((key.to_lowercase(), value.trim().to_lowercase()))
-  } else {
-    Err(Box::new(ParseError::InvalidHeaderEntryFormat))
-  }
+fn list_values(values: &[String]) -> Vec<&str> {
+  values
+    .iter()
+    .flat_map(|v| v.split(','))
+    .map(|v| v.trim_matches([' ', '\t']))
+    .filter(|v| !v.is_empty())
+    .collect()
 }
 
-pub fn parse_stream(stream: TcpStream) -> Result<Request, AnyError> {
-  let mut reader = BufReader::new(stream);
-  let mut request = Request::new(None);
+// determines the body length as per RFC 9112, section 6.3
+fn parse_framing(headers: &mut Headers, config: &ServerConfig) -> Result<Framing, ParseError> {
+  if headers.get_all("transfer-encoding").is_some() {
+    if headers.get_all("content-length").is_some() {
+      if !config.lenient_parsing {
+        return Err(ParseError::ConflictingFraming)
+      }
 
-  let mut buf: Vec<u8> = Vec::with_capacity(BUFFER_SIZE);
-  let mut line = 0;
-  let mut header_sizes = 0;
-  loop {
-    let res = {
-      reader.read_until(0xA, &mut buf)?
-    };
+      // transfer-encoding overrides content-length
+      headers.remove("content-length");
+    }
 
-    if header_sizes + res > BUFFER_SIZE {
-      return Err(Box::new(ParseError::HeaderTooLong(BUFFER_SIZE)))
+    let codings = list_values(headers.get_all("transfer-encoding").unwrap());
+    let is_chunked = |coding: &&str| coding.eq_ignore_ascii_case("chunked");
+    let chunked = codings.iter().filter(|c| is_chunked(c)).count();
+    if chunked != 1 || !codings.last().is_some_and(is_chunked) {
+      return Err(ParseError::InvalidTransferEncoding)
     }
 
-    header_sizes += res;
-    line += 1;
+    if codings.len() > 1 {
+      return Err(ParseError::UnsupportedTransferEncoding(codings[0].to_string()))
+    }
+
+    return Ok(Framing::Chunked)
+  }
+
+  let Some(lengths) = headers.get_all("content-length") else {
+    return Ok(Framing::Empty)
+  };
+
+  let lengths = list_values(lengths);
+  if lengths.is_empty() || !lengths.iter().all(|v| is_digits(v)) {
+    return Err(ParseError::InvalidContentLength)
+  }
+
+  if lengths.len() > 1 && (!config.lenient_parsing || lengths.iter().any(|v| *v != lengths[0])) {
+    return Err(ParseError::ConflictingContentLength)
+  }
+
+  lengths[0]
+    .parse()
+    .map(Framing::Length)
+    .map_err(|_| ParseError::InvalidContentLength)
+}
+
+// reads the request head into `buf`, returns the head length
+fn read_head(reader: &mut impl Read, parser: &mut Parser, buf: &mut Vec<u8>) -> Result<usize, AnyError> {
+  loop {
+    let filled = buf.len();
+    buf.resize(filled + READ_SIZE, 0);
+    let res = reader.read(&mut buf[filled..])?;
+    buf.truncate(filled + res);
 
     if res == 0 {
-      // header reading stops here...
-      // caused by EOF
-      break;
+      return Ok(parser.eof(buf)?)
     }
 
-    if 
-      (res == 1 && buf[0] == 0xA) ||
-      (res == 2 && buf[0] == 0xD && buf[1] == 0xA) 
-    {
-      // header reading stops here...
-      // caused by incoming payload stream
-      request.set_body(Some(reader));
-      break;
+    if let Status::Complete(length) = parser.parse(buf)? {
+      return Ok(length)
     }
+  }
+}
 
-    if line == 1 {
-      request.set_location(parse_location(&buf)?);
-      buf.clear();
-      continue;
-    }
+pub fn parse_stream<R: Read + 'static>(stream: R, config: &ServerConfig) -> Result<Request, AnyError> {
+  let mut reader = stream;
+  let mut request = Request::new(None);
+  request.set_body_limit(config.max_parsed_body_size);
+  request.set_max_body_size(config.max_body_size);
+
+  let mut parser = Parser::request(config);
+  let mut buf: Vec<u8> = Vec::with_capacity(READ_SIZE);
+  let length = read_head(&mut reader, &mut parser, &mut buf)?;
 
-  
-    let (key, value) = parse_header(&buf)?;
+  let Some(start_line) = parser.start_line() else {
+    return Err(Box::new(ParseError::EmptyRequest))
+  };
+  request.set_location(parse_location(&buf, start_line, config)?);
+  trace!(head = length, fields = parser.headers().len(), "Request head read");
+
+  for header in parser.headers() {
+    let (key, value) = parse_header(&buf, header)?;
     request.mut_headers().append(key, value);
-    buf.clear();
   }
 
-  if request.location().1 == "" {
-    return Err(Box::new(ParseError::EmptyRequest))
+  let hosts = request.headers().get_all("host").map_or(0, |v| v.len());
+  if hosts > 1 || (hosts == 0 && !config.lenient_parsing) {
+    return Err(Box::new(ParseError::InvalidHost))
   }
-  return Ok(request)
+
+  // bytes read past the head belong to the body
+  buf.drain(..length);
+  let reader = BufReader::new(Cursor::new(buf).chain(reader));
+
+  match parse_framing(request.mut_headers(), config)? {
+    Framing::Empty => {},
+    Framing::Length(length) => {
+      if length > config.max_body_size {
+        return Err(Box::new(ParseError::BodyTooLarge(config.max_body_size)))
+      }
+
+      trace!(length, "Request body framed by Content-Length");
+      request.set_body(Some(Box::new(reader.take(length as u64))));
+    },
+    Framing::Chunked => {
+      trace!("Request body chunked");
+      let body = ChunkedReader::new(
+        reader,
+        config.max_body_size,
+        config.max_header_bytes,
+        config.lenient_parsing
+      );
+      request.set_body(Some(Box::new(BufReader::new(body))));
+    }
+  }
+
+  Ok(request)
 }
\ No newline at end of file
@@ -0,0 +1,160 @@
+use std::io::{self, BufRead, Read};
+
+use super::error::Error as ParseError;
+
+// generous limit for a chunk size line, extensions included
+const CHUNK_LINE_LIMIT: usize = 4096;
+
+#[derive(Debug, PartialEq)]
+enum State {
+  Size,
+  Data,
+  DataEnd,
+  Done
+}
+
+/// Decodes a body sent with `Transfer-Encoding: chunked` (RFC 9112, section 7.1).
+///
+/// Chunk extensions and trailer fields are read and discarded.
+pub struct ChunkedReader<R> {
+  inner: R,
+  state: State,
+  remaining: usize,
+  total: usize,
+  limit: usize,
+  trailer_limit: usize,
+  lenient: bool
+}
+
+fn invalid(err: ParseError) -> io::Error {
+  io::Error::new(io::ErrorKind::InvalidData, err)
+}
+
+impl<R: BufRead> ChunkedReader<R> {
+  pub fn new(inner: R, limit: usize, trailer_limit: usize, lenient: bool) -> ChunkedReader<R> {
+    ChunkedReader {
+      inner,
+      state: State::Size,
+      remaining: 0,
+      total: 0,
+      limit,
+      trailer_limit,
+      lenient
+    }
+  }
+
+  // reads a line and strips its line ending, EOF before line feed is an error
+  fn read_line(&mut self, limit: usize) -> io::Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    let res = (&mut self.inner)
+      .take(limit as u64 + 1)
+      .read_until(0xA, &mut buf)?;
+
+    if res > limit {
+      return Err(invalid(ParseError::InvalidChunk))
+    }
+
+    if res == 0 || buf.last() != Some(&0xA) {
+      return Err(io::Error::new(io::ErrorKind::UnexpectedEof, ParseError::InvalidChunk))
+    }
+
+    buf.pop();
+    if buf.last() == Some(&0xD) {
+      buf.pop();
+    } else if !self.lenient {
+      return Err(invalid(ParseError::BareLineFeed))
+    }
+
+    if buf.contains(&0xD) {
+      return Err(invalid(ParseError::BareCarriageReturn))
+    }
+
+    Ok(buf)
+  }
+
+  fn read_size(&mut self) -> io::Result<usize> {
+    let line = self.read_line(CHUNK_LINE_LIMIT)?;
+    let size = match line.iter().position(|b| *b == b';') {
+      Some(index) => &line[..index],
+      None => &line[..]
+    };
+
+    // chunk-ext may be preceded by bad whitespace, the size itself may not
+    let size = match size.iter().rposition(|b| *b != b' ' && *b != b'\t') {
+      Some(index) => &size[..=index],
+      None => &[]
+    };
+
+    if size.is_empty() || !size.iter().all(|b| b.is_ascii_hexdigit()) {
+      return Err(invalid(ParseError::InvalidChunk))
+    }
+
+    // cannot fail, the bytes are all ascii hex digits
+    let size = std::str::from_utf8(size).unwrap();
+    usize::from_str_radix(size, 16).map_err(|_| invalid(ParseError::InvalidChunk))
+  }
+
+  fn read_trailers(&mut self) -> io::Result<()> {
+    let mut size = 0;
+    loop {
+      let line = self.read_line(self.trailer_limit)?;
+      if line.is_empty() {
+        return Ok(())
+      }
+
+      size += line.len();
+      if size > self.trailer_limit {
+        return Err(invalid(ParseError::HeadersTooLarge(self.trailer_limit)))
+      }
+    }
+  }
+}
+
+impl<R: BufRead> Read for ChunkedReader<R> {
+  fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+    loop {
+      match self.state {
+        State::Done => return Ok(0),
+        State::Size => {
+          let size = self.read_size()?;
+          if size == 0 {
+            self.read_trailers()?;
+            self.state = State::Done;
+            continue
+          }
+
+          self.total = self.total
+            .checked_add(size)
+            .filter(|total| *total <= self.limit)
+            .ok_or_else(|| invalid(ParseError::BodyTooLarge(self.limit)))?;
+
+          self.remaining = size;
+          self.state = State::Data;
+        },
+        State::Data => {
+          if out.is_empty() {
+            return Ok(0)
+          }
+
+          let max = out.len().min(self.remaining);
+          let res = self.inner.read(&mut out[..max])?;
+          if res == 0 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, ParseError::InvalidChunk))
+          }
+
+          self.remaining -= res;
+          if self.remaining == 0 {
+            self.state = State::DataEnd;
+          }
+          return Ok(res)
+        },
+        State::DataEnd => {
+          if !self.read_line(2)?.is_empty() {
+            return Err(invalid(ParseError::InvalidChunk))
+          }
+          self.state = State::Size;
+        }
+      }
+    }
+  }
+}
@@ -1,22 +1,114 @@
+use crate::protocol::StatusCode;
+
 #[derive(Debug)]
 pub enum Error {
+  RequestLineTooLong(usize),
+  URITooLong(usize),
   HeaderTooLong(usize),
+  HeadersTooLarge(usize),
+  TooManyHeaders(usize),
+  BodyTooLarge(usize),
   InvalidLocationFormat,
   InvalidHeaderEntryFormat,
+  InvalidHeaderName(String),
+  InvalidHeaderValue(String),
+  InvalidMethod(String),
+  InvalidContentLength,
+  InvalidTransferEncoding,
+  InvalidChunk,
+  InvalidHost,
+  BareLineFeed,
+  BareCarriageReturn,
+  WhitespaceBeforeColon,
+  ObsoleteLineFolding,
+  ConflictingFraming,
+  ConflictingContentLength,
+  IncompleteRequest,
   EmptyRequest,
   UnsupportedMethod(String),
-  UnsupportedProtocol(String)
+  UnsupportedProtocol(String),
+  UnsupportedTransferEncoding(String)
+}
+
+impl Error {
+  /// Status code the server should answer with when a request fails to parse.
+  pub fn status(&self) -> StatusCode {
+    match self {
+      Error::RequestLineTooLong(_) |
+      Error::URITooLong(_) => StatusCode::URITooLong,
+      Error::HeaderTooLong(_) |
+      Error::HeadersTooLarge(_) |
+      Error::TooManyHeaders(_) => StatusCode::RequestHeaderFieldsTooLarge,
+      Error::BodyTooLarge(_) => StatusCode::ContentTooLarge,
+      Error::UnsupportedMethod(_) |
+      Error::UnsupportedTransferEncoding(_) => StatusCode::NotImplemented,
+      Error::UnsupportedProtocol(_) => StatusCode::HTTPVersionNotSupported,
+      _ => StatusCode::BadRequest
+    }
+  }
+
+  /// The name of the variant, e.g. to label metrics with.
+  pub fn name(&self) -> &'static str {
+    match self {
+      Error::RequestLineTooLong(_) => "RequestLineTooLong",
+      Error::URITooLong(_) => "URITooLong",
+      Error::HeaderTooLong(_) => "HeaderTooLong",
+      Error::HeadersTooLarge(_) => "HeadersTooLarge",
+      Error::TooManyHeaders(_) => "TooManyHeaders",
+      Error::BodyTooLarge(_) => "BodyTooLarge",
+      Error::InvalidLocationFormat => "InvalidLocationFormat",
+      Error::InvalidHeaderEntryFormat => "InvalidHeaderEntryFormat",
+      Error::InvalidHeaderName(_) => "InvalidHeaderName",
+      Error::InvalidHeaderValue(_) => "InvalidHeaderValue",
+      Error::InvalidMethod(_) => "InvalidMethod",
+      Error::InvalidContentLength => "InvalidContentLength",
+      Error::InvalidTransferEncoding => "InvalidTransferEncoding",
+      Error::InvalidChunk => "InvalidChunk",
+      Error::InvalidHost => "InvalidHost",
+      Error::BareLineFeed => "BareLineFeed",
+      Error::BareCarriageReturn => "BareCarriageReturn",
+      Error::WhitespaceBeforeColon => "WhitespaceBeforeColon",
+      Error::ObsoleteLineFolding => "ObsoleteLineFolding",
+      Error::ConflictingFraming => "ConflictingFraming",
+      Error::ConflictingContentLength => "ConflictingContentLength",
+      Error::IncompleteRequest => "IncompleteRequest",
+      Error::EmptyRequest => "EmptyRequest",
+      Error::UnsupportedMethod(_) => "UnsupportedMethod",
+      Error::UnsupportedProtocol(_) => "UnsupportedProtocol",
+      Error::UnsupportedTransferEncoding(_) => "UnsupportedTransferEncoding"
+    }
+  }
 }
 
 impl std::fmt::Display for Error {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
       match self {
+        Error::RequestLineTooLong(size) => write!(f, "Request line is too long, exceeded size limit: {size}"),
+        Error::URITooLong(size) => write!(f, "URI is too long, exceeded size limit: {size}"),
         Error::HeaderTooLong(size) => write!(f, "Header is too long, exceeded size limit: {size}"),
+        Error::HeadersTooLarge(size) => write!(f, "Headers are too large, exceeded size limit: {size}"),
+        Error::TooManyHeaders(count) => write!(f, "Too many headers, exceeded count limit: {count}"),
+        Error::BodyTooLarge(size) => write!(f, "Body is too large, exceeded size limit: {size}"),
         Error::InvalidLocationFormat => write!(f, "Invalid location format"),
         Error::InvalidHeaderEntryFormat => write!(f, "Invalid header entry format"),
+        Error::InvalidHeaderName(name) => write!(f, "Invalid header name: {name}"),
+        Error::InvalidHeaderValue(name) => write!(f, "Invalid header value for: {name}"),
+        Error::InvalidMethod(method) => write!(f, "Invalid request method token: {method}"),
+        Error::InvalidContentLength => write!(f, "Invalid content length"),
+        Error::InvalidTransferEncoding => write!(f, "Invalid transfer encoding"),
+        Error::InvalidChunk => write!(f, "Invalid chunked body"),
+        Error::InvalidHost => write!(f, "Request must contain exactly one host header"),
+        Error::BareLineFeed => write!(f, "Line must be terminated by CRLF"),
+        Error::BareCarriageReturn => write!(f, "Bare carriage return is not allowed"),
+        Error::WhitespaceBeforeColon => write!(f, "Whitespace between header name and colon is not allowed"),
+        Error::ObsoleteLineFolding => write!(f, "Obsolete line folding is not allowed"),
+        Error::ConflictingFraming => write!(f, "Content-Length and Transfer-Encoding must not be sent together"),
+        Error::ConflictingContentLength => write!(f, "Conflicting content length"),
+        Error::IncompleteRequest => write!(f, "Incomplete request"),
         Error::EmptyRequest => write!(f, "Empty request"),
         Error::UnsupportedMethod(method) => write!(f, "Invalid request method: {method}"),
-        Error::UnsupportedProtocol(protocol) => write!(f, "Invalid request protocol: {protocol}")
+        Error::UnsupportedProtocol(protocol) => write!(f, "Invalid request protocol: {protocol}"),
+        Error::UnsupportedTransferEncoding(coding) => write!(f, "Unsupported transfer encoding: {coding}")
       }
   }
 }
@@ -0,0 +1,385 @@
+use std::io::Read;
+
+use crate::{config::ServerConfig, protocol::StatusCode, request::{Method, Request}};
+use super::{error::Error as ParseError, parse_stream};
+
+fn parse(raw: &'static str, config: &ServerConfig) -> Result<Request, Box<dyn std::error::Error>> {
+  parse_stream(raw.as_bytes(), config)
+}
+
+fn parse_error(raw: &'static str, config: &ServerConfig) -> ParseError {
+  match parse(raw, config) {
+    Ok(_) => panic!("Request should not be parsed: {raw:?}"),
+    Err(err) => *err.downcast::<ParseError>().expect("Error should be a parse error")
+  }
+}
+
+mod limit_test {
+  use super::*;
+
+  #[test]
+  fn simple_request_test() {
+    let mut req = parse(
+      "POST /a/b HTTP/1.1\r\nHost: localhost\r\nContent-Length: 5\r\n\r\nhello, world",
+      &ServerConfig::default()
+    ).unwrap();
+
+    assert_eq!(req.location().0, Method::Post);
+    assert_eq!(req.location().1, "/a/b");
+    assert_eq!(req.headers().get("host").unwrap(), "localhost");
+
+    let mut body = String::new();
+    req.body().as_mut().unwrap().read_to_string(&mut body).unwrap();
+    assert_eq!(body, "hello");
+  }
+
+  #[test]
+  fn header_value_case_test() {
+    let req = parse(
+      "GET / HTTP/1.1\r\nHost: a\r\nCookie: SID=AbC; Theme=Dark\r\n\r\n",
+      &ServerConfig::default()
+    ).unwrap();
+
+    // values keep their case, names are looked up case-insensitively
+    assert_eq!(req.headers().get("Cookie").unwrap(), "SID=AbC; Theme=Dark");
+    assert_eq!(req.cookies().get("SID"), Some("AbC"));
+  }
+
+  #[test]
+  fn request_line_too_long_test() {
+    let config = ServerConfig { max_request_line: 16, ..Default::default() };
+    let err = parse_error("GET /a/very/long/path HTTP/1.1\r\n\r\n", &config);
+
+    assert!(matches!(err, ParseError::RequestLineTooLong(16)));
+    assert_eq!(err.status(), StatusCode::URITooLong);
+  }
+
+  #[test]
+  fn uri_too_long_test() {
+    let config = ServerConfig { max_uri_length: 4, ..Default::default() };
+    let err = parse_error("GET /a/b/c HTTP/1.1\r\n\r\n", &config);
+
+    assert!(matches!(err, ParseError::URITooLong(4)));
+    assert_eq!(err.status(), StatusCode::URITooLong);
+  }
+
+  #[test]
+  fn header_too_long_test() {
+    let config = ServerConfig { max_header_size: 16, ..Default::default() };
+    let err = parse_error("GET / HTTP/1.1\r\nCookie: a-rather-long-cookie\r\n\r\n", &config);
+
+    assert!(matches!(err, ParseError::HeaderTooLong(16)));
+    assert_eq!(err.status(), StatusCode::RequestHeaderFieldsTooLarge);
+  }
+
+  #[test]
+  fn headers_too_large_test() {
+    let config = ServerConfig { max_header_bytes: 32, ..Default::default() };
+    let err = parse_error("GET / HTTP/1.1\r\nA: 0123456789\r\nB: 0123456789\r\nC: 0123456789\r\n\r\n", &config);
+
+    assert!(matches!(err, ParseError::HeadersTooLarge(32)));
+    assert_eq!(err.status(), StatusCode::RequestHeaderFieldsTooLarge);
+  }
+
+  #[test]
+  fn too_many_headers_test() {
+    let config = ServerConfig { max_header_count: 2, ..Default::default() };
+    let err = parse_error("GET / HTTP/1.1\r\nA: 1\r\nB: 2\r\nC: 3\r\n\r\n", &config);
+
+    assert!(matches!(err, ParseError::TooManyHeaders(2)));
+    assert_eq!(err.status(), StatusCode::RequestHeaderFieldsTooLarge);
+  }
+
+  #[test]
+  fn body_too_large_test() {
+    let config = ServerConfig { max_body_size: 4, ..Default::default() };
+    let err = parse_error("POST / HTTP/1.1\r\nHost: a\r\nContent-Length: 5\r\n\r\nhello", &config);
+
+    assert!(matches!(err, ParseError::BodyTooLarge(4)));
+    assert_eq!(err.status(), StatusCode::ContentTooLarge);
+  }
+
+  #[test]
+  fn large_header_within_default_limits_test() {
+    let raw = format!("GET / HTTP/1.1\r\nHost: a\r\nCookie: {}\r\n\r\n", "a".repeat(8 * 1024));
+    let raw: &'static str = Box::leak(raw.into_boxed_str());
+    let req = parse(raw, &ServerConfig::default()).unwrap();
+
+    assert_eq!(req.headers().get("cookie").unwrap().len(), 8 * 1024);
+  }
+}
+
+mod framing_test {
+  use super::*;
+
+  fn read_body(req: &mut Request) -> Result<String, std::io::Error> {
+    let mut body = String::new();
+    if let Some(reader) = req.body().as_mut() {
+      reader.read_to_string(&mut body)?;
+    }
+    Ok(body)
+  }
+
+  fn lenient() -> ServerConfig {
+    ServerConfig { lenient_parsing: true, ..Default::default() }
+  }
+
+  #[test]
+  fn chunked_body_test() {
+    let mut req = parse(
+      "POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: chunked\r\n\r\n5;ext=1\r\nhello\r\n7\r\n, world\r\n0\r\nTrailer: a\r\n\r\nGET / HTTP/1.1",
+      &ServerConfig::default()
+    ).unwrap();
+
+    assert_eq!(read_body(&mut req).unwrap(), "hello, world");
+  }
+
+  #[test]
+  fn chunked_body_too_large_test() {
+    let config = ServerConfig { max_body_size: 8, ..Default::default() };
+    let mut req = parse(
+      "POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n7\r\n, world\r\n0\r\n\r\n",
+      &config
+    ).unwrap();
+
+    let err = read_body(&mut req).unwrap_err();
+    let err = err.into_inner().unwrap().downcast::<ParseError>().unwrap();
+    assert!(matches!(*err, ParseError::BodyTooLarge(8)));
+  }
+
+  #[test]
+  fn body_without_framing_is_empty_test() {
+    let mut req = parse("POST / HTTP/1.1\r\nHost: a\r\n\r\nhello", &ServerConfig::default()).unwrap();
+    assert_eq!(read_body(&mut req).unwrap(), "");
+  }
+
+  #[test]
+  fn leading_empty_line_test() {
+    let req = parse("\r\nGET / HTTP/1.1\r\nHost: a\r\n\r\n", &ServerConfig::default()).unwrap();
+    assert_eq!(req.location().1, "/");
+  }
+
+  #[test]
+  fn lenient_line_feed_test() {
+    let req = parse("get / HTTP/1.1\nHost: a\n\n", &lenient()).unwrap();
+    assert_eq!(req.location().0, Method::Get);
+  }
+
+  #[test]
+  fn version_case_test() {
+    let err = parse_error("GET / http/1.1\r\nHost: a\r\n\r\n", &ServerConfig::default());
+    assert_eq!(err.status(), StatusCode::HTTPVersionNotSupported);
+
+    let req = parse("GET / http/1.1\r\nHost: a\r\n\r\n", &lenient()).unwrap();
+    assert_eq!(req.location().1, "/");
+  }
+
+  #[test]
+  fn lenient_line_folding_test() {
+    let req = parse("GET / HTTP/1.1\r\nHost: a\r\nX-Folded: a,\r\n  b\r\n\r\n", &lenient()).unwrap();
+    assert_eq!(req.headers().get("x-folded").unwrap(), "a, b");
+  }
+
+  #[test]
+  fn lenient_duplicate_content_length_test() {
+    let mut req = parse(
+      "POST / HTTP/1.1\r\nHost: a\r\nContent-Length: 5\r\nContent-Length: 5\r\n\r\nhello",
+      &lenient()
+    ).unwrap();
+    assert_eq!(read_body(&mut req).unwrap(), "hello");
+
+    let err = parse_error(
+      "POST / HTTP/1.1\r\nHost: a\r\nContent-Length: 5, 6\r\n\r\nhello",
+      &lenient()
+    );
+    assert!(matches!(err, ParseError::ConflictingContentLength));
+  }
+
+  #[test]
+  fn lenient_transfer_encoding_wins_test() {
+    let mut req = parse(
+      "POST / HTTP/1.1\r\nHost: a\r\nContent-Length: 3\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n0\r\n\r\n",
+      &lenient()
+    ).unwrap();
+
+    assert!(req.headers().get("content-length").is_none());
+    assert_eq!(read_body(&mut req).unwrap(), "hello");
+  }
+}
+
+mod smuggling_test {
+  use super::*;
+
+  // known request smuggling and framing payloads, every entry
+  // must be rejected by the strict parser before reaching a handler
+  const CORPUS: &[(&str, &str)] = &[
+    ("CL.TE", "POST / HTTP/1.1\r\nHost: a\r\nContent-Length: 13\r\nTransfer-Encoding: chunked\r\n\r\n0\r\n\r\nSMUGGLED"),
+    ("TE.CL", "POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: chunked\r\nContent-Length: 3\r\n\r\n8\r\nSMUGGLED\r\n0\r\n\r\n"),
+    ("duplicate content-length", "POST / HTTP/1.1\r\nHost: a\r\nContent-Length: 0\r\nContent-Length: 8\r\n\r\nSMUGGLED"),
+    ("content-length list", "POST / HTTP/1.1\r\nHost: a\r\nContent-Length: 0, 8\r\n\r\nSMUGGLED"),
+    ("signed content-length", "POST / HTTP/1.1\r\nHost: a\r\nContent-Length: +8\r\n\r\nSMUGGLED"),
+    ("hex content-length", "POST / HTTP/1.1\r\nHost: a\r\nContent-Length: 0x8\r\n\r\nSMUGGLED"),
+    ("negative content-length", "POST / HTTP/1.1\r\nHost: a\r\nContent-Length: -1\r\n\r\nSMUGGLED"),
+    ("overflowing content-length", "POST / HTTP/1.1\r\nHost: a\r\nContent-Length: 99999999999999999999999\r\n\r\n"),
+    ("space before colon", "POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding : chunked\r\n\r\n0\r\n\r\n"),
+    ("tab before colon", "POST / HTTP/1.1\r\nHost: a\r\nContent-Length\t: 8\r\n\r\nSMUGGLED"),
+    ("obsolete line folding", "POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding:\r\n chunked\r\n\r\n0\r\n\r\n"),
+    ("bare line feed", "POST / HTTP/1.1\nHost: a\nContent-Length: 8\n\nSMUGGLED"),
+    ("bare line feed in header", "POST / HTTP/1.1\r\nHost: a\nContent-Length: 8\r\n\r\nSMUGGLED"),
+    ("bare carriage return", "POST / HTTP/1.1\r\nHost: a\rContent-Length: 8\r\n\r\nSMUGGLED"),
+    ("chunked not final", "POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: chunked, identity\r\n\r\n0\r\n\r\n"),
+    ("chunked twice", "POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: chunked\r\nTransfer-Encoding: chunked\r\n\r\n0\r\n\r\n"),
+    ("obfuscated chunked", "POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: xchunked\r\n\r\n0\r\n\r\n"),
+    ("unsupported coding", "POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: gzip, chunked\r\n\r\n0\r\n\r\n"),
+    ("invalid header name", "POST / HTTP/1.1\r\nHost: a\r\nContent(Length): 8\r\n\r\nSMUGGLED"),
+    ("empty header name", "POST / HTTP/1.1\r\nHost: a\r\n: 8\r\n\r\nSMUGGLED"),
+    ("control character in value", "POST / HTTP/1.1\r\nHost: a\r\nX-A: a\x00b\r\n\r\n"),
+    ("invalid method", "G(E)T / HTTP/1.1\r\nHost: a\r\n\r\n"),
+    ("lowercase method", "get / HTTP/1.1\r\nHost: a\r\n\r\n"),
+    ("lowercase protocol", "GET / http/1.1\r\nHost: a\r\n\r\n"),
+    ("double space in request line", "GET  / HTTP/1.1\r\nHost: a\r\n\r\n"),
+    ("tab in request line", "GET\t/ HTTP/1.1\r\nHost: a\r\n\r\n"),
+    ("missing host", "GET / HTTP/1.1\r\n\r\n"),
+    ("duplicate host", "GET / HTTP/1.1\r\nHost: a\r\nHost: b\r\n\r\n"),
+    ("unterminated headers", "GET / HTTP/1.1\r\nHost: a\r\n"),
+  ];
+
+  #[test]
+  fn corpus_is_rejected_test() {
+    for (name, payload) in CORPUS {
+      match parse(payload, &ServerConfig::default()) {
+        Ok(_) => panic!("Payload should be rejected: {name}"),
+        Err(err) => assert!(err.downcast_ref::<ParseError>().is_some(), "Unexpected error for {name}: {err}")
+      }
+    }
+  }
+
+  #[test]
+  fn invalid_chunk_is_rejected_test() {
+    let payloads = [
+      "5\r\nhello\r\n-1\r\n\r\n",
+      "5\r\nhelloSMUGGLED\r\n0\r\n\r\n",
+      "0x5\r\nhello\r\n0\r\n\r\n",
+      " 5\r\nhello\r\n0\r\n\r\n",
+      "fffffffffffffffffffff\r\nhello\r\n0\r\n\r\n",
+      "5\nhello\n0\n\n",
+      "5\r\nhello",
+    ];
+
+    for payload in payloads {
+      let raw = format!("POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: chunked\r\n\r\n{payload}");
+      let mut req = parse(Box::leak(raw.into_boxed_str()), &ServerConfig::default()).unwrap();
+
+      let mut body = Vec::new();
+      let res = req.body().as_mut().unwrap().read_to_end(&mut body);
+      assert!(res.is_err(), "Chunked payload should be rejected: {payload:?}");
+    }
+  }
+
+  #[test]
+  fn statuses_test() {
+    let config = ServerConfig::default();
+    let err = parse_error("PUT / HTTP/1.1\r\nHost: a\r\n\r\n", &config);
+    assert_eq!(err.status(), StatusCode::NotImplemented);
+    assert!(parse("OPTIONS * HTTP/1.1\r\nHost: a\r\n\r\n", &config).is_ok());
+
+    let err = parse_error("POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: gzip, chunked\r\n\r\n", &config);
+    assert_eq!(err.status(), StatusCode::NotImplemented);
+
+    let err = parse_error("GET / HTTP/1.0\r\nHost: a\r\n\r\n", &config);
+    assert_eq!(err.status(), StatusCode::HTTPVersionNotSupported);
+
+    let err = parse_error("POST / HTTP/1.1\r\nHost: a\r\nContent-Length: 1\r\nTransfer-Encoding: chunked\r\n\r\n", &config);
+    assert_eq!(err.status(), StatusCode::BadRequest);
+  }
+}
+
+mod property_test {
+  use proptest::prelude::*;
+
+  use super::*;
+
+  fn parse_bytes(raw: Vec<u8>) -> Result<Request, Box<dyn std::error::Error>> {
+    parse_stream(std::io::Cursor::new(raw), &ServerConfig::default())
+  }
+
+  fn drain_body(req: &mut Request) {
+    if let Some(body) = req.body().as_mut() {
+      let _ = body.read_to_end(&mut Vec::new());
+    }
+  }
+
+  fn serialize(req: &Request) -> Vec<u8> {
+    let method = match req.location().0 {
+      Method::Post => "POST",
+      _ => "GET"
+    };
+
+    let mut raw = format!("{method} {} HTTP/1.1\r\n", req.location().1);
+    for (key, values) in req.headers().iter() {
+      for value in values {
+        raw.push_str(&format!("{key}: {value}\r\n"));
+      }
+    }
+    raw.push_str("\r\n");
+    raw.into_bytes()
+  }
+
+  fn sorted_headers(req: &Request) -> Vec<(String, Vec<String>)> {
+    let mut headers: Vec<_> = req.headers()
+      .iter()
+      .map(|(k, v)| (k.clone(), v.clone()))
+      .collect();
+    headers.sort();
+    headers
+  }
+
+  prop_compose! {
+    fn request()(
+      method in prop::sample::select(vec!["GET", "POST"]),
+      target in "/[a-zA-Z0-9/._~%?=&-]{0,24}",
+      headers in prop::collection::vec(("[A-Za-z][A-Za-z0-9-]{0,10}", "[ -~]{0,24}"), 0..8)
+    ) -> Vec<u8> {
+      let mut raw = format!("{method} {target} HTTP/1.1\r\nHost: localhost\r\n");
+      for (key, value) in headers {
+        let key = key.to_lowercase();
+        if ["host", "content-length", "transfer-encoding"].contains(&key.as_str()) {
+          continue
+        }
+        raw.push_str(&format!("{key}: {value}\r\n"));
+      }
+      raw.push_str("\r\n");
+      raw.into_bytes()
+    }
+  }
+
+  proptest! {
+    #[test]
+    fn never_panics_test(raw in prop::collection::vec(any::<u8>(), 0..512)) {
+      if let Ok(mut req) = parse_bytes(raw) {
+        drain_body(&mut req);
+      }
+    }
+
+    #[test]
+    fn never_panics_on_request_like_input_test(
+      headers in r"[A-Za-z0-9:;, \t\r\n-]{0,128}",
+      body in r"[0-9a-fA-F;=\r\n]{0,64}"
+    ) {
+      let raw = format!("POST / HTTP/1.1\r\nHost: a\r\n{headers}\r\n\r\n{body}");
+      if let Ok(mut req) = parse_bytes(raw.into_bytes()) {
+        drain_body(&mut req);
+      }
+    }
+
+    #[test]
+    fn round_trip_test(raw in request()) {
+      let req = parse_bytes(raw).unwrap();
+      let reparsed = parse_bytes(serialize(&req)).unwrap();
+
+      prop_assert_eq!(&req.location().0, &reparsed.location().0);
+      prop_assert_eq!(&req.location().1, &reparsed.location().1);
+      prop_assert_eq!(sorted_headers(&req), sorted_headers(&reparsed));
+    }
+  }
+}